@@ -6,4 +6,15 @@ extern "C" {
     pub fn mlirContextIsMultithreadingEnabled(ctx: MlirContext) -> u8;
     pub fn mlirTypeIsIntegerType(r#type: MlirType) -> u8;
 
+    pub fn mlirPassDestroy(pass: mlir_capi::Pass::MlirPass);
+
+    pub fn mlirTypeIsALLVMArrayType(r#type: MlirType) -> u8;
+    pub fn mlirLLVMArrayTypeGetNumElements(r#type: MlirType) -> u32;
+    pub fn mlirTypeIsALLVMFunctionType(r#type: MlirType) -> u8;
+    pub fn mlirTypeIsALLVMVoidType(r#type: MlirType) -> u8;
+    pub fn mlirAttributeIsALLVMLinkageAttr(attr: MlirAttribute) -> u8;
+    pub fn mlirLLVMLinkageAttrGetLinkage(attr: MlirAttribute) -> u32;
+    pub fn mlirAttributeIsALLVMCConvAttr(attr: MlirAttribute) -> u8;
+    pub fn mlirLLVMCConvAttrGetCallingConv(attr: MlirAttribute) -> u32;
+
 }
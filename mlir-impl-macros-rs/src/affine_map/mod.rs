@@ -0,0 +1,250 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parenthesized, parse2, Token};
+use syn::{Error, Ident, LitInt, Result};
+
+/// An affine expression in MLIR's textual syntax, e.g. `d0 + s0 * 2` or `d1 floordiv 4`.
+enum AffineExprSyntax {
+    Ident(Ident),
+    Const(LitInt),
+    Neg(Box<AffineExprSyntax>),
+    Binary(Box<AffineExprSyntax>, BinaryOp, Box<AffineExprSyntax>),
+}
+
+#[derive(Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    FloorDiv,
+    CeilDiv,
+    Mod,
+}
+
+impl AffineExprSyntax {
+    // expr := term (('+' | '-') term)*
+    fn parse_sum(input: ParseStream) -> Result<Self> {
+        let mut lhs = Self::parse_product(input)?;
+        loop {
+            let op = if input.peek(Token![+]) {
+                input.parse::<Token![+]>()?;
+                BinaryOp::Add
+            } else if input.peek(Token![-]) {
+                input.parse::<Token![-]>()?;
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = Self::parse_product(input)?;
+            lhs = Self::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+    }
+
+    // term := unary (('*' | 'floordiv' | 'ceildiv' | 'mod') unary)*
+    fn parse_product(input: ParseStream) -> Result<Self> {
+        let mut lhs = Self::parse_unary(input)?;
+        loop {
+            let op = if input.peek(Token![*]) {
+                input.parse::<Token![*]>()?;
+                BinaryOp::Mul
+            } else if input.peek(Token![mod]) {
+                input.parse::<Token![mod]>()?;
+                BinaryOp::Mod
+            } else if input.peek(Ident) {
+                let fork = input.fork();
+                let ident = fork.parse::<Ident>()?;
+                let op = if ident == "floordiv" {
+                    BinaryOp::FloorDiv
+                } else if ident == "ceildiv" {
+                    BinaryOp::CeilDiv
+                } else {
+                    return Err(Error::new(ident.span(), "expected an affine operator"));
+                };
+                input.parse::<Ident>()?;
+                op
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = Self::parse_unary(input)?;
+            lhs = Self::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+    }
+
+    // unary := '-' unary | ident | integer | '(' expr ')'
+    fn parse_unary(input: ParseStream) -> Result<Self> {
+        if input.peek(Token![-]) {
+            input.parse::<Token![-]>()?;
+            return Ok(Self::Neg(Box::new(Self::parse_unary(input)?)));
+        }
+        if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let expr = Self::parse_sum(&content)?;
+            if !content.is_empty() {
+                return Err(content.error("unexpected token in affine expression"));
+            }
+            return Ok(expr);
+        }
+        if input.peek(LitInt) {
+            return Ok(Self::Const(input.parse()?));
+        }
+        Ok(Self::Ident(input.parse()?))
+    }
+
+    fn to_tokens(&self, map: &AffineMapSyntax, ctx: &Ident) -> Result<TokenStream> {
+        Ok(match self {
+            Self::Ident(ident) => {
+                if !map.dims.contains(ident) && !map.symbols.contains(ident) {
+                    return Err(Error::new(
+                        ident.span(),
+                        format!("`{}` is neither a dimension nor a symbol of the map", ident),
+                    ));
+                }
+                quote! { #ident }
+            }
+            Self::Const(lit) => {
+                let value = lit.base10_parse::<i64>()?;
+                quote! { ::mlir_rs::affine_expr::AffineExpr::const_expr_get(#ctx, #value) }
+            }
+            Self::Neg(expr) => {
+                if let Self::Const(lit) = expr.as_ref() {
+                    let value = -lit.base10_parse::<i64>()?;
+                    return Ok(
+                        quote! { ::mlir_rs::affine_expr::AffineExpr::const_expr_get(#ctx, #value) },
+                    );
+                }
+                let expr = expr.to_tokens(map, ctx)?;
+                quote! { (-#expr) }
+            }
+            Self::Binary(lhs, op, rhs) => {
+                let lhs = lhs.to_tokens(map, ctx)?;
+                let rhs = rhs.to_tokens(map, ctx)?;
+                match op {
+                    BinaryOp::Add => quote! { (#lhs + #rhs) },
+                    BinaryOp::Sub => quote! { (#lhs - #rhs) },
+                    BinaryOp::Mul => quote! { (#lhs * #rhs) },
+                    BinaryOp::FloorDiv => quote! { (#lhs).floor_div(#rhs) },
+                    BinaryOp::CeilDiv => quote! { (#lhs).ceil_div(#rhs) },
+                    BinaryOp::Mod => quote! { (#lhs).rem(#rhs) },
+                }
+            }
+        })
+    }
+}
+
+impl Parse for AffineExprSyntax {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Self::parse_sum(input)
+    }
+}
+
+/// `ctx, (d0, d1)[s0] -> (d0 + s0, d1 floordiv 4)`, the symbol list is optional.
+struct AffineMapSyntax {
+    ctx: TokenStream,
+    dims: Vec<Ident>,
+    symbols: Vec<Ident>,
+    results: Vec<AffineExprSyntax>,
+}
+
+impl Parse for AffineMapSyntax {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // The context expression extends up to the first top level comma.
+        let mut ctx = TokenStream::new();
+        while !input.peek(Token![,]) {
+            ctx.extend([input.parse::<TokenTree>()?]);
+        }
+        if ctx.is_empty() {
+            return Err(input.error("expected a context expression"));
+        }
+        input.parse::<Token![,]>()?;
+        let content;
+        parenthesized!(content in input);
+        let dims: Vec<Ident> = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect();
+        let mut symbols = vec![];
+        if input.peek(syn::token::Bracket) {
+            let content;
+            bracketed!(content in input);
+            symbols = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .collect();
+        }
+        // The dims and symbols share a single scope of `let` bindings in the expansion.
+        let mut declared: Vec<&Ident> = vec![];
+        for ident in dims.iter().chain(symbols.iter()) {
+            if declared.contains(&ident) {
+                return Err(Error::new(
+                    ident.span(),
+                    format!("`{}` is declared more than once in the map", ident),
+                ));
+            }
+            declared.push(ident);
+        }
+        input.parse::<Token![->]>()?;
+        let content;
+        parenthesized!(content in input);
+        let results = Punctuated::<AffineExprSyntax, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect();
+        Ok(AffineMapSyntax {
+            ctx,
+            dims,
+            symbols,
+            results,
+        })
+    }
+}
+
+fn expand_affine_map(map: AffineMapSyntax) -> Result<TokenStream> {
+    let ctx_expr = &map.ctx;
+    let ctx = Ident::new("__affine_map_ctx", proc_macro2::Span::call_site());
+    let dims = &map.dims;
+    let dim_pos = 0..dims.len();
+    let symbols = &map.symbols;
+    let symbol_pos = 0..symbols.len();
+    let num_dims = dims.len();
+    let num_symbols = symbols.len();
+    let results = map
+        .results
+        .iter()
+        .map(|expr| expr.to_tokens(&map, &ctx))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(quote! {
+        {
+            let #ctx: &::mlir_rs::context::Context = #ctx_expr;
+            #(
+                #[allow(unused_variables)]
+                let #dims = ::mlir_rs::affine_expr::AffineExpr::dim_expr_get(#ctx, #dim_pos);
+            )*
+            #(
+                #[allow(unused_variables)]
+                let #symbols = ::mlir_rs::affine_expr::AffineExpr::symbol_expr_get(#ctx, #symbol_pos);
+            )*
+            ::mlir_rs::affine_map::AffineMap::get(#ctx, #num_dims, #num_symbols, &[#(#results),*])
+        }
+    })
+}
+
+pub fn affine_map(input: TokenStream) -> TokenStream {
+    parse2::<AffineMapSyntax>(input)
+        .and_then(expand_affine_map)
+        .unwrap_or_else(Error::into_compile_error)
+}
+
+#[cfg(test)]
+mod affine_map_test {
+    use super::*;
+
+    #[test]
+    fn duplicate_identifiers() {
+        assert!(parse2::<AffineMapSyntax>(quote! { ctx, (d0, d1)[s0] -> (d0) }).is_ok());
+        assert!(parse2::<AffineMapSyntax>(quote! { ctx, (d0, d0) -> (d0) }).is_err());
+        assert!(parse2::<AffineMapSyntax>(quote! { ctx, (d0)[d0] -> (d0) }).is_err());
+        assert!(parse2::<AffineMapSyntax>(quote! { ctx, (d0)[s0, s0] -> (d0) }).is_err());
+        let error = affine_map(quote! { ctx, (d0, d0) -> (d0) }).to_string();
+        assert!(error.contains("compile_error"));
+    }
+}
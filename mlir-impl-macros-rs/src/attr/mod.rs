@@ -78,3 +78,33 @@ pub fn define_builtin_attrs(input: TokenStream) -> TokenStream {
     let tys_info = BuiltinAttrsInfo {};
     define_builtin_type_attr(input, &tys_info)
 }
+
+/// Attributes of non-builtin dialects, whose kind-checking functions are given explicitly. Such a
+/// function must be `unsafe` and take an `Attr`.
+struct DialectAttrsInfo;
+impl BuilintsInfo for DialectAttrsInfo {
+    type ElemSyn = DialectElementSyntax;
+    fn get_kind_trait(&self, bi: &Self::ElemSyn) -> Ident {
+        bi.to_ident("AttrTrait")
+    }
+    fn get_struct_name(&self, bi: &DialectElementSyntax) -> Ident {
+        bi.to_ident(&format!("{}Attr", bi.name))
+    }
+    fn get_parent(&self, bi: &DialectElementSyntax) -> Ident {
+        bi.to_ident("Attr")
+    }
+    fn get_capi_handle_type(&self, bi: &DialectElementSyntax) -> Ident {
+        bi.to_ident("MlirAttribute")
+    }
+    fn get_is_a_capi_func(&self, bi: &DialectElementSyntax) -> syn::Path {
+        bi.is_a_func.clone()
+    }
+    fn get_ancestors(&self, _bi: &DialectElementSyntax) -> Vec<syn::Ident> {
+        vec![]
+    }
+}
+
+pub fn define_dialect_attrs(input: TokenStream) -> TokenStream {
+    let attrs_info = DialectAttrsInfo {};
+    define_builtin_type_attr(input, &attrs_info)
+}
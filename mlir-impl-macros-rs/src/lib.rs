@@ -1,5 +1,6 @@
 use proc_macro;
 
+mod affine_map;
 mod attr;
 mod r#type;
 mod type_attr;
@@ -11,6 +12,13 @@ pub fn define_builtin_types(types: proc_macro::TokenStream) -> proc_macro::Token
     proc_macro::TokenStream::from(res)
 }
 
+#[proc_macro]
+pub fn define_dialect_types(types: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let types = proc_macro2::TokenStream::from(types);
+    let res = r#type::define_dialect_types(types);
+    proc_macro::TokenStream::from(res)
+}
+
 #[proc_macro]
 pub fn define_float_kind(types: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let types = proc_macro2::TokenStream::from(types);
@@ -24,3 +32,19 @@ pub fn define_builtin_attrs(types: proc_macro::TokenStream) -> proc_macro::Token
     let res = attr::define_builtin_attrs(attrs);
     proc_macro::TokenStream::from(res)
 }
+
+#[proc_macro]
+pub fn define_dialect_attrs(attrs: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let attrs = proc_macro2::TokenStream::from(attrs);
+    let res = attr::define_dialect_attrs(attrs);
+    proc_macro::TokenStream::from(res)
+}
+
+/// Builds an `AffineMap` from MLIR's textual syntax at run time, e.g.
+/// `affine_map!(&ctx, (d0, d1)[s0] -> (d0 + s0, d1 floordiv 4))`.
+#[proc_macro]
+pub fn affine_map(map: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let map = proc_macro2::TokenStream::from(map);
+    let res = affine_map::affine_map(map);
+    proc_macro::TokenStream::from(res)
+}
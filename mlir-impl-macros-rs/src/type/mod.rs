@@ -79,3 +79,34 @@ pub fn define_builtin_types(input: TokenStream) -> TokenStream {
     let tys_info = BuiltinTypesInfo {};
     define_builtin_type_attr(input, &tys_info)
 }
+
+/// Types of non-builtin dialects, for which the C API functions checking the kind of a type
+/// don't follow a common naming scheme, so they are given explicitly. Such a function must be
+/// `unsafe` and take a `Type`.
+struct DialectTypesInfo;
+impl BuilintsInfo for DialectTypesInfo {
+    type ElemSyn = DialectElementSyntax;
+    fn get_kind_trait(&self, bi: &Self::ElemSyn) -> Ident {
+        bi.to_ident("TypeTrait")
+    }
+    fn get_struct_name(&self, bi: &DialectElementSyntax) -> Ident {
+        bi.to_ident(&format!("{}Type", bi.name))
+    }
+    fn get_parent(&self, bi: &DialectElementSyntax) -> Ident {
+        bi.to_ident("Type")
+    }
+    fn get_capi_handle_type(&self, bi: &DialectElementSyntax) -> Ident {
+        bi.to_ident("MlirType")
+    }
+    fn get_is_a_capi_func(&self, bi: &DialectElementSyntax) -> syn::Path {
+        bi.is_a_func.clone()
+    }
+    fn get_ancestors(&self, _bi: &DialectElementSyntax) -> Vec<syn::Ident> {
+        vec![]
+    }
+}
+
+pub fn define_dialect_types(input: TokenStream) -> TokenStream {
+    let tys_info = DialectTypesInfo {};
+    define_builtin_type_attr(input, &tys_info)
+}
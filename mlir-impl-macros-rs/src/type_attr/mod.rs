@@ -2,7 +2,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::{parse2, Token};
+use syn::{parenthesized, parse2, Token};
 use syn::{Ident, Result};
 
 pub trait ElementSyntax {
@@ -27,6 +27,30 @@ impl<T: Parse + Clone> Parse for ElementListSyntax<T> {
     }
 }
 
+/// An element `(Name, is_a_func)` of the types or attributes of a non-builtin dialect.
+#[derive(Clone)]
+pub struct DialectElementSyntax {
+    pub name: Ident,
+    pub is_a_func: syn::Path,
+}
+
+impl ElementSyntax for DialectElementSyntax {
+    fn get_name(&self) -> Ident {
+        self.name.clone()
+    }
+}
+
+impl Parse for DialectElementSyntax {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        let name = content.parse::<syn::Ident>()?;
+        content.parse::<Token![,]>()?;
+        let is_a_func = content.parse::<syn::Path>()?;
+        Ok(DialectElementSyntax { name, is_a_func })
+    }
+}
+
 pub trait BuilintsInfo {
     type ElemSyn: ElementSyntax + Parse + Clone;
     fn parse_input(&self, input: TokenStream) -> Vec<Self::ElemSyn> {
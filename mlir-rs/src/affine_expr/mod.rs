@@ -6,11 +6,14 @@ use crate::type_cast::*;
 
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
+use std::ops::{Add, Mul, Neg, Sub};
 
 use mlir_capi::AffineExpr as MLIR_AffineExpr;
 use mlir_capi::AffineExpr::*;
 use mlir_capi::IR::*;
 
+pub mod tree;
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct AffineExpr<'ctx> {
@@ -90,6 +93,9 @@ impl<'ctx> AffineExpr<'ctx> {
         self.print(&mut printer);
         Ok(())
     }
+    pub fn get_context(self) -> ContextRef<'ctx> {
+        ContextRef::from_handle_same_context(self.get_context_handle(), &self)
+    }
     pub fn dump(self) {
         unsafe {
             MLIR_AffineExpr::FFIVoid_::mlirAffineExprDump(self);
@@ -121,6 +127,12 @@ impl<'ctx> AffineExpr<'ctx> {
     pub fn is_a_dim(self) -> bool {
         to_rbool(unsafe { MLIR_AffineExpr::FFIVal_::mlirAffineExprIsADim(self) })
     }
+    pub fn dim_expr_get_pos(self) -> i64 {
+        unsafe { MLIR_AffineExpr::FFIVal_::mlirAffineDimExprGetPosition(self) }
+    }
+    pub fn is_a_symbol(self) -> bool {
+        to_rbool(unsafe { MLIR_AffineExpr::FFIVal_::mlirAffineExprIsASymbol(self) })
+    }
     pub fn symbol_expr_get(ctx: &'ctx Context, pos: usize) -> Self {
         let handle = unsafe { MLIR_AffineExpr::FFIVal_::mlirAffineSymbolExprGet(ctx, pos as i64) };
         unsafe { Self::from_handle_and_phantom(handle, PhantomData::default()) }
@@ -185,3 +197,112 @@ impl<'ctx> AffineExpr<'ctx> {
         Self::from_handle_same_context(handle, &self)
     }
 }
+
+/// Right hand side operand of the affine expression operators, either another `AffineExpr` or an
+/// `i64` constant materialized in the context of the left hand side.
+pub trait AffineExprOperand<'ctx> {
+    fn to_affine_expr(self, lhs: AffineExpr<'ctx>) -> AffineExpr<'ctx>;
+}
+
+impl<'ctx> AffineExprOperand<'ctx> for AffineExpr<'ctx> {
+    fn to_affine_expr(self, _lhs: AffineExpr<'ctx>) -> AffineExpr<'ctx> {
+        self
+    }
+}
+
+impl<'ctx> AffineExprOperand<'ctx> for i64 {
+    fn to_affine_expr(self, lhs: AffineExpr<'ctx>) -> AffineExpr<'ctx> {
+        let handle =
+            unsafe { MLIR_AffineExpr::FFIVal_::mlirAffineConstantExprGet(lhs.get_context(), self) };
+        AffineExpr::from_handle_same_context(handle, &lhs)
+    }
+}
+
+impl<'ctx> AffineExpr<'ctx> {
+    pub fn floor_div<T: AffineExprOperand<'ctx>>(self, rhs: T) -> Self {
+        Self::floordiv_expr_get(self, rhs.to_affine_expr(self))
+    }
+    pub fn ceil_div<T: AffineExprOperand<'ctx>>(self, rhs: T) -> Self {
+        Self::ceildiv_expr_get(self, rhs.to_affine_expr(self))
+    }
+    pub fn rem<T: AffineExprOperand<'ctx>>(self, rhs: T) -> Self {
+        Self::mod_expr_get(self, rhs.to_affine_expr(self))
+    }
+}
+
+impl<'ctx, T: AffineExprOperand<'ctx>> Add<T> for AffineExpr<'ctx> {
+    type Output = Self;
+    fn add(self, rhs: T) -> Self {
+        Self::add_expr_get(self, rhs.to_affine_expr(self))
+    }
+}
+
+impl<'ctx, T: AffineExprOperand<'ctx>> Mul<T> for AffineExpr<'ctx> {
+    type Output = Self;
+    fn mul(self, rhs: T) -> Self {
+        Self::mul_expr_get(self, rhs.to_affine_expr(self))
+    }
+}
+
+impl<'ctx, T: AffineExprOperand<'ctx>> Sub<T> for AffineExpr<'ctx> {
+    type Output = Self;
+    fn sub(self, rhs: T) -> Self {
+        // Like the C++ API, `a - b` is represented as `a + b * -1`.
+        self + rhs.to_affine_expr(self) * -1
+    }
+}
+
+impl<'ctx> Neg for AffineExpr<'ctx> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self * -1
+    }
+}
+
+impl<'ctx> Add<AffineExpr<'ctx>> for i64 {
+    type Output = AffineExpr<'ctx>;
+    fn add(self, rhs: AffineExpr<'ctx>) -> AffineExpr<'ctx> {
+        rhs + self
+    }
+}
+
+impl<'ctx> Mul<AffineExpr<'ctx>> for i64 {
+    type Output = AffineExpr<'ctx>;
+    fn mul(self, rhs: AffineExpr<'ctx>) -> AffineExpr<'ctx> {
+        rhs * self
+    }
+}
+
+impl<'ctx> Sub<AffineExpr<'ctx>> for i64 {
+    type Output = AffineExpr<'ctx>;
+    fn sub(self, rhs: AffineExpr<'ctx>) -> AffineExpr<'ctx> {
+        -rhs + self
+    }
+}
+
+#[cfg(test)]
+mod affine_expr_test {
+    use super::*;
+
+    #[test]
+    fn operators() {
+        let ctx = Context::create();
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let d1 = AffineExpr::dim_expr_get(&ctx, 1);
+        let s0 = AffineExpr::symbol_expr_get(&ctx, 0);
+        let map = |exprs: &[AffineExpr]| AffineMap::get(&ctx, 2, 1, exprs).to_string();
+        assert_eq!(
+            map(&[d0 + s0, d1 * 4, d0 - d1, -d0, 3 - d1, 2 + d0 * 2]),
+            "(d0, d1)[s0] -> (d0 + s0, d1 * 4, d0 - d1, -d0, -d1 + 3, d0 * 2 + 2)"
+        );
+        assert_eq!(
+            map(&[d0.floor_div(4), d1.ceil_div(s0), d0.rem(8)]),
+            "(d0, d1)[s0] -> (d0 floordiv 4, d1 ceildiv s0, d0 mod 8)"
+        );
+        let c = AffineExpr::const_expr_get(&ctx, 6) * 7 - 2;
+        assert!(c.is_a_constant());
+        assert_eq!(c.const_expr_get_value(), 40);
+        assert_eq!(d0 + 0, d0);
+        assert!((d0 * 2 + s0).is_a_add());
+    }
+}
@@ -0,0 +1,477 @@
+use crate::affine_expr::*;
+use crate::context::*;
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// An owned, context-free copy of an `AffineExpr`, for analyzing index expressions in Rust without
+/// going through the C API for every node.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AffineExprTree {
+    Dim(usize),
+    Symbol(usize),
+    Const(i64),
+    Add(Box<AffineExprTree>, Box<AffineExprTree>),
+    Mul(Box<AffineExprTree>, Box<AffineExprTree>),
+    Mod(Box<AffineExprTree>, Box<AffineExprTree>),
+    FloorDiv(Box<AffineExprTree>, Box<AffineExprTree>),
+    CeilDiv(Box<AffineExprTree>, Box<AffineExprTree>),
+}
+
+/// `sum(dims[i] * d_i) + sum(symbols[i] * s_i) + constant`. Positions past the end of `dims` or
+/// `symbols` have a zero coefficient.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AffineLinearForm {
+    pub dims: Vec<i64>,
+    pub symbols: Vec<i64>,
+    pub constant: i64,
+}
+
+impl AffineLinearForm {
+    pub fn get_dim_coeff(&self, pos: usize) -> i64 {
+        self.dims.get(pos).copied().unwrap_or(0)
+    }
+    pub fn get_symbol_coeff(&self, pos: usize) -> i64 {
+        self.symbols.get(pos).copied().unwrap_or(0)
+    }
+    pub fn is_constant(&self) -> bool {
+        self.dims.iter().chain(self.symbols.iter()).all(|c| *c == 0)
+    }
+    fn combine(mut self, other: Self, factor: i64) -> Option<Self> {
+        fn combine_coeffs(lhs: &mut Vec<i64>, rhs: &[i64], factor: i64) -> Option<()> {
+            if lhs.len() < rhs.len() {
+                lhs.resize(rhs.len(), 0);
+            }
+            for (l, r) in lhs.iter_mut().zip(rhs) {
+                *l = l.checked_add(r.checked_mul(factor)?)?;
+            }
+            Some(())
+        }
+        combine_coeffs(&mut self.dims, &other.dims, factor)?;
+        combine_coeffs(&mut self.symbols, &other.symbols, factor)?;
+        self.constant = self
+            .constant
+            .checked_add(other.constant.checked_mul(factor)?)?;
+        Some(self)
+    }
+    fn scale(self, factor: i64) -> Option<Self> {
+        Self::default().combine(self, factor)
+    }
+    /// Rebuilds the expression as `c0 * d0 + ... + cN * sN + constant`, dropping zero terms.
+    pub fn to_tree(&self) -> AffineExprTree {
+        let dims = self
+            .dims
+            .iter()
+            .enumerate()
+            .map(|(pos, c)| (AffineExprTree::Dim(pos), *c));
+        let symbols = self
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(pos, c)| (AffineExprTree::Symbol(pos), *c));
+        let mut res: Option<AffineExprTree> = None;
+        for (term, coeff) in dims.chain(symbols) {
+            let term = match coeff {
+                0 => continue,
+                1 => term,
+                _ => term * AffineExprTree::Const(coeff),
+            };
+            res = Some(match res {
+                Some(lhs) => lhs + term,
+                None => term,
+            });
+        }
+        match res {
+            Some(res) if self.constant == 0 => res,
+            Some(res) => res + AffineExprTree::Const(self.constant),
+            None => AffineExprTree::Const(self.constant),
+        }
+    }
+}
+
+fn floor_div(lhs: i64, rhs: i64) -> Option<i64> {
+    let quotient = lhs.checked_div(rhs)?;
+    if lhs % rhs != 0 && (lhs < 0) != (rhs < 0) {
+        Some(quotient - 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+fn ceil_div(lhs: i64, rhs: i64) -> Option<i64> {
+    let quotient = lhs.checked_div(rhs)?;
+    if lhs % rhs != 0 && (lhs < 0) == (rhs < 0) {
+        Some(quotient + 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+fn modulo(lhs: i64, rhs: i64) -> Option<i64> {
+    lhs.checked_sub(floor_div(lhs, rhs)?.checked_mul(rhs)?)
+}
+
+impl AffineExprTree {
+    pub fn from_affine_expr(expr: AffineExpr) -> Self {
+        if expr.is_a_dim() {
+            return Self::Dim(expr.dim_expr_get_pos() as usize);
+        }
+        if expr.is_a_symbol() {
+            return Self::Symbol(expr.symbol_expr_get_pos() as usize);
+        }
+        if expr.is_a_constant() {
+            return Self::Const(expr.const_expr_get_value());
+        }
+        let lhs = Box::new(Self::from_affine_expr(expr.binary_get_lhs()));
+        let rhs = Box::new(Self::from_affine_expr(expr.binary_get_rhs()));
+        if expr.is_a_add() {
+            Self::Add(lhs, rhs)
+        } else if expr.is_a_mul() {
+            Self::Mul(lhs, rhs)
+        } else if expr.is_a_mod() {
+            Self::Mod(lhs, rhs)
+        } else if expr.is_a_floordiv() {
+            Self::FloorDiv(lhs, rhs)
+        } else if expr.is_a_ceildiv() {
+            Self::CeilDiv(lhs, rhs)
+        } else {
+            unreachable!("unknown affine expression kind: {}", expr)
+        }
+    }
+    pub fn to_affine_expr<'ctx>(&self, ctx: &'ctx Context) -> AffineExpr<'ctx> {
+        match self {
+            Self::Dim(pos) => AffineExpr::dim_expr_get(ctx, *pos),
+            Self::Symbol(pos) => AffineExpr::symbol_expr_get(ctx, *pos),
+            Self::Const(value) => AffineExpr::const_expr_get(ctx, *value),
+            Self::Add(lhs, rhs) => {
+                AffineExpr::add_expr_get(lhs.to_affine_expr(ctx), rhs.to_affine_expr(ctx))
+            }
+            Self::Mul(lhs, rhs) => {
+                AffineExpr::mul_expr_get(lhs.to_affine_expr(ctx), rhs.to_affine_expr(ctx))
+            }
+            Self::Mod(lhs, rhs) => {
+                AffineExpr::mod_expr_get(lhs.to_affine_expr(ctx), rhs.to_affine_expr(ctx))
+            }
+            Self::FloorDiv(lhs, rhs) => {
+                AffineExpr::floordiv_expr_get(lhs.to_affine_expr(ctx), rhs.to_affine_expr(ctx))
+            }
+            Self::CeilDiv(lhs, rhs) => {
+                AffineExpr::ceildiv_expr_get(lhs.to_affine_expr(ctx), rhs.to_affine_expr(ctx))
+            }
+        }
+    }
+    pub fn get_operands(&self) -> Option<(&Self, &Self)> {
+        match self {
+            Self::Dim(_) | Self::Symbol(_) | Self::Const(_) => None,
+            Self::Add(lhs, rhs)
+            | Self::Mul(lhs, rhs)
+            | Self::Mod(lhs, rhs)
+            | Self::FloorDiv(lhs, rhs)
+            | Self::CeilDiv(lhs, rhs) => Some((lhs, rhs)),
+        }
+    }
+    pub fn as_const(&self) -> Option<i64> {
+        match self {
+            Self::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+    pub fn is_symbolic_or_constant(&self) -> bool {
+        match self {
+            Self::Dim(_) => false,
+            Self::Symbol(_) | Self::Const(_) => true,
+            _ => {
+                let (lhs, rhs) = self.get_operands().unwrap();
+                lhs.is_symbolic_or_constant() && rhs.is_symbolic_or_constant()
+            }
+        }
+    }
+    /// Same as `AffineExpr::is_pure_affine`: multiplications need a constant operand, and divisions
+    /// and modulos a constant right hand side.
+    pub fn is_pure_affine(&self) -> bool {
+        match self {
+            Self::Dim(_) | Self::Symbol(_) | Self::Const(_) => true,
+            Self::Add(lhs, rhs) => lhs.is_pure_affine() && rhs.is_pure_affine(),
+            Self::Mul(lhs, rhs) => {
+                lhs.is_pure_affine()
+                    && rhs.is_pure_affine()
+                    && (lhs.as_const().is_some() || rhs.as_const().is_some())
+            }
+            Self::Mod(lhs, rhs) | Self::FloorDiv(lhs, rhs) | Self::CeilDiv(lhs, rhs) => {
+                lhs.is_pure_affine() && rhs.as_const().is_some()
+            }
+        }
+    }
+    pub fn is_func_of_dim(&self, pos: usize) -> bool {
+        match self {
+            Self::Dim(dim) => *dim == pos,
+            Self::Symbol(_) | Self::Const(_) => false,
+            _ => {
+                let (lhs, rhs) = self.get_operands().unwrap();
+                lhs.is_func_of_dim(pos) || rhs.is_func_of_dim(pos)
+            }
+        }
+    }
+    /// Evaluates the expression, returns `None` on an out of range position, a division by zero or
+    /// an overflow.
+    pub fn evaluate(&self, dims: &[i64], symbols: &[i64]) -> Option<i64> {
+        match self {
+            Self::Dim(pos) => dims.get(*pos).copied(),
+            Self::Symbol(pos) => symbols.get(*pos).copied(),
+            Self::Const(value) => Some(*value),
+            Self::Add(lhs, rhs) => lhs
+                .evaluate(dims, symbols)?
+                .checked_add(rhs.evaluate(dims, symbols)?),
+            Self::Mul(lhs, rhs) => lhs
+                .evaluate(dims, symbols)?
+                .checked_mul(rhs.evaluate(dims, symbols)?),
+            Self::Mod(lhs, rhs) => {
+                modulo(lhs.evaluate(dims, symbols)?, rhs.evaluate(dims, symbols)?)
+            }
+            Self::FloorDiv(lhs, rhs) => {
+                floor_div(lhs.evaluate(dims, symbols)?, rhs.evaluate(dims, symbols)?)
+            }
+            Self::CeilDiv(lhs, rhs) => {
+                ceil_div(lhs.evaluate(dims, symbols)?, rhs.evaluate(dims, symbols)?)
+            }
+        }
+    }
+    /// Replaces `d_i` with `dims[i]` and `s_i` with `symbols[i]`, positions without a replacement
+    /// are kept as is.
+    pub fn substitute(&self, dims: &[AffineExprTree], symbols: &[AffineExprTree]) -> Self {
+        let map_binary = |lhs: &Self, rhs: &Self| {
+            (
+                Box::new(lhs.substitute(dims, symbols)),
+                Box::new(rhs.substitute(dims, symbols)),
+            )
+        };
+        match self {
+            Self::Dim(pos) => dims.get(*pos).unwrap_or(self).clone(),
+            Self::Symbol(pos) => symbols.get(*pos).unwrap_or(self).clone(),
+            Self::Const(_) => self.clone(),
+            Self::Add(lhs, rhs) => {
+                let (lhs, rhs) = map_binary(lhs, rhs);
+                Self::Add(lhs, rhs)
+            }
+            Self::Mul(lhs, rhs) => {
+                let (lhs, rhs) = map_binary(lhs, rhs);
+                Self::Mul(lhs, rhs)
+            }
+            Self::Mod(lhs, rhs) => {
+                let (lhs, rhs) = map_binary(lhs, rhs);
+                Self::Mod(lhs, rhs)
+            }
+            Self::FloorDiv(lhs, rhs) => {
+                let (lhs, rhs) = map_binary(lhs, rhs);
+                Self::FloorDiv(lhs, rhs)
+            }
+            Self::CeilDiv(lhs, rhs) => {
+                let (lhs, rhs) = map_binary(lhs, rhs);
+                Self::CeilDiv(lhs, rhs)
+            }
+        }
+    }
+    /// Returns the coefficients of the expression if it is a linear combination of dims and
+    /// symbols, `None` otherwise (or on overflow). Constant divisions and modulos are folded.
+    pub fn get_linear_form(&self) -> Option<AffineLinearForm> {
+        match self {
+            Self::Dim(pos) => {
+                let mut dims = vec![0; pos + 1];
+                dims[*pos] = 1;
+                Some(AffineLinearForm {
+                    dims,
+                    ..Default::default()
+                })
+            }
+            Self::Symbol(pos) => {
+                let mut symbols = vec![0; pos + 1];
+                symbols[*pos] = 1;
+                Some(AffineLinearForm {
+                    symbols,
+                    ..Default::default()
+                })
+            }
+            Self::Const(constant) => Some(AffineLinearForm {
+                constant: *constant,
+                ..Default::default()
+            }),
+            Self::Add(lhs, rhs) => lhs.get_linear_form()?.combine(rhs.get_linear_form()?, 1),
+            Self::Mul(lhs, rhs) => {
+                let lhs = lhs.get_linear_form()?;
+                let rhs = rhs.get_linear_form()?;
+                if lhs.is_constant() {
+                    rhs.scale(lhs.constant)
+                } else if rhs.is_constant() {
+                    lhs.scale(rhs.constant)
+                } else {
+                    None
+                }
+            }
+            Self::Mod(..) | Self::FloorDiv(..) | Self::CeilDiv(..) => {
+                let constant = self.evaluate(&[], &[])?;
+                Some(AffineLinearForm {
+                    constant,
+                    ..Default::default()
+                })
+            }
+        }
+    }
+    /// Folds constants, drops identity operations and puts linear expressions into the canonical
+    /// `c0 * d0 + ... + constant` form.
+    pub fn simplify(&self) -> Self {
+        if let Some(linear_form) = self.get_linear_form() {
+            return linear_form.to_tree();
+        }
+        let (lhs, rhs) = match self.get_operands() {
+            Some((lhs, rhs)) => (lhs.simplify(), rhs.simplify()),
+            None => return self.clone(),
+        };
+        if let (Some(l), Some(r)) = (lhs.as_const(), rhs.as_const()) {
+            let folded = match self {
+                Self::Mod(..) => modulo(l, r),
+                Self::FloorDiv(..) => floor_div(l, r),
+                Self::CeilDiv(..) => ceil_div(l, r),
+                _ => None,
+            };
+            if let Some(folded) = folded {
+                return Self::Const(folded);
+            }
+        }
+        match (self, rhs.as_const()) {
+            (Self::Add(..), Some(0)) => lhs,
+            (Self::Add(..), _) => lhs + rhs,
+            (Self::Mul(..), Some(0)) => Self::Const(0),
+            (Self::Mul(..), Some(1)) => lhs,
+            (Self::Mul(..), _) => lhs * rhs,
+            (Self::Mod(..), Some(1) | Some(-1)) => Self::Const(0),
+            (Self::Mod(..), _) => Self::Mod(Box::new(lhs), Box::new(rhs)),
+            (Self::FloorDiv(..) | Self::CeilDiv(..), Some(1)) => lhs,
+            (Self::FloorDiv(..), _) => Self::FloorDiv(Box::new(lhs), Box::new(rhs)),
+            (Self::CeilDiv(..), _) => Self::CeilDiv(Box::new(lhs), Box::new(rhs)),
+            (Self::Dim(_) | Self::Symbol(_) | Self::Const(_), _) => unreachable!(),
+        }
+    }
+}
+
+impl<'ctx> From<AffineExpr<'ctx>> for AffineExprTree {
+    fn from(expr: AffineExpr<'ctx>) -> Self {
+        Self::from_affine_expr(expr)
+    }
+}
+
+impl Add for AffineExprTree {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::Add(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Mul for AffineExprTree {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::Mul(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl Sub for AffineExprTree {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self + rhs * Self::Const(-1)
+    }
+}
+
+impl Neg for AffineExprTree {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self * Self::Const(-1)
+    }
+}
+
+#[cfg(test)]
+mod affine_expr_tree_test {
+    use super::*;
+    use AffineExprTree::*;
+
+    #[test]
+    fn round_trip() {
+        let ctx = Context::create();
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let d1 = AffineExpr::dim_expr_get(&ctx, 1);
+        let s0 = AffineExpr::symbol_expr_get(&ctx, 0);
+        for expr in [
+            d0 + s0 * 3,
+            d1.floor_div(4),
+            (d0 - d1).ceil_div(s0),
+            d0.rem(8) + 1,
+        ] {
+            let tree = AffineExprTree::from(expr);
+            assert_eq!(tree.to_affine_expr(&ctx), expr);
+            assert_eq!(tree.is_pure_affine(), expr.is_pure_affine());
+            assert_eq!(
+                tree.is_symbolic_or_constant(),
+                expr.is_symbolic_or_constant()
+            );
+        }
+        assert_eq!(
+            AffineExprTree::from(d0 * 2 + 5),
+            Dim(0) * Const(2) + Const(5)
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let expr = (Dim(0) - Symbol(0)) + Dim(1) * Const(3);
+        assert_eq!(expr.evaluate(&[10, 2], &[4]), Some(12));
+        assert_eq!(expr.evaluate(&[10], &[4]), None);
+        let div = |kind: fn(Box<AffineExprTree>, Box<AffineExprTree>) -> AffineExprTree| {
+            [(7, 2), (-7, 2), (7, -2), (-7, -2), (6, 3)]
+                .iter()
+                .map(|(l, r)| kind(Box::new(Const(*l)), Box::new(Const(*r))).evaluate(&[], &[]))
+                .collect::<Vec<_>>()
+        };
+        let s = |v: [i64; 5]| v.iter().map(|x| Some(*x)).collect::<Vec<_>>();
+        assert_eq!(div(FloorDiv), s([3, -4, -4, 3, 2]));
+        assert_eq!(div(CeilDiv), s([4, -3, -3, 4, 2]));
+        assert_eq!(div(Mod), s([1, 1, -1, -1, 0]));
+        assert_eq!(
+            FloorDiv(Box::new(Dim(0)), Box::new(Const(0))).evaluate(&[1], &[]),
+            None
+        );
+        assert_eq!((Const(i64::MAX) + Const(1)).evaluate(&[], &[]), None);
+    }
+
+    #[test]
+    fn substitute_and_linear_form() {
+        let expr = Dim(0) * Const(2) + Symbol(1) - Dim(2);
+        let substituted = expr.substitute(&[Dim(1) + Const(1)], &[]);
+        assert_eq!(
+            substituted,
+            (Dim(1) + Const(1)) * Const(2) + Symbol(1) - Dim(2)
+        );
+        let linear_form = substituted.get_linear_form().unwrap();
+        assert_eq!(linear_form.dims, vec![0, 2, -1]);
+        assert_eq!(linear_form.symbols, vec![0, 1]);
+        assert_eq!(linear_form.constant, 2);
+        assert_eq!(linear_form.get_dim_coeff(5), 0);
+        assert_eq!(
+            substituted.simplify(),
+            Dim(1) * Const(2) + Dim(2) * Const(-1) + Symbol(1) + Const(2)
+        );
+        assert!((Dim(0) * Dim(1)).get_linear_form().is_none());
+        assert!(FloorDiv(Box::new(Dim(0)), Box::new(Const(2)))
+            .get_linear_form()
+            .is_none());
+    }
+
+    #[test]
+    fn simplify() {
+        let floordiv = FloorDiv(Box::new(Dim(0) + Dim(0) + Const(0)), Box::new(Const(1)));
+        assert_eq!(floordiv.simplify(), Dim(0) * Const(2));
+        let modulo = Mod(Box::new(Dim(0) * Dim(1) * Const(1)), Box::new(Const(4)));
+        assert_eq!(
+            modulo.simplify(),
+            Mod(Box::new(Dim(0) * Dim(1)), Box::new(Const(4)))
+        );
+        assert_eq!((Dim(0) * Dim(1) * Const(0)).simplify(), Const(0));
+        assert_eq!((Dim(0) - Dim(0)).simplify(), Const(0));
+    }
+}
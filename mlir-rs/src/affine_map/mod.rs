@@ -1,12 +1,15 @@
+use crate::affine_expr::tree::*;
 use crate::affine_expr::*;
 use crate::common::*;
 use crate::context::*;
+use crate::r#type::*;
 use crate::support::*;
 use crate::type_cast::*;
 
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
+use mlir_capi::AffineExpr as MLIR_AffineExpr;
 use mlir_capi::AffineMap as MLIR_AffineMap;
 use mlir_capi::AffineMap::*;
 use mlir_capi::IR::*;
@@ -94,6 +97,9 @@ impl<'ctx> AffineMap<'ctx> {
             MLIR_AffineMap::FFIVoid_::mlirAffineMapDump(self);
         }
     }
+    pub fn get_context(self) -> ContextRef<'ctx> {
+        ContextRef::from_handle_same_context(self.get_context_handle(), &self)
+    }
     pub fn empty_get(ctx: &'ctx Context) -> Self {
         let handle = unsafe { MLIR_AffineMap::FFIVal_::mlirAffineMapEmptyGet(ctx) };
         unsafe { Self::from_handle_and_phantom(handle, PhantomData::default()) }
@@ -229,6 +235,177 @@ impl<'ctx> AffineMap<'ctx> {
         };
         AffineMap::from_handle_same_context(handle, &self)
     }
+    pub fn get_results(self) -> Vec<AffineExpr<'ctx>> {
+        (0..self.get_num_results())
+            .map(|pos| self.get_result(pos))
+            .collect()
+    }
+    fn get_same_context(self, dim_count: usize, symbol_count: usize, exprs: &[AffineExpr]) -> Self {
+        let handle = unsafe {
+            MLIR_AffineMap::FFIVal_::mlirAffineMapGet(
+                self.get_context(),
+                dim_count as i64,
+                symbol_count as i64,
+                exprs.len() as i64,
+                exprs.as_ptr() as *const _ as *mut _,
+            )
+        };
+        AffineMap::from_handle_same_context(handle, &self)
+    }
+    fn dim_expr_get_same_context(self, pos: usize) -> AffineExpr<'ctx> {
+        let handle = unsafe {
+            MLIR_AffineExpr::FFIVal_::mlirAffineDimExprGet(self.get_context(), pos as i64)
+        };
+        AffineExpr::from_handle_same_context(handle, &self)
+    }
+    fn symbol_expr_get_same_context(self, pos: usize) -> AffineExpr<'ctx> {
+        let handle = unsafe {
+            MLIR_AffineExpr::FFIVal_::mlirAffineSymbolExprGet(self.get_context(), pos as i64)
+        };
+        AffineExpr::from_handle_same_context(handle, &self)
+    }
+    /// Returns `self ∘ other`, i.e. `self(other(dims)[other syms])[self syms]`, with the same
+    /// conventions as the C++ `AffineMap::compose`: the result takes the dims of `other`, and the
+    /// symbols of `self` followed by those of `other`. Fails if the number of dims of `self` is not
+    /// the number of results of `other`.
+    pub fn compose(self, other: AffineMap<'ctx>) -> Result<Self, String> {
+        if self.get_num_dims() != other.get_num_results() {
+            return Err(format!(
+                "cannot compose {} with {}, expected {} results",
+                self,
+                other,
+                self.get_num_dims()
+            ));
+        }
+        let num_dims = other.get_num_dims();
+        let num_self_symbols = self.get_num_symbols();
+        let num_symbols = num_self_symbols + other.get_num_symbols();
+        let mut inner = other;
+        // Shift the symbols of `other` past those of `self`, starting from the last one so that a
+        // renamed symbol is never renamed again.
+        if num_self_symbols > 0 {
+            for pos in (0..other.get_num_symbols()).rev() {
+                inner = inner.replace(
+                    other.symbol_expr_get_same_context(pos),
+                    other.symbol_expr_get_same_context(pos + num_self_symbols),
+                    num_dims,
+                    num_symbols,
+                );
+            }
+        }
+        let results: Vec<_> = self
+            .get_results()
+            .into_iter()
+            .map(|expr| expr.compose(inner))
+            .collect();
+        Ok(self.get_same_context(num_dims, num_symbols, &results))
+    }
+    /// Same as the C++ `inversePermutation`: the results which are plain dims define the inverse,
+    /// the first occurrence of a dim wins. Returns `None` if some dim is not a result, or if the
+    /// map has symbols.
+    pub fn inverse_permutation(self) -> Option<Self> {
+        if self.is_empty() {
+            return Some(self);
+        }
+        if self.get_num_symbols() != 0 {
+            return None;
+        }
+        let mut exprs = vec![None; self.get_num_dims()];
+        for (idx, expr) in self.get_results().into_iter().enumerate() {
+            if expr.is_a_dim() {
+                let pos = expr.dim_expr_get_pos() as usize;
+                if exprs[pos].is_none() {
+                    exprs[pos] = Some(self.dim_expr_get_same_context(idx));
+                }
+            }
+        }
+        let exprs: Option<Vec<_>> = exprs.into_iter().collect();
+        Some(self.get_same_context(self.get_num_results(), 0, &exprs?))
+    }
+    /// Evaluates the results on concrete dim and symbol values. Fails if the number of values does
+    /// not match the map, or on a division by zero or an overflow.
+    pub fn evaluate(self, dims: &[i64], symbols: &[i64]) -> Result<Vec<i64>, String> {
+        if dims.len() != self.get_num_dims() || symbols.len() != self.get_num_symbols() {
+            return Err(format!(
+                "expected {} dims and {} symbols for {}, got {} and {}",
+                self.get_num_dims(),
+                self.get_num_symbols(),
+                self,
+                dims.len(),
+                symbols.len()
+            ));
+        }
+        self.get_results()
+            .into_iter()
+            .map(|expr| {
+                AffineExprTree::from(expr)
+                    .evaluate(dims, symbols)
+                    .ok_or_else(|| format!("failed to evaluate {}", expr))
+            })
+            .collect()
+    }
+    /// Infers the static shape accessed by a (symbol-less) map from the loop `ranges`, the way
+    /// linalg verifies its indexing maps: each result size is one more than its largest value over
+    /// the loops. A result is dynamic if it is not a linear combination of the dims, depends on a
+    /// dynamic or empty range, or overflows. Returns `None` if the number of ranges does not match
+    /// the map, or the map has symbols.
+    pub fn infer_result_shape(self, ranges: &[i64]) -> Option<Vec<i64>> {
+        if ranges.len() != self.get_num_dims() || self.get_num_symbols() != 0 {
+            return None;
+        }
+        let infer_size = |expr: AffineExpr| -> Option<i64> {
+            // Bounding the values of a linear form only needs the ends of the ranges.
+            let form = AffineExprTree::from(expr).get_linear_form()?;
+            let mut largest = form.constant;
+            for (pos, range) in ranges.iter().enumerate() {
+                let coeff = form.get_dim_coeff(pos);
+                if coeff == 0 {
+                    continue;
+                }
+                if RankedTensorType::is_dynamic_size(*range) || *range <= 0 {
+                    return None;
+                }
+                if coeff > 0 {
+                    largest = largest.checked_add(coeff.checked_mul(range.checked_sub(1)?)?)?;
+                }
+            }
+            largest.checked_add(1)
+        };
+        let dynamic = RankedTensorType::get_dynamic_size();
+        Some(
+            self.get_results()
+                .into_iter()
+                .map(|expr| infer_size(expr).unwrap_or(dynamic))
+                .collect(),
+        )
+    }
+    /// Infers the loop ranges from the static `shapes` accessed through `maps`, the way linalg
+    /// does: a loop dim takes the first static size of an operand dimension indexed by that plain
+    /// dim. Ranges which cannot be inferred are dynamic. Returns `None` if the maps do not have the
+    /// same number of dims, or a shape does not match the results of its map.
+    pub fn infer_loop_ranges(maps: &[AffineMap<'ctx>], shapes: &[&[i64]]) -> Option<Vec<i64>> {
+        if maps.len() != shapes.len() {
+            return None;
+        }
+        let num_dims = maps.first()?.get_num_dims();
+        let dynamic = RankedTensorType::get_dynamic_size();
+        let mut ranges = vec![dynamic; num_dims];
+        for (map, shape) in maps.iter().zip(shapes) {
+            if map.get_num_dims() != num_dims || map.get_num_results() != shape.len() {
+                return None;
+            }
+            for (expr, size) in map.get_results().into_iter().zip(shape.iter()) {
+                if !expr.is_a_dim() || RankedTensorType::is_dynamic_size(*size) {
+                    continue;
+                }
+                let pos = expr.dim_expr_get_pos() as usize;
+                if RankedTensorType::is_dynamic_size(ranges[pos]) {
+                    ranges[pos] = *size;
+                }
+            }
+        }
+        Some(ranges)
+    }
     pub fn compress_unused_combols(affine_maps: &[AffineMap<'ctx>]) -> Vec<AffineMap<'ctx>> {
         let mut res = vec![AffineMap::create_null(); affine_maps.len()];
         extern "C" fn compress_helper(res: *mut std::ffi::c_void, idx: i64, m: MlirAffineMap) {
@@ -305,4 +482,89 @@ pub mod affine_map_test {
         }
         res
     }
+
+    #[test]
+    fn affine_map_macro() {
+        let ctx = Context::create();
+        let map = crate::affine_map!(&ctx, (d0, d1)[s0] -> (d0 + s0, d1 floordiv 4));
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let d1 = AffineExpr::dim_expr_get(&ctx, 1);
+        let s0 = AffineExpr::symbol_expr_get(&ctx, 0);
+        assert_eq!(map, AffineMap::get(&ctx, 2, 1, &[d0 + s0, d1.floor_div(4)]));
+        let map = crate::affine_map!(&ctx, (i, j) -> (-(i * 2) + 3 mod j, (i - j) ceildiv 5, 7));
+        assert_eq!(
+            map.to_string(),
+            "(d0, d1) -> (d0 * -2 + 3 mod d1, (d0 - d1) ceildiv 5, 7)"
+        );
+        let map = crate::affine_map!(&ctx, () -> ());
+        assert_eq!(map.get_num_results(), 0);
+    }
+
+    #[test]
+    fn compose_and_inverse() {
+        let ctx = Context::create();
+        let outer = crate::affine_map!(&ctx, (d0, d1)[s0] -> (d0 + s0, d1));
+        let inner = crate::affine_map!(&ctx, (d0)[s0] -> (d0 * 2, d0 + s0));
+        assert_eq!(
+            outer.compose(inner),
+            Ok(crate::affine_map!(&ctx, (d0)[s0, s1] -> (d0 * 2 + s0, d0 + s1)))
+        );
+        assert!(inner.compose(inner).is_err());
+        let perm = AffineMap::permutation_get(&ctx, &[1, 2, 0]);
+        assert_eq!(
+            perm.inverse_permutation(),
+            Some(AffineMap::permutation_get(&ctx, &[2, 0, 1]))
+        );
+        assert!(perm
+            .compose(perm.inverse_permutation().unwrap())
+            .unwrap()
+            .is_identify());
+        let projection = crate::affine_map!(&ctx, (d0, d1, d2) -> (d2, d0 + d1, d0, d1));
+        assert_eq!(
+            projection.inverse_permutation(),
+            Some(crate::affine_map!(&ctx, (d0, d1, d2, d3) -> (d2, d3, d0)))
+        );
+        let broadcast = crate::affine_map!(&ctx, (d0, d1) -> (d0));
+        assert_eq!(broadcast.inverse_permutation(), None);
+        assert_eq!(outer.inverse_permutation(), None);
+    }
+
+    #[test]
+    fn evaluate_and_shapes() {
+        let ctx = Context::create();
+        let map = crate::affine_map!(&ctx, (d0, d1)[s0] -> (d0 + s0, d1 floordiv 4, 7));
+        assert_eq!(map.get_results().len(), 3);
+        assert_eq!(map.get_results()[2].const_expr_get_value(), 7);
+        assert_eq!(map.evaluate(&[3, 9], &[2]), Ok(vec![5, 2, 7]));
+        assert!(map.evaluate(&[3, 9], &[]).is_err());
+        let div = crate::affine_map!(&ctx, (d0)[s0] -> (d0 floordiv s0));
+        assert!(div.evaluate(&[3], &[0]).is_err());
+
+        let dynamic = RankedTensorType::get_dynamic_size();
+        let conv = crate::affine_map!(&ctx, (d0, d1, d2) -> (d0 + d2, d1));
+        assert_eq!(conv.infer_result_shape(&[4, 5, 3]), Some(vec![6, 5]));
+        assert_eq!(
+            conv.infer_result_shape(&[4, dynamic, 3]),
+            Some(vec![6, dynamic])
+        );
+        assert_eq!(conv.infer_result_shape(&[4, 0, 3]), Some(vec![6, dynamic]));
+        assert_eq!(conv.infer_result_shape(&[4, 5]), None);
+        let non_linear = crate::affine_map!(&ctx, (d0, d1) -> (d0 mod 4, d0 - d1, d1 * 2));
+        assert_eq!(
+            non_linear.infer_result_shape(&[6, 3]),
+            Some(vec![dynamic, 6, 5])
+        );
+        assert_eq!(
+            non_linear.infer_result_shape(&[6, i64::MAX]),
+            Some(vec![dynamic, 6, dynamic])
+        );
+
+        let lhs = crate::affine_map!(&ctx, (d0, d1, d2) -> (d0, d2));
+        let rhs = crate::affine_map!(&ctx, (d0, d1, d2) -> (d2, d1));
+        let out = crate::affine_map!(&ctx, (d0, d1, d2) -> (d0, d1));
+        let ranges =
+            AffineMap::infer_loop_ranges(&[lhs, rhs, out], &[&[4, 8], &[8, dynamic], &[4, 16]]);
+        assert_eq!(ranges, Some(vec![4, 16, 8]));
+        assert_eq!(AffineMap::infer_loop_ranges(&[lhs], &[&[4]]), None);
+    }
 }
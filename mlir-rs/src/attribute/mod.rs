@@ -1,6 +1,7 @@
 use crate::affine_map::*;
 use crate::common::*;
 use crate::context::*;
+use crate::diagnostic::*;
 use crate::dialect::*;
 use crate::integer_set::*;
 use crate::location::*;
@@ -43,9 +44,15 @@ impl<'ctx> Into<MlirAttribute> for Attr<'ctx> {
 }
 
 impl<'ctx> Attr<'ctx> {
-    pub fn parse(ctx: &'ctx Context, attr: &str) -> Self {
+    pub fn parse(ctx: &'ctx Context, attr: &str) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new(ctx);
         let handle = unsafe { IR::FFIVal_::mlirAttributeParseGet(ctx, StrRef::from(attr)) };
-        unsafe { Self::from_handle_and_phantom(handle, PhantomData::default()) }
+        let attr = unsafe { Self::from_handle_and_phantom(handle, PhantomData::default()) };
+        if attr.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(attr)
+        }
     }
 }
 
@@ -138,7 +145,7 @@ pub mod attr_test {
             "0xDEAD : f16",
         ]
         .iter()
-        .map(|x| Attr::parse(ctx, x))
+        .map(|x| Attr::parse(ctx, x).unwrap())
         .collect()
     }
 
@@ -169,19 +176,19 @@ pub mod attr_test {
     fn test_parse_get_type_print_eq() {
         let ctx = Context::create();
         ctx.set_allow_unregistered_dialects(true);
-        let affine_map = Attr::parse(&ctx, "affine_map<(d0) -> (d0)>");
+        let affine_map = Attr::parse(&ctx, "affine_map<(d0) -> (d0)>").unwrap();
         assert!(IsA::<AffineMapAttr>::is_a_non_null(affine_map));
         affine_map.get_type();
         println!("{}", affine_map);
-        let array = Attr::parse(&ctx, "[]");
+        let array = Attr::parse(&ctx, "[]").unwrap();
         assert!(IsA::<ArrayAttr>::is_a_non_null(array));
         array.get_type();
         println!("{}", array);
-        let dense_array = Attr::parse(&ctx, "array<f64: 42., 12.>");
+        let dense_array = Attr::parse(&ctx, "array<f64: 42., 12.>").unwrap();
         assert!(IsA::<DenseF64ArrayAttr>::is_a_non_null(dense_array));
         dense_array.get_type();
         println!("{}", dense_array);
-        let dense_elements = Attr::parse(&ctx, "dense<[10.0, 11.0]> : tensor<2xf32>");
+        let dense_elements = Attr::parse(&ctx, "dense<[10.0, 11.0]> : tensor<2xf32>").unwrap();
         assert!(IsA::<DenseFPElementsAttr>::is_a_non_null(dense_elements));
         dense_elements.get_type();
         println!("{}", dense_elements);
@@ -190,91 +197,95 @@ pub mod attr_test {
         let dense_string = Attr::parse(
             &ctx,
             "dense<[\"example1\", \"example2\"]> : tensor<2x!foo.string>",
-        );
+        )
+        .unwrap();
         assert!(!dense_string.is_null());
         println!("{}", dense_string);
         let dict_attr = Attr::parse(
             &ctx,
             "{int_attr = 10, \"string attr name\" = \"string attribute\"}",
-        );
+        )
+        .unwrap();
         assert!(IsA::<DictionaryAttr>::is_a_non_null(dict_attr));
         dict_attr.get_type();
         println!("{}", dict_attr);
-        let float_attr = Attr::parse(&ctx, "0x7CFF : f16");
+        let float_attr = Attr::parse(&ctx, "0x7CFF : f16").unwrap();
         assert!(IsA::<FloatAttr>::is_a_non_null(float_attr));
         float_attr.get_type();
         println!("{}", float_attr);
-        let int_attr = Attr::parse(&ctx, "true");
+        let int_attr = Attr::parse(&ctx, "true").unwrap();
         assert!(IsA::<IntegerAttr>::is_a_non_null(int_attr));
         int_attr.get_type();
         println!("{}", int_attr);
-        let int_set_attr = Attr::parse(&ctx, "affine_set<(d0) : (d0 - 2 >= 0)>");
+        let int_set_attr = Attr::parse(&ctx, "affine_set<(d0) : (d0 - 2 >= 0)>").unwrap();
         assert!(IsA::<IntegerSetAttr>::is_a_non_null(int_set_attr));
         int_set_attr.get_type();
         println!("{}", int_set_attr);
-        let opaque_attr = Attr::parse(&ctx, "#foobar_dialect<\"opaque attribute data\">");
+        let opaque_attr = Attr::parse(&ctx, "#foobar_dialect<\"opaque attribute data\">").unwrap();
         assert!(IsA::<OpaqueAttr>::is_a_non_null(opaque_attr));
         opaque_attr.get_type();
         println!("{}", opaque_attr);
         let sparse_elem_attr =
-            Attr::parse(&ctx, "sparse<[[0, 0], [1, 2]], [1, 5]> : tensor<3x4xi32>");
+            Attr::parse(&ctx, "sparse<[[0, 0], [1, 2]], [1, 5]> : tensor<3x4xi32>").unwrap();
         assert!(IsA::<SparseElementsAttr>::is_a_non_null(sparse_elem_attr));
         sparse_elem_attr.get_type();
         println!("{}", sparse_elem_attr);
-        let str_attr = Attr::parse(&ctx, "\"An important string\"");
+        let str_attr = Attr::parse(&ctx, "\"An important string\"").unwrap();
         assert!(IsA::<StringAttr>::is_a_non_null(str_attr));
         str_attr.get_type();
         println!("{}", str_attr);
-        let flat_sym_ref = Attr::parse(&ctx, "@flat_reference");
+        let flat_sym_ref = Attr::parse(&ctx, "@flat_reference").unwrap();
         assert!(IsA::<FlatSymbolRefAttr>::is_a_non_null(flat_sym_ref));
         assert!(IsA::<SymbolRefAttr>::is_a_non_null(flat_sym_ref));
         flat_sym_ref.get_type();
         println!("{}", flat_sym_ref);
-        let nested_sym_ref = Attr::parse(&ctx, "@parent_reference::@nested_reference");
+        let nested_sym_ref = Attr::parse(&ctx, "@parent_reference::@nested_reference").unwrap();
         assert!(!IsA::<FlatSymbolRefAttr>::is_a_non_null(nested_sym_ref));
         assert!(IsA::<SymbolRefAttr>::is_a_non_null(nested_sym_ref));
         nested_sym_ref.get_type();
         println!("{}", nested_sym_ref);
-        let type_attr = Attr::parse(&ctx, "i32");
+        let type_attr = Attr::parse(&ctx, "i32").unwrap();
         assert!(IsA::<TypeAttr>::is_a_non_null(type_attr));
         type_attr.get_type();
         println!("{}", type_attr);
-        let unit_attr = Attr::parse(&ctx, "unit");
+        let unit_attr = Attr::parse(&ctx, "unit").unwrap();
         assert!(IsA::<UnitAttr>::is_a_non_null(unit_attr));
         unit_attr.get_type();
         println!("{}", unit_attr);
-        let strided_attr = Attr::parse(&ctx, "strided<[7, 2]>");
+        let strided_attr = Attr::parse(&ctx, "strided<[7, 2]>").unwrap();
         assert!(IsA::<StridedLayoutAttr>::is_a_non_null(strided_attr));
         strided_attr.get_type();
         println!("{}", strided_attr);
-        let loc_attr1 = Attr::parse(&ctx, "loc(callsite(\"foo\" at \"mysource.cc\":10:8))");
+        let loc_attr1 =
+            Attr::parse(&ctx, "loc(callsite(\"foo\" at \"mysource.cc\":10:8))").unwrap();
         assert!(IsA::<LocationAttr>::is_a_non_null(loc_attr1));
         loc_attr1.get_type();
         println!("{}", loc_attr1);
-        let loc_attr2 = Attr::parse(&ctx, "loc(\"mysource.cc\":10:8 to 12:18)");
+        let loc_attr2 = Attr::parse(&ctx, "loc(\"mysource.cc\":10:8 to 12:18)").unwrap();
         assert!(IsA::<LocationAttr>::is_a_non_null(loc_attr2));
         loc_attr2.get_type();
         println!("{}", loc_attr2);
         let loc_attr3 = Attr::parse(
             &ctx,
             "loc(fused<\"CSE\">[\"mysource.cc\":10:8, \"mysource.cc\":22:8])",
-        );
+        )
+        .unwrap();
         assert!(IsA::<LocationAttr>::is_a_non_null(loc_attr3));
         loc_attr3.get_type();
         println!("{}", loc_attr3);
-        let loc_attr4 = Attr::parse(&ctx, "loc(\"CSE\"(\"mysource.cc\":10:8))");
+        let loc_attr4 = Attr::parse(&ctx, "loc(\"CSE\"(\"mysource.cc\":10:8))").unwrap();
         assert!(IsA::<LocationAttr>::is_a_non_null(loc_attr4));
         loc_attr4.get_type();
         println!("{}", loc_attr4);
-        let loc_attr5 = Attr::parse(&ctx, "loc(\"mysource\")");
+        let loc_attr5 = Attr::parse(&ctx, "loc(\"mysource\")").unwrap();
         assert!(IsA::<LocationAttr>::is_a_non_null(loc_attr5));
         loc_attr5.get_type();
         println!("{}", loc_attr5);
-        let loc_attr6 = Attr::parse(&ctx, "loc(unknown)");
+        let loc_attr6 = Attr::parse(&ctx, "loc(unknown)").unwrap();
         assert!(IsA::<LocationAttr>::is_a_non_null(loc_attr6));
         loc_attr6.get_type();
         println!("{}", loc_attr6);
-        let dist_attr = Attr::parse(&ctx, "distinct[0]<42.0 : f32>");
+        let dist_attr = Attr::parse(&ctx, "distinct[0]<42.0 : f32>").unwrap();
         // FIXME: no dist attr
         assert!(!dist_attr.is_null());
         dist_attr.get_type();
@@ -401,6 +412,15 @@ pub struct NamedAttr<'ctx> {
     pub attribute: Attr<'ctx>,
 }
 
+impl<'ctx> NamedAttr<'ctx> {
+    pub fn get(ctx: &'ctx Context, name: &str, attribute: Attr<'ctx>) -> Self {
+        Self {
+            name: Identifier::get(ctx, name),
+            attribute,
+        }
+    }
+}
+
 impl<'ctx> Into<MlirNamedAttribute> for NamedAttr<'ctx> {
     fn into(self) -> MlirNamedAttribute {
         MlirNamedAttribute {
@@ -606,11 +626,20 @@ impl<'ctx> FloatAttr<'ctx> {
         let attr = unsafe { Attr::from_handle_and_phantom(handle, PhantomData::default()) };
         unsafe { IsA::<Self>::cast(attr) }
     }
-    pub fn f64_get_checked(loc: Location, ty: Type<'ctx>, val: f64) -> Self {
+    pub fn f64_get_checked(
+        loc: Location<'ctx>,
+        ty: Type<'ctx>,
+        val: f64,
+    ) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new_for(&loc);
         let handle =
             unsafe { BuiltinAttributes::FFIVal_::mlirFloatAttrDoubleGetChecked(loc, ty, val) };
         let attr = unsafe { Attr::from_handle_and_phantom(handle, PhantomData::default()) };
-        unsafe { IsA::<Self>::cast(attr) }
+        if attr.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(unsafe { IsA::<Self>::cast(attr) })
+        }
     }
     pub fn get_value_f64(self) -> f64 {
         unsafe { BuiltinAttributes::FFIVal_::mlirFloatAttrGetValueDouble(self) }
@@ -645,7 +674,10 @@ pub mod float_attr_test {
             for f_val in float_vals {
                 let f_attr = FloatAttr::f64_get(&ctx, f_ty.into(), *f_val);
                 assert!(IsA::<FloatAttr>::is_a_non_null(f_attr));
-                assert_eq!(f_attr, FloatAttr::f64_get_checked(loc, f_ty.into(), *f_val));
+                assert_eq!(
+                    f_attr,
+                    FloatAttr::f64_get_checked(loc, f_ty.into(), *f_val).unwrap()
+                );
                 let lhs = f_attr.get_value_f64();
                 let rhs = *f_val;
                 if lhs.is_nan() {
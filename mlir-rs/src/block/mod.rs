@@ -42,6 +42,14 @@ impl<'ctx> Into<MlirBlock> for BlockRef<'ctx> {
     }
 }
 
+impl<'ctx> std::ops::Deref for BlockRef<'ctx> {
+    type Target = Block<'ctx>;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
 impl<'ctx> BlockRef<'ctx> {
     pub unsafe fn wrap(handle: MlirBlock, phantom: PhantomData<&'ctx Context>) -> Self {
         Self { handle, phantom }
@@ -64,75 +72,90 @@ impl<'ctx> Block<'ctx> {
         }
     }
     pub fn detach(self) {}
-    pub fn is_null(self) -> bool {
+    pub fn is_null(&self) -> bool {
         self.handle.ptr == std::ptr::null_mut()
     }
-    pub fn get_parent_operation(self) -> OperationRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetParentOperation(&self) };
+    pub fn get_parent_operation(&self) -> OperationRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetParentOperation(self) };
         // FIXME: block has no context
         unsafe { OperationRef::from_handle_and_phantom(handle, PhantomData::default()) }
     }
-    pub fn get_parent_region(self) -> RegionRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetParentRegion(&self) };
+    pub fn get_parent_region(&self) -> RegionRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetParentRegion(self) };
         unsafe { RegionRef::wrap(handle, self.phantom) }
     }
-    pub fn get_next_in_region(self) -> BlockRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetNextInRegion(&self) };
+    pub fn get_next_in_region(&self) -> BlockRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetNextInRegion(self) };
         unsafe { BlockRef::wrap(handle, self.phantom) }
     }
-    pub fn get_first_operation(self) -> OperationRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetFirstOperation(&self) };
+    pub fn get_first_operation(&self) -> OperationRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetFirstOperation(self) };
         unsafe { OperationRef::from_handle_and_phantom(handle, PhantomData::default()) }
     }
-    pub fn get_terminator(self) -> OperationRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetTerminator(&self) };
+    /// The operations currently in the block. The list is collected upfront, so the block may
+    /// be modified while iterating.
+    pub fn operations(&self) -> std::vec::IntoIter<OperationRef<'ctx>> {
+        let mut ops = vec![];
+        let mut op = self.get_first_operation();
+        while !op.is_null() {
+            ops.push(op);
+            op = op.get_next_in_block();
+        }
+        ops.into_iter()
+    }
+    pub fn get_terminator(&self) -> OperationRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetTerminator(self) };
         unsafe { OperationRef::from_handle_and_phantom(handle, PhantomData::default()) }
     }
-    pub fn append_owned_operation(self, op: Operation<'ctx>) {
+    pub fn append_owned_operation(&self, op: Operation<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirBlockAppendOwnedOperation(&self, &op);
+            mlir_capi::IR::FFIVoid_::mlirBlockAppendOwnedOperation(self, &op);
         }
         std::mem::forget(op);
     }
-    pub fn insert_owned_operation(self, pos: usize, op: Operation<'ctx>) {
+    pub fn insert_owned_operation(&self, pos: usize, op: Operation<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirBlockInsertOwnedOperation(&self, pos as i64, &op);
+            mlir_capi::IR::FFIVoid_::mlirBlockInsertOwnedOperation(self, pos as i64, &op);
         }
         std::mem::forget(op)
     }
-    pub fn insert_owned_operation_after(self, reference: Operation<'ctx>, op: Operation<'ctx>) {
+    pub fn insert_owned_operation_after(&self, reference: &Operation<'ctx>, op: Operation<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirBlockInsertOwnedOperationAfter(&self, &reference, &op);
+            mlir_capi::IR::FFIVoid_::mlirBlockInsertOwnedOperationAfter(self, reference, &op);
         }
         std::mem::forget(op)
     }
-    pub fn insert_owned_operation_before(self, reference: Operation<'ctx>, op: Operation<'ctx>) {
+    pub fn insert_owned_operation_before(&self, reference: &Operation<'ctx>, op: Operation<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirBlockInsertOwnedOperationBefore(&self, &reference, &op);
+            mlir_capi::IR::FFIVoid_::mlirBlockInsertOwnedOperationBefore(self, reference, &op);
         }
         std::mem::forget(op)
     }
-    pub fn get_num_arguments(self) -> usize {
-        (unsafe { mlir_capi::IR::FFIVal_::<i64>::mlirBlockGetNumArguments(&self) }) as usize
+    pub fn get_num_arguments(&self) -> usize {
+        (unsafe { mlir_capi::IR::FFIVal_::<i64>::mlirBlockGetNumArguments(self) }) as usize
     }
-    pub fn add_argument(self, arg_type: Type<'ctx>, loc: Location<'ctx>) -> Value<'ctx> {
-        unsafe { mlir_capi::IR::FFIVal_::mlirBlockAddArgument(&self, arg_type, loc) }
+    pub fn add_argument(&self, arg_type: Type<'ctx>, loc: Location<'ctx>) -> Value<'ctx> {
+        unsafe { mlir_capi::IR::FFIVal_::mlirBlockAddArgument(self, arg_type, loc) }
     }
-    pub fn erase_argument(self, pos: usize) {
+    pub fn erase_argument(&self, pos: usize) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirBlockEraseArgument(&self, pos as u32);
+            mlir_capi::IR::FFIVoid_::mlirBlockEraseArgument(self, pos as u32);
         }
     }
     pub fn insert_argument(
-        self,
+        &self,
         pos: usize,
         arg_type: Type<'ctx>,
         loc: Location<'ctx>,
     ) -> Value<'ctx> {
-        unsafe { mlir_capi::IR::FFIVal_::mlirBlockInsertArgument(&self, pos as i64, arg_type, loc) }
+        unsafe { mlir_capi::IR::FFIVal_::mlirBlockInsertArgument(self, pos as i64, arg_type, loc) }
+    }
+    pub fn get_argument(&self, pos: usize) -> Value<'ctx> {
+        unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetArgument(self, pos as i64) }
     }
-    pub fn get_argument(self, pos: usize) -> Value<'ctx> {
-        unsafe { mlir_capi::IR::FFIVal_::mlirBlockGetArgument(&self, pos as i64) }
+    pub fn arguments(&self) -> impl DoubleEndedIterator<Item = Value<'ctx>> + ExactSizeIterator {
+        let block = unsafe { BlockRef::wrap(self.handle, self.phantom) };
+        (0..self.get_num_arguments()).map(move |pos| block.get_argument(pos))
     }
 }
 
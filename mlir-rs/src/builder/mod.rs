@@ -1 +1,303 @@
+use crate::attribute::*;
+use crate::block::*;
+use crate::context::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::operation_state::*;
+use crate::r#type::*;
+use crate::region::*;
+use crate::value::*;
 
+use std::ops::{Deref, DerefMut};
+
+/// Where an `OpBuilder` inserts the operations it creates: before `before`, or at the end of
+/// `block` if `before` is `None`.
+#[derive(Copy, Clone)]
+pub struct InsertionPoint<'ctx> {
+    pub block: BlockRef<'ctx>,
+    pub before: Option<OperationRef<'ctx>>,
+}
+
+impl<'ctx> InsertionPoint<'ctx> {
+    pub fn at_block_begin(block: BlockRef<'ctx>) -> Self {
+        let first = block.get_first_operation();
+        Self {
+            block,
+            before: if first.is_null() { None } else { Some(first) },
+        }
+    }
+    pub fn at_block_end(block: BlockRef<'ctx>) -> Self {
+        Self {
+            block,
+            before: None,
+        }
+    }
+    pub fn before(op: OperationRef<'ctx>) -> Self {
+        Self {
+            block: op.get_block(),
+            before: Some(op),
+        }
+    }
+    pub fn after(op: OperationRef<'ctx>) -> Self {
+        let next = op.get_next_in_block();
+        Self {
+            block: op.get_block(),
+            before: if next.is_null() { None } else { Some(next) },
+        }
+    }
+}
+
+pub struct OpBuilder<'ctx> {
+    ctx: &'ctx Context,
+    insertion_point: Option<InsertionPoint<'ctx>>,
+}
+
+impl<'ctx> OpBuilder<'ctx> {
+    /// Creates a builder without insertion point.
+    pub fn new(ctx: &'ctx Context) -> Self {
+        Self {
+            ctx,
+            insertion_point: None,
+        }
+    }
+    pub fn at_block_begin(ctx: &'ctx Context, block: BlockRef<'ctx>) -> Self {
+        Self {
+            ctx,
+            insertion_point: Some(InsertionPoint::at_block_begin(block)),
+        }
+    }
+    pub fn at_block_end(ctx: &'ctx Context, block: BlockRef<'ctx>) -> Self {
+        Self {
+            ctx,
+            insertion_point: Some(InsertionPoint::at_block_end(block)),
+        }
+    }
+    pub fn get_context(&self) -> &'ctx Context {
+        self.ctx
+    }
+    pub fn get_unknown_loc(&self) -> Location<'ctx> {
+        Location::unknown_get(self.ctx)
+    }
+
+    pub fn get_insertion_point(&self) -> Option<InsertionPoint<'ctx>> {
+        self.insertion_point
+    }
+    pub fn get_insertion_block(&self) -> Option<BlockRef<'ctx>> {
+        self.insertion_point.map(|ip| ip.block)
+    }
+    pub fn restore_insertion_point(&mut self, ip: Option<InsertionPoint<'ctx>>) {
+        self.insertion_point = ip;
+    }
+    pub fn clear_insertion_point(&mut self) {
+        self.insertion_point = None;
+    }
+    pub fn set_insertion_point_to_start(&mut self, block: BlockRef<'ctx>) {
+        self.insertion_point = Some(InsertionPoint::at_block_begin(block));
+    }
+    pub fn set_insertion_point_to_end(&mut self, block: BlockRef<'ctx>) {
+        self.insertion_point = Some(InsertionPoint::at_block_end(block));
+    }
+    /// Sets the insertion point to right before `op`.
+    pub fn set_insertion_point(&mut self, op: OperationRef<'ctx>) {
+        self.insertion_point = Some(InsertionPoint::before(op));
+    }
+    pub fn set_insertion_point_after(&mut self, op: OperationRef<'ctx>) {
+        self.insertion_point = Some(InsertionPoint::after(op));
+    }
+    /// Returns a guard through which the builder can be used, the insertion point at the time
+    /// of the call is restored when the guard is dropped.
+    pub fn insertion_guard<'b>(&'b mut self) -> InsertionGuard<'b, 'ctx> {
+        let saved = self.insertion_point;
+        InsertionGuard {
+            builder: self,
+            saved,
+        }
+    }
+
+    /// Inserts `op` at the insertion point, panics if there is none.
+    pub fn insert(&self, op: Operation<'ctx>) -> OperationRef<'ctx> {
+        let ip = self
+            .insertion_point
+            .expect("OpBuilder has no insertion point");
+        let op_ref = OperationRef::from_handle_same_context(op.handle, &op);
+        match ip.before {
+            Some(before) => ip.block.insert_owned_operation_before(&before, op),
+            None => ip.block.append_owned_operation(op),
+        }
+        op_ref
+    }
+    /// Creates an operation from `state` and inserts it at the insertion point.
+    pub fn create(&self, state: &OperationState<'ctx>) -> OperationRef<'ctx> {
+        let op = Operation::create(state);
+        assert!(!op.is_null(), "failed to create operation");
+        self.insert(op)
+    }
+    /// Creates an operation without regions or successors and inserts it at the insertion
+    /// point.
+    pub fn create_operation(
+        &self,
+        name: &'ctx str,
+        loc: Location<'ctx>,
+        operands: &[Value<'ctx>],
+        results: &[Type<'ctx>],
+        attributes: &[NamedAttr<'ctx>],
+    ) -> OperationRef<'ctx> {
+        let mut state = OperationState::get(name, loc);
+        state
+            .add_operands(operands)
+            .add_results(results)
+            .add_attributes(attributes);
+        self.create(&state)
+    }
+    /// Creates an operation from `state` without inserting it anywhere.
+    pub fn create_detached(&self, state: &OperationState<'ctx>) -> Operation<'ctx> {
+        Operation::create(state)
+    }
+
+    /// Appends a new block with the given arguments to `region`, and sets the insertion point
+    /// to the end of it.
+    pub fn create_block(
+        &mut self,
+        region: RegionRef<'ctx>,
+        arg_types: &[Type<'ctx>],
+        arg_locs: &[Location<'ctx>],
+    ) -> BlockRef<'ctx> {
+        let block = Self::create_owned_block(arg_types, arg_locs);
+        let block_ref = unsafe { BlockRef::wrap(block.handle, Default::default()) };
+        region.append_owned_block(block);
+        self.set_insertion_point_to_end(block_ref);
+        block_ref
+    }
+    /// Inserts a new block with the given arguments right before `before`, and sets the
+    /// insertion point to the end of it.
+    pub fn create_block_before(
+        &mut self,
+        before: BlockRef<'ctx>,
+        arg_types: &[Type<'ctx>],
+        arg_locs: &[Location<'ctx>],
+    ) -> BlockRef<'ctx> {
+        let block = Self::create_owned_block(arg_types, arg_locs);
+        let block_ref = unsafe { BlockRef::wrap(block.handle, Default::default()) };
+        before
+            .get_parent_region()
+            .insert_owned_block_before(&before, block);
+        self.set_insertion_point_to_end(block_ref);
+        block_ref
+    }
+    fn create_owned_block(arg_types: &[Type<'ctx>], arg_locs: &[Location<'ctx>]) -> Block<'ctx> {
+        assert_eq!(
+            arg_types.len(),
+            arg_locs.len(),
+            "every block argument needs a location"
+        );
+        Block::create(arg_types, arg_locs)
+    }
+}
+
+pub struct InsertionGuard<'b, 'ctx> {
+    builder: &'b mut OpBuilder<'ctx>,
+    saved: Option<InsertionPoint<'ctx>>,
+}
+
+impl<'b, 'ctx> Deref for InsertionGuard<'b, 'ctx> {
+    type Target = OpBuilder<'ctx>;
+    fn deref(&self) -> &Self::Target {
+        self.builder
+    }
+}
+
+impl<'b, 'ctx> DerefMut for InsertionGuard<'b, 'ctx> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.builder
+    }
+}
+
+impl<'b, 'ctx> Drop for InsertionGuard<'b, 'ctx> {
+    fn drop(&mut self) {
+        self.builder.restore_insertion_point(self.saved);
+    }
+}
+
+#[cfg(test)]
+mod builder_test {
+    use super::*;
+    use crate::module::*;
+
+    fn get_names<'ctx>(block: BlockRef<'ctx>) -> Vec<&'ctx str> {
+        block.operations().map(|op| op.get_name().str()).collect()
+    }
+
+    #[test]
+    fn insertion_point() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let body = module.get_body();
+        let mut builder = OpBuilder::at_block_end(&ctx, body);
+        let b = builder.create(&OperationState::get("test.b", loc));
+        let d = builder.create(&OperationState::get("test.d", loc));
+        builder.set_insertion_point_to_start(body);
+        builder.create(&OperationState::get("test.a", loc));
+        builder.set_insertion_point_after(b);
+        builder.create(&OperationState::get("test.c", loc));
+        builder.set_insertion_point(d);
+        builder.create(&OperationState::get("test.c2", loc));
+        builder.set_insertion_point_to_end(body);
+        builder.create(&OperationState::get("test.e", loc));
+        assert_eq!(
+            get_names(body),
+            vec!["test.a", "test.b", "test.c", "test.c2", "test.d", "test.e"]
+        );
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+
+    #[test]
+    fn insertion_guard() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let body = module.get_body();
+        let mut builder = OpBuilder::at_block_end(&ctx, body);
+        let a = builder.create(&OperationState::get("test.a", loc));
+        {
+            let mut guard = builder.insertion_guard();
+            guard.set_insertion_point(a);
+            guard.create(&OperationState::get("test.before_a", loc));
+        }
+        builder.create(&OperationState::get("test.b", loc));
+        assert_eq!(get_names(body), vec!["test.before_a", "test.a", "test.b"]);
+        builder.clear_insertion_point();
+        assert!(builder.get_insertion_block().is_none());
+        let detached = builder.create_detached(&OperationState::get("test.c", loc));
+        assert!(!detached.is_null());
+    }
+
+    #[test]
+    fn create_block() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let mut builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let mut state = OperationState::get("test.region_op", loc);
+        state.add_owned_regions(vec![Region::create()]);
+        let region_op = builder.create(&state);
+        let region = region_op.get_region(0);
+
+        let i32_ty = IntegerType::get(&ctx, 32);
+        let exit = builder.create_block(region, &[i32_ty.into()], &[loc]);
+        assert_eq!(exit.get_num_arguments(), 1);
+        builder.create(&OperationState::get("test.exit", loc));
+        let entry = builder.create_block_before(exit, &[], &[]);
+        assert!(*builder.get_insertion_block().unwrap() == *entry);
+        builder.create(&OperationState::get("test.entry", loc));
+
+        let blocks: Vec<_> = region.blocks().collect();
+        assert_eq!(blocks.len(), 2);
+        assert!(*blocks[0] == *entry);
+        assert_eq!(get_names(entry), vec!["test.entry"]);
+        assert_eq!(get_names(exit), vec!["test.exit"]);
+    }
+}
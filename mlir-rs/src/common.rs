@@ -55,3 +55,20 @@ impl<'a, 'b> PrintCallback for PrintToFormatter<'a, 'b> {
         self.formatter.write_str(s).unwrap();
     }
 }
+
+#[derive(Default)]
+pub struct PrintToString {
+    pub s: String,
+}
+
+impl PrintToString {
+    pub fn new() -> Self {
+        Self { s: String::new() }
+    }
+}
+
+impl PrintCallback for PrintToString {
+    fn print(&mut self, s: &str) {
+        self.s.push_str(s);
+    }
+}
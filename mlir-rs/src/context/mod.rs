@@ -1,5 +1,6 @@
 use crate::common;
 use crate::common::{to_cbool, to_rbool, to_string_ref};
+use crate::diagnostic::*;
 use crate::dialect::Dialect;
 use crate::dialect::DialectRegistry;
 use mlir_capi::IR::MlirContext;
@@ -89,6 +90,41 @@ impl Context {
         to_rbool(res)
     }
     // FIXME: support mlirContextSetThreadPool
+
+    /// Attaches a handler that is invoked for every diagnostic emitted in this context. The
+    /// handler returns whether it handled the diagnostic, an unhandled diagnostic is passed on
+    /// to the previously attached handlers. Handlers are invoked in reverse order of attachment.
+    ///
+    /// The guard may be leaked, in which case the context keeps the handler until it is
+    /// destroyed, so the handler must not borrow anything. See `with_diagnostic_handler` for a
+    /// handler borrowing from the caller.
+    pub fn attach_diagnostic_handler<'ctx, F>(
+        &'ctx self,
+        handler: F,
+    ) -> DiagnosticHandlerGuard<'ctx>
+    where
+        F: FnMut(Diagnostic<'ctx>) -> bool + 'static,
+    {
+        let ctx =
+            unsafe { ContextRef::from_handle_and_phantom(self.handle, PhantomData::default()) };
+        DiagnosticHandlerGuard::attach(ctx, handler)
+    }
+    /// Runs `body` with `handler` attached like `attach_diagnostic_handler`, the handler is
+    /// detached before returning, so it may borrow from the caller.
+    pub fn with_diagnostic_handler<'ctx, F, R>(
+        &'ctx self,
+        handler: F,
+        body: impl FnOnce() -> R,
+    ) -> R
+    where
+        F: FnMut(Diagnostic<'ctx>) -> bool + 'ctx,
+    {
+        let ctx =
+            unsafe { ContextRef::from_handle_and_phantom(self.handle, PhantomData::default()) };
+        // The guard cannot escape this function, so it is dropped even if `body` unwinds.
+        let _guard = unsafe { DiagnosticHandlerGuard::attach_unchecked(ctx, handler) };
+        body()
+    }
 }
 
 impl Drop for Context {
@@ -222,9 +258,9 @@ pub mod context_test {
 
     #[test]
     fn create_with_registry() {
-        let amdgpu_handle = get_handle_for_upstream_dialect(UpstreamDialectName::AMDGPU);
-        let arith_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Arith);
-        let vector_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Vector);
+        let amdgpu_handle = get_handle_for_upstream_dialect(UpstreamDialectName::AMDGPU).unwrap();
+        let arith_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Arith).unwrap();
+        let vector_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Vector).unwrap();
         let reg1 = DialectRegistry::create();
         amdgpu_handle.insert_dialect(&reg1);
         arith_handle.insert_dialect(&reg1);
@@ -254,7 +290,7 @@ pub mod context_test {
 
     #[test]
     fn num_registered_dialects() {
-        let async_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Async);
+        let async_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Async).unwrap();
         let ctx = Context::create();
         assert!(1 == ctx.get_num_registered_dialects());
         async_handle.register_dialect(&ctx);
@@ -263,8 +299,8 @@ pub mod context_test {
 
     #[test]
     fn get_or_load_dialect() {
-        let amdgpu_handle = get_handle_for_upstream_dialect(UpstreamDialectName::AMDGPU);
-        let arith_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Arith);
+        let amdgpu_handle = get_handle_for_upstream_dialect(UpstreamDialectName::AMDGPU).unwrap();
+        let arith_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Arith).unwrap();
         let reg = DialectRegistry::create();
         amdgpu_handle.insert_dialect(&reg);
         arith_handle.insert_dialect(&reg);
@@ -285,7 +321,7 @@ pub mod context_test {
         let ctx = Context::create();
         assert!(!ctx.is_registered_operation("vector.load"));
         assert!(!ctx.is_registered_operation("vector.foobar"));
-        let vector_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Vector);
+        let vector_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Vector).unwrap();
         vector_handle.load_dialect(&ctx);
         ctx.load_all_available_dialects();
         assert!(ctx.is_registered_operation("vector.load"));
@@ -297,7 +333,7 @@ pub mod context_test {
         let ctx = Context::create();
         assert!(!ctx.is_registered_operation("func.func"));
         assert!(!ctx.is_registered_operation("func.foobar"));
-        let func_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Func);
+        let func_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Func).unwrap();
         func_handle.register_dialect(&ctx);
         assert!(!ctx.is_registered_operation("func.func"));
         assert!(!ctx.is_registered_operation("func.foobar"));
@@ -310,7 +346,7 @@ pub mod context_test {
     pub fn create_context_with_all_upstream_dialects() -> Context {
         let ctx = Context::create();
         for info in get_all_dialect_info() {
-            get_handle_for_upstream_dialect(info.0).load_dialect(&ctx);
+            info.0.load_dialect(&ctx);
         }
         ctx
     }
@@ -0,0 +1,427 @@
+use crate::common::*;
+use crate::context::*;
+use crate::location::*;
+use crate::support::*;
+
+use mlir_capi::Diagnostics as MLIR_Diagnostics;
+use mlir_capi::Diagnostics::*;
+use mlir_capi::Support::MlirLogicalResult;
+
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Note,
+    Remark,
+}
+
+impl Display for DiagnosticSeverity {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        let s = match self {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Note => "note",
+            DiagnosticSeverity::Remark => "remark",
+        };
+        formatter.write_str(s)
+    }
+}
+
+/// A diagnostic reported by MLIR. The underlying `MlirDiagnostic` is only valid while the
+/// handler runs, so its content is copied out before the handler is invoked.
+#[derive(Clone)]
+pub struct Diagnostic<'ctx> {
+    pub severity: DiagnosticSeverity,
+    pub location: Location<'ctx>,
+    pub message: String,
+    pub notes: Vec<Diagnostic<'ctx>>,
+}
+
+impl<'ctx> Diagnostic<'ctx> {
+    /// Copies out the content of `diag`, which must be a valid diagnostic.
+    pub unsafe fn from_raw(diag: MlirDiagnostic) -> Self {
+        let severity =
+            match MLIR_Diagnostics::FFIVal_::<MlirDiagnosticSeverity>::mlirDiagnosticGetSeverity(
+                diag,
+            ) as u32
+            {
+                0 => DiagnosticSeverity::Error,
+                1 => DiagnosticSeverity::Warning,
+                2 => DiagnosticSeverity::Note,
+                _ => DiagnosticSeverity::Remark,
+            };
+        let location_handle = MLIR_Diagnostics::FFIVal_::mlirDiagnosticGetLocation(diag);
+        let location = Location::from_handle_and_phantom(location_handle, PhantomData::default());
+        let mut printer = PrintToString::new();
+        let callback: &mut dyn PrintCallback = &mut printer;
+        MLIR_Diagnostics::FFIVoid_::mlirDiagnosticPrint(
+            diag,
+            print_helper as *mut _,
+            &callback as *const &mut dyn PrintCallback as *mut _,
+        );
+        let num_notes = MLIR_Diagnostics::FFIVal_::<i64>::mlirDiagnosticGetNumNotes(diag);
+        let notes = (0..num_notes)
+            .map(|pos| Self::from_raw(MLIR_Diagnostics::FFIVal_::mlirDiagnosticGetNote(diag, pos)))
+            .collect();
+        Self {
+            severity,
+            location,
+            message: printer.s,
+            notes,
+        }
+    }
+}
+
+impl<'ctx> Display for Diagnostic<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            formatter,
+            "{}: {}: {}",
+            self.location, self.severity, self.message
+        )?;
+        for note in &self.notes {
+            write!(formatter, "\n{}", note)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'ctx> Debug for Diagnostic<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        Display::fmt(self, formatter)
+    }
+}
+
+/// Detaches the diagnostic handler from the context when dropped.
+pub struct DiagnosticHandlerGuard<'ctx> {
+    ctx: ContextRef<'ctx>,
+    pub id: MlirDiagnosticHandlerID,
+}
+
+impl<'ctx> Drop for DiagnosticHandlerGuard<'ctx> {
+    fn drop(&mut self) {
+        unsafe {
+            MLIR_Diagnostics::FFIVoid_::mlirContextDetachDiagnosticHandler(self.ctx, self.id);
+        }
+    }
+}
+
+impl<'ctx> DiagnosticHandlerGuard<'ctx> {
+    pub fn attach<F>(ctx: ContextRef<'ctx>, handler: F) -> Self
+    where
+        F: FnMut(Diagnostic<'ctx>) -> bool + 'static,
+    {
+        unsafe { Self::attach_unchecked(ctx, handler) }
+    }
+    /// # Safety
+    ///
+    /// Leaking the guard leaves the handler attached to the context, the caller must make sure
+    /// that the guard is dropped before anything borrowed by `handler` goes away.
+    pub(crate) unsafe fn attach_unchecked<F>(ctx: ContextRef<'ctx>, handler: F) -> Self
+    where
+        F: FnMut(Diagnostic<'ctx>) -> bool + 'ctx,
+    {
+        let user_data = Box::into_raw(Box::new(handler));
+        let id = unsafe {
+            MLIR_Diagnostics::FFIVal_::mlirContextAttachDiagnosticHandler(
+                ctx,
+                diagnostic_handler::<F> as *mut _,
+                user_data as *mut c_void,
+                delete_diagnostic_handler::<F> as *mut _,
+            )
+        };
+        Self { ctx, id }
+    }
+    pub fn get_context(&self) -> ContextRef<'ctx> {
+        self.ctx
+    }
+}
+
+unsafe extern "C" fn diagnostic_handler<'ctx, F: FnMut(Diagnostic<'ctx>) -> bool>(
+    diag: MlirDiagnostic,
+    user_data: *mut c_void,
+) -> MlirLogicalResult {
+    let handler = (user_data as *mut F).as_mut_unchecked();
+    if handler(Diagnostic::from_raw(diag)) {
+        LogicalResult::success().handle
+    } else {
+        LogicalResult::failure().handle
+    }
+}
+
+unsafe extern "C" fn delete_diagnostic_handler<'ctx, F: FnMut(Diagnostic<'ctx>) -> bool>(
+    user_data: *mut c_void,
+) {
+    drop(Box::from_raw(user_data as *mut F));
+}
+
+/// The diagnostics emitted by a failed operation.
+#[derive(Clone, Default)]
+pub struct Diagnostics<'ctx> {
+    pub diagnostics: Vec<Diagnostic<'ctx>>,
+}
+
+impl<'ctx> Diagnostics<'ctx> {
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic<'ctx>> {
+        self.diagnostics.iter()
+    }
+    pub fn has_errors(&self) -> bool {
+        self.iter()
+            .any(|diag| diag.severity == DiagnosticSeverity::Error)
+    }
+}
+
+impl<'ctx> IntoIterator for Diagnostics<'ctx> {
+    type Item = Diagnostic<'ctx>;
+    type IntoIter = std::vec::IntoIter<Diagnostic<'ctx>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.into_iter()
+    }
+}
+
+impl<'ctx> Display for Diagnostics<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for (i, diag) in self.iter().enumerate() {
+            if i != 0 {
+                formatter.write_str("\n")?;
+            }
+            Display::fmt(diag, formatter)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'ctx> Debug for Diagnostics<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        Display::fmt(self, formatter)
+    }
+}
+
+impl<'ctx> std::error::Error for Diagnostics<'ctx> {}
+
+/// Collects all diagnostics emitted in a context while it is alive, preventing them from
+/// reaching the handlers attached earlier.
+pub struct ScopedDiagnosticCollector<'ctx> {
+    diagnostics: Rc<RefCell<Vec<Diagnostic<'ctx>>>>,
+    _guard: DiagnosticHandlerGuard<'ctx>,
+}
+
+impl<'ctx> ScopedDiagnosticCollector<'ctx> {
+    pub fn new(ctx: &'ctx Context) -> Self {
+        let ctx =
+            unsafe { ContextRef::from_handle_and_phantom(ctx.handle, PhantomData::default()) };
+        Self::new_for_context_ref(ctx)
+    }
+    /// Collects the diagnostics of the context `obj` belongs to.
+    pub fn new_for<T: HandleWithContext<'ctx>>(obj: &T) -> Self {
+        let ctx = ContextRef::from_handle_same_context(obj.get_context_handle(), obj);
+        Self::new_for_context_ref(ctx)
+    }
+    pub fn new_for_context_ref(ctx: ContextRef<'ctx>) -> Self {
+        let diagnostics = Rc::new(RefCell::new(vec![]));
+        let collected = diagnostics.clone();
+        // The handler only owns a reference count, so it stays valid even if the collector is
+        // leaked.
+        let guard = unsafe {
+            DiagnosticHandlerGuard::attach_unchecked(ctx, move |diag| {
+                collected.borrow_mut().push(diag);
+                true
+            })
+        };
+        Self {
+            diagnostics,
+            _guard: guard,
+        }
+    }
+    /// Takes the diagnostics collected so far.
+    pub fn take(&self) -> Diagnostics<'ctx> {
+        Diagnostics {
+            diagnostics: std::mem::take(&mut *self.diagnostics.borrow_mut()),
+        }
+    }
+    pub fn into_diagnostics(self) -> Diagnostics<'ctx> {
+        self.take()
+    }
+}
+
+#[cfg(test)]
+mod diagnostic_test {
+    use super::*;
+    use crate::attribute::*;
+    use crate::module::*;
+    use crate::operation::*;
+    use crate::operation_state::*;
+    use crate::r#type::*;
+
+    #[test]
+    fn attach_diagnostic_handler() {
+        let ctx = Context::create();
+        let messages = Rc::new(RefCell::new(vec![]));
+        {
+            let collected = messages.clone();
+            let _guard = ctx.attach_diagnostic_handler(move |diag| {
+                assert_eq!(diag.severity, DiagnosticSeverity::Error);
+                collected.borrow_mut().push(diag.message);
+                true
+            });
+            let loc = Location::file_line_col_get(&ctx, "foo.mlir", 3, 7);
+            loc.emit_error("something went wrong");
+        }
+        assert_eq!(*messages.borrow(), vec!["something went wrong".to_string()]);
+    }
+
+    #[test]
+    fn location() {
+        let ctx = Context::create();
+        let diags = RefCell::new(vec![]);
+        let loc = Location::file_line_col_get(&ctx, "foo.mlir", 3, 7);
+        ctx.with_diagnostic_handler(
+            |diag| {
+                diags.borrow_mut().push(diag);
+                true
+            },
+            || loc.emit_error("bad"),
+        );
+        let diags = diags.borrow();
+        assert_eq!(diags.len(), 1);
+        assert!(diags[0].location == loc);
+        assert!(diags[0].notes.is_empty());
+        assert_eq!(format!("{}", diags[0]), "loc(\"foo.mlir\":3:7): error: bad");
+    }
+
+    #[test]
+    fn parse_error() {
+        let ctx = Context::create();
+        let diags = RefCell::new(vec![]);
+        let op = ctx.with_diagnostic_handler(
+            |diag| {
+                diags.borrow_mut().push(diag);
+                true
+            },
+            || Operation::create_parse(&ctx, "%0 = ", "parse_error"),
+        );
+        assert!(op.is_null());
+        let diags = diags.borrow();
+        assert!(!diags.is_empty());
+        assert_eq!(diags[0].severity, DiagnosticSeverity::Error);
+        assert!(!diags[0].message.is_empty());
+    }
+
+    #[test]
+    fn nested_handlers() {
+        let ctx = Context::create();
+        let outer = Rc::new(RefCell::new(0));
+        let inner = Rc::new(RefCell::new(0));
+        let outer_count = outer.clone();
+        let _outer_guard = ctx.attach_diagnostic_handler(move |_| {
+            *outer_count.borrow_mut() += 1;
+            true
+        });
+        let loc = Location::unknown_get(&ctx);
+        {
+            let inner_count = inner.clone();
+            let _inner_guard = ctx.attach_diagnostic_handler(move |_| {
+                *inner_count.borrow_mut() += 1;
+                false
+            });
+            loc.emit_error("passed on");
+        }
+        loc.emit_error("inner handler detached");
+        assert_eq!(*inner.borrow(), 1);
+        assert_eq!(*outer.borrow(), 2);
+    }
+
+    #[test]
+    fn leaked_guard() {
+        let ctx = Context::create();
+        let count = Rc::new(RefCell::new(0));
+        let handler_count = count.clone();
+        std::mem::forget(ctx.attach_diagnostic_handler(move |_| {
+            *handler_count.borrow_mut() += 1;
+            true
+        }));
+        Location::unknown_get(&ctx).emit_error("still handled");
+        assert_eq!(*count.borrow(), 1);
+        drop(ctx);
+        assert_eq!(Rc::strong_count(&count), 1);
+    }
+
+    #[test]
+    fn scoped_diagnostic_collector() {
+        let ctx = Context::create();
+        let loc = Location::file_line_col_get(&ctx, "foo.mlir", 1, 2);
+        let outer = RefCell::new(0);
+        ctx.with_diagnostic_handler(
+            |_| {
+                *outer.borrow_mut() += 1;
+                true
+            },
+            || {
+                {
+                    let collector = ScopedDiagnosticCollector::new(&ctx);
+                    loc.emit_error("first");
+                    loc.emit_error("second");
+                    let diags = collector.take();
+                    assert_eq!(diags.len(), 2);
+                    assert!(diags.has_errors());
+                    assert_eq!(
+                        format!("{}", diags),
+                        "loc(\"foo.mlir\":1:2): error: first\nloc(\"foo.mlir\":1:2): error: second"
+                    );
+                    assert!(collector.into_diagnostics().is_empty());
+                }
+                assert_eq!(*outer.borrow(), 0);
+                loc.emit_error("third");
+            },
+        );
+        assert_eq!(*outer.borrow(), 1);
+    }
+
+    #[test]
+    fn parse_module_error() {
+        let ctx = Context::create();
+        let err = Module::create_parse(&ctx, "module { %0 = }").unwrap_err();
+        assert!(err.has_errors());
+        assert!(!err.diagnostics[0].message.is_empty());
+        assert!(Module::create_parse(&ctx, "module {}").is_ok());
+    }
+
+    #[test]
+    fn fallible_constructors() {
+        let ctx = Context::create();
+        let loc = Location::unknown_get(&ctx);
+        assert!(Type::parse(&ctx, "not_a_type").unwrap_err().has_errors());
+        assert!(Attr::parse(&ctx, "[1, ").unwrap_err().has_errors());
+        let f32_ty = FloatType::get(&ctx, FloatKind::F32);
+        let err = VectorType::get_checked(loc, &[-2], f32_ty.into()).unwrap_err();
+        assert!(err.has_errors());
+        assert!(VectorType::get_checked(loc, &[2], f32_ty.into()).is_ok());
+        let i32_ty = IntegerType::get(&ctx, 32);
+        assert!(FloatAttr::f64_get_checked(loc, i32_ty.into(), 1.0).is_err());
+    }
+
+    #[test]
+    fn verify() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let module = Module::create_parse(&ctx, "module {}").unwrap();
+        assert!(module.get_operation_ref().verify().is_ok());
+        // builtin.module requires exactly one region.
+        let state = OperationState::get("builtin.module", Location::unknown_get(&ctx));
+        let op = Operation::create(&state);
+        let err = op.verify().unwrap_err();
+        assert!(err.has_errors());
+    }
+}
@@ -0,0 +1,687 @@
+use super::{
+    append_block, create_region_op, get_operand_segment_sizes, read_segment_sizes, Dialect,
+    DialectRegistry,
+};
+use crate::affine_map::*;
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::context::*;
+use crate::integer_set::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+/// Loads the affine dialect in `ctx`. The C API has no dialect handle for it, so it is loaded by
+/// name. If it is not registered in `ctx` yet, all upstream dialects are registered first: this
+/// makes the other upstream dialects available in `ctx` too, but does not load them.
+pub fn load_dialect(ctx: &Context) -> Dialect {
+    let dialect = ctx.get_or_load_dialect("affine");
+    if !dialect.is_null() {
+        return dialect;
+    }
+    let registry = DialectRegistry::create();
+    registry.insert_all_upstream_dialects();
+    ctx.append_dialect_registry(&registry);
+    ctx.get_or_load_dialect("affine")
+}
+
+fn get_types<'ctx>(values: &[Value<'ctx>]) -> Vec<Type<'ctx>> {
+    values.iter().map(|value| value.get_type()).collect()
+}
+
+/// Checks that `operands` provide one value for each dim and symbol of `map`.
+fn check_map_operands(map: AffineMap, operands: &[Value]) -> Result<(), String> {
+    if map.get_num_inputs() != operands.len() {
+        return Err(format!(
+            "expected {} operands for {}, got {}",
+            map.get_num_inputs(),
+            map,
+            operands.len()
+        ));
+    }
+    Ok(())
+}
+
+fn get_map<'ctx>(op: OperationRef<'ctx>, attr_name: &str) -> AffineMap<'ctx> {
+    let attr: AffineMapAttr = op.get_attr_by_name(attr_name).dyn_cast();
+    attr.get_value()
+}
+
+fn get_memref_type<'ctx>(value: Value<'ctx>) -> Result<MemRefType<'ctx>, String> {
+    let ty: MemRefType = value.get_type().dyn_cast();
+    if ty.is_null() {
+        Err(format!("expected a memref, got {}", value.get_type()))
+    } else {
+        Ok(ty)
+    }
+}
+
+/// Returns the access map of affine memory ops, which defaults to the identity of the rank of
+/// `ty`.
+fn get_access_map<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    ty: MemRefType<'ctx>,
+    map: Option<AffineMap<'ctx>>,
+    map_operands: &[Value<'ctx>],
+) -> Result<AffineMap<'ctx>, String> {
+    let rank = ty.get_rank() as usize;
+    let map = map.unwrap_or_else(|| AffineMap::multi_dim_identity_get(builder.get_context(), rank));
+    if map.get_num_results() != rank {
+        return Err(format!("expected a map with {} results, got {}", rank, map));
+    }
+    check_map_operands(map, map_operands)?;
+    Ok(map)
+}
+
+define_op_view!(YieldOp, "affine.yield");
+
+impl<'ctx> YieldOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, values: &[Value<'ctx>]) -> Self {
+        Self {
+            op: builder.create_operation("affine.yield", loc, values, &[], &[]),
+        }
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+define_op_view!(ForOp, "affine.for");
+
+impl<'ctx> ForOp<'ctx> {
+    /// Creates an `affine.for` from the maximum of the results of `lower_bound_map` to the
+    /// minimum of the results of `upper_bound_map`, whose body is populated by `body`. The
+    /// closure receives the induction variable and the loop-carried values, and returns the
+    /// values yielded to the next iteration.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lower_bound_map: AffineMap<'ctx>,
+        lower_bound_operands: &[Value<'ctx>],
+        upper_bound_map: AffineMap<'ctx>,
+        upper_bound_operands: &[Value<'ctx>],
+        step: i64,
+        iter_args: &[Value<'ctx>],
+        body: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&OpBuilder<'ctx>, Value<'ctx>, &[Value<'ctx>]) -> Vec<Value<'ctx>>,
+    {
+        for map in [lower_bound_map, upper_bound_map] {
+            if map.get_num_results() == 0 {
+                return Err(format!("expected a bound map with results, got {}", map));
+            }
+        }
+        check_map_operands(lower_bound_map, lower_bound_operands)?;
+        check_map_operands(upper_bound_map, upper_bound_operands)?;
+        if step <= 0 {
+            return Err(format!("expected a positive step, got {}", step));
+        }
+
+        let ctx = builder.get_context();
+        let index_ty: Type = IndexType::get(ctx).into();
+        let attrs = [
+            NamedAttr::get(
+                ctx,
+                "lowerBoundMap",
+                AffineMapAttr::get(lower_bound_map).into(),
+            ),
+            NamedAttr::get(
+                ctx,
+                "upperBoundMap",
+                AffineMapAttr::get(upper_bound_map).into(),
+            ),
+            NamedAttr::get(ctx, "step", IntegerAttr::get(index_ty, step).into()),
+            get_operand_segment_sizes(
+                builder,
+                &[
+                    lower_bound_operands.len(),
+                    upper_bound_operands.len(),
+                    iter_args.len(),
+                ],
+            ),
+        ];
+        let mut operands = lower_bound_operands.to_vec();
+        operands.extend_from_slice(upper_bound_operands);
+        operands.extend_from_slice(iter_args);
+        let result_types = get_types(iter_args);
+        let op = create_region_op(
+            builder,
+            "affine.for",
+            loc,
+            &operands,
+            &result_types,
+            &attrs,
+            1,
+        );
+
+        let mut arg_types = vec![index_ty];
+        arg_types.extend_from_slice(&result_types);
+        let block = append_block(op.get_region(0), &arg_types, loc);
+        let body_builder = OpBuilder::at_block_end(ctx, block);
+        let args: Vec<Value> = block.arguments().collect();
+        let yielded = body(&body_builder, args[0], &args[1..]);
+        YieldOp::create(&body_builder, loc, &yielded);
+        Ok(Self { op })
+    }
+    /// Creates an `affine.for` with constant bounds.
+    pub fn create_constant<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lower_bound: i64,
+        upper_bound: i64,
+        step: i64,
+        iter_args: &[Value<'ctx>],
+        body: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&OpBuilder<'ctx>, Value<'ctx>, &[Value<'ctx>]) -> Vec<Value<'ctx>>,
+    {
+        let ctx = builder.get_context();
+        Self::create(
+            builder,
+            loc,
+            AffineMap::constant_get(ctx, lower_bound),
+            &[],
+            AffineMap::constant_get(ctx, upper_bound),
+            &[],
+            step,
+            iter_args,
+            body,
+        )
+    }
+    fn get_segment_sizes(&self) -> (usize, usize) {
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        (segments[0], segments[1])
+    }
+    pub fn get_lower_bound_map(&self) -> AffineMap<'ctx> {
+        get_map(self.op, "lowerBoundMap")
+    }
+    pub fn get_upper_bound_map(&self) -> AffineMap<'ctx> {
+        get_map(self.op, "upperBoundMap")
+    }
+    pub fn get_lower_bound_operands(&self) -> Vec<Value<'ctx>> {
+        let (num_lower, _) = self.get_segment_sizes();
+        self.op.operands().take(num_lower).collect()
+    }
+    pub fn get_upper_bound_operands(&self) -> Vec<Value<'ctx>> {
+        let (num_lower, num_upper) = self.get_segment_sizes();
+        self.op.operands().skip(num_lower).take(num_upper).collect()
+    }
+    pub fn get_step(&self) -> i64 {
+        let step: IntegerAttr = self.op.get_attr_by_name("step").dyn_cast();
+        step.get_value_int()
+    }
+    pub fn get_init_args(&self) -> Vec<Value<'ctx>> {
+        let (num_lower, num_upper) = self.get_segment_sizes();
+        self.op.operands().skip(num_lower + num_upper).collect()
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    pub fn get_induction_var(&self) -> Value<'ctx> {
+        self.get_body().get_argument(0)
+    }
+    pub fn get_region_iter_args(&self) -> Vec<Value<'ctx>> {
+        self.get_body().arguments().skip(1).collect()
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+define_op_view!(IfOp, "affine.if");
+
+impl<'ctx> IfOp<'ctx> {
+    fn create_if_op(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: IntegerSet<'ctx>,
+        operands: &[Value<'ctx>],
+        result_types: &[Type<'ctx>],
+    ) -> Result<OperationRef<'ctx>, String> {
+        if condition.get_num_inputs() != operands.len() {
+            return Err(format!(
+                "expected {} operands for {}, got {}",
+                condition.get_num_inputs(),
+                condition,
+                operands.len()
+            ));
+        }
+        let ctx = builder.get_context();
+        let attr = NamedAttr::get(ctx, "condition", IntegerSetAttr::get(condition).into());
+        Ok(create_region_op(
+            builder,
+            "affine.if",
+            loc,
+            operands,
+            result_types,
+            &[attr],
+            2,
+        ))
+    }
+    /// Creates an `affine.if` without else branch, whose body runs when the dims and symbols
+    /// in `operands` satisfy `condition`. Such an `affine.if` cannot have results.
+    pub fn create<T>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: IntegerSet<'ctx>,
+        operands: &[Value<'ctx>],
+        then_body: T,
+    ) -> Result<Self, String>
+    where
+        T: FnOnce(&OpBuilder<'ctx>),
+    {
+        let op = Self::create_if_op(builder, loc, condition, operands, &[])?;
+        let then_block = append_block(op.get_region(0), &[], loc);
+        let then_builder = OpBuilder::at_block_end(builder.get_context(), then_block);
+        then_body(&then_builder);
+        YieldOp::create(&then_builder, loc, &[]);
+        Ok(Self { op })
+    }
+    /// Creates an `affine.if` with both branches, each closure returns the values the branch
+    /// yields as the results of the `affine.if`.
+    pub fn create_with_else<T, E>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: IntegerSet<'ctx>,
+        operands: &[Value<'ctx>],
+        result_types: &[Type<'ctx>],
+        then_body: T,
+        else_body: E,
+    ) -> Result<Self, String>
+    where
+        T: FnOnce(&OpBuilder<'ctx>) -> Vec<Value<'ctx>>,
+        E: FnOnce(&OpBuilder<'ctx>) -> Vec<Value<'ctx>>,
+    {
+        let op = Self::create_if_op(builder, loc, condition, operands, result_types)?;
+        let then_block = append_block(op.get_region(0), &[], loc);
+        let then_builder = OpBuilder::at_block_end(builder.get_context(), then_block);
+        let yielded = then_body(&then_builder);
+        YieldOp::create(&then_builder, loc, &yielded);
+        let else_block = append_block(op.get_region(1), &[], loc);
+        let else_builder = OpBuilder::at_block_end(builder.get_context(), else_block);
+        let yielded = else_body(&else_builder);
+        YieldOp::create(&else_builder, loc, &yielded);
+        Ok(Self { op })
+    }
+    pub fn get_condition(&self) -> IntegerSet<'ctx> {
+        let attr: IntegerSetAttr = self.op.get_attr_by_name("condition").dyn_cast();
+        attr.get_value()
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+    pub fn get_then_block(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    /// Returns the else block, or `None` if there is no else branch.
+    pub fn get_else_block(&self) -> Option<BlockRef<'ctx>> {
+        let block = self.op.get_region(1).get_first_block();
+        if block.is_null() {
+            None
+        } else {
+            Some(block)
+        }
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+define_op_view!(LoadOp, "affine.load");
+
+impl<'ctx> LoadOp<'ctx> {
+    /// Creates an `affine.load` of the element of `memref` at the results of `map` applied to
+    /// `map_operands`. The map defaults to the identity, taking one index per dimension.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        memref: Value<'ctx>,
+        map: Option<AffineMap<'ctx>>,
+        map_operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let ty = get_memref_type(memref)?;
+        let map = get_access_map(builder, ty, map, map_operands)?;
+        let ctx = builder.get_context();
+        let mut operands = vec![memref];
+        operands.extend_from_slice(map_operands);
+        let op = builder.create_operation(
+            "affine.load",
+            loc,
+            &operands,
+            &[ty.get_element_type()],
+            &[NamedAttr::get(ctx, "map", AffineMapAttr::get(map).into())],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_memref(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_map(&self) -> AffineMap<'ctx> {
+        get_map(self.op, "map")
+    }
+    pub fn get_map_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(1).collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(StoreOp, "affine.store");
+
+impl<'ctx> StoreOp<'ctx> {
+    /// Creates an `affine.store` of `value` into `memref` at the results of `map` applied to
+    /// `map_operands`. The map defaults to the identity, taking one index per dimension.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        value: Value<'ctx>,
+        memref: Value<'ctx>,
+        map: Option<AffineMap<'ctx>>,
+        map_operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let ty = get_memref_type(memref)?;
+        if value.get_type() != ty.get_element_type() {
+            return Err(format!(
+                "expected a value of type {}, got {}",
+                ty.get_element_type(),
+                value.get_type()
+            ));
+        }
+        let map = get_access_map(builder, ty, map, map_operands)?;
+        let ctx = builder.get_context();
+        let mut operands = vec![value, memref];
+        operands.extend_from_slice(map_operands);
+        let op = builder.create_operation(
+            "affine.store",
+            loc,
+            &operands,
+            &[],
+            &[NamedAttr::get(ctx, "map", AffineMapAttr::get(map).into())],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_value(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_memref(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_map(&self) -> AffineMap<'ctx> {
+        get_map(self.op, "map")
+    }
+    pub fn get_map_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(2).collect()
+    }
+}
+
+/// Creates an op of `name` computing an index from the results of `map` applied to `operands`.
+fn create_map_op<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    name: &'static str,
+    loc: Location<'ctx>,
+    map: AffineMap<'ctx>,
+    operands: &[Value<'ctx>],
+) -> Result<OperationRef<'ctx>, String> {
+    check_map_operands(map, operands)?;
+    let ctx = builder.get_context();
+    Ok(builder.create_operation(
+        name,
+        loc,
+        operands,
+        &[IndexType::get(ctx).into()],
+        &[NamedAttr::get(ctx, "map", AffineMapAttr::get(map).into())],
+    ))
+}
+
+define_op_view!(ApplyOp, "affine.apply");
+
+impl<'ctx> ApplyOp<'ctx> {
+    /// Creates an `affine.apply` of the single-result `map` to `operands`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        map: AffineMap<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        if map.get_num_results() != 1 {
+            return Err(format!("expected a map with a single result, got {}", map));
+        }
+        let op = create_map_op(builder, "affine.apply", loc, map, operands)?;
+        Ok(Self { op })
+    }
+}
+
+define_op_view!(MinOp, "affine.min");
+
+impl<'ctx> MinOp<'ctx> {
+    /// Creates an `affine.min` of the results of `map` applied to `operands`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        map: AffineMap<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        if map.get_num_results() == 0 {
+            return Err(format!("expected a map with results, got {}", map));
+        }
+        let op = create_map_op(builder, "affine.min", loc, map, operands)?;
+        Ok(Self { op })
+    }
+}
+
+define_op_view!(MaxOp, "affine.max");
+
+impl<'ctx> MaxOp<'ctx> {
+    /// Creates an `affine.max` of the results of `map` applied to `operands`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        map: AffineMap<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        if map.get_num_results() == 0 {
+            return Err(format!("expected a map with results, got {}", map));
+        }
+        let op = create_map_op(builder, "affine.max", loc, map, operands)?;
+        Ok(Self { op })
+    }
+}
+
+macro_rules! impl_map_op_accessors {
+    ($($view:ident),*) => {
+        $(
+            impl<'ctx> $view<'ctx> {
+                pub fn get_map(&self) -> AffineMap<'ctx> {
+                    get_map(self.op, "map")
+                }
+                pub fn get_map_operands(&self) -> Vec<Value<'ctx>> {
+                    self.op.operands().collect()
+                }
+                pub fn get_result(&self) -> Value<'ctx> {
+                    self.op.get_result(0)
+                }
+            }
+        )*
+    };
+}
+
+impl_map_op_accessors!(ApplyOp, MinOp, MaxOp);
+
+#[cfg(test)]
+mod affine_test {
+    use super::*;
+    use crate::affine_expr::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Affine,
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::MemRef,
+    ];
+
+    #[test]
+    fn load_by_name() {
+        let ctx = create_context_with(&[UpstreamDialectName::Func]);
+        assert!(!ctx.is_registered_operation("affine.for"));
+        let dialect = load_dialect(&ctx);
+        assert_eq!(dialect.get_namespace(), "affine");
+        assert!(dialect == ctx.get_or_load_dialect("affine"));
+        assert!(ctx.is_registered_operation("affine.for"));
+        let num_registered_dialects = ctx.get_num_registered_dialects();
+        assert!(load_dialect(&ctx) == dialect);
+        assert!(UpstreamDialectName::Affine.load_dialect(&ctx) == dialect);
+        assert_eq!(ctx.get_num_registered_dialects(), num_registered_dialects);
+        assert!(get_handle_for_upstream_dialect(UpstreamDialectName::Affine).is_none());
+    }
+
+    #[test]
+    fn loops_and_accesses() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let dynamic = MemRefType::get_dynamic_size();
+        let memref_ty: Type =
+            MemRefType::contiguous_get(f32_ty, &[dynamic, 16], Attr::create_null()).into();
+        let func_ty = FunctionType::get(&ctx, &[memref_ty, index_ty], &[f32_ty]);
+        let func = FuncOp::create(&builder, loc, "sum", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let memref = func.get_argument(0).unwrap();
+        let n = func.get_argument(1).unwrap();
+
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let d1 = AffineExpr::dim_expr_get(&ctx, 1);
+        let s0 = AffineExpr::symbol_expr_get(&ctx, 0);
+        let c16 = AffineExpr::const_expr_get(&ctx, 16);
+        let zero = arith::ConstantOp::create_float(&body, loc, 0.0, f32_ty).get_result();
+        let identity = AffineMap::multi_dim_identity_get(&ctx, 1);
+        let upper = AffineMap::get(&ctx, 0, 1, &[s0]);
+        assert!(
+            ForOp::create(&body, loc, identity, &[], upper, &[n], 1, &[], |_, _, _| {
+                vec![]
+            })
+            .is_err()
+        );
+        let outer = ForOp::create(
+            &body,
+            loc,
+            AffineMap::constant_get(&ctx, 0),
+            &[],
+            upper,
+            &[n],
+            1,
+            &[zero],
+            |b, i, args| {
+                let inner = ForOp::create_constant(b, loc, 0, 16, 4, args, |b, j, args| {
+                    let value = LoadOp::create(b, loc, memref, None, &[i, j])
+                        .unwrap()
+                        .get_result();
+                    vec![arith::AddFOp::create(b, loc, args[0], value).get_result()]
+                })
+                .unwrap();
+                assert_eq!(inner.get_step(), 4);
+                inner.get_results()
+            },
+        )
+        .unwrap();
+        assert!(outer.get_upper_bound_map() == upper);
+        assert!(outer.get_upper_bound_operands() == vec![n]);
+        assert!(outer.get_lower_bound_operands().is_empty());
+        assert!(outer.get_init_args() == vec![zero]);
+        let i = outer.get_induction_var();
+
+        let transposed = AffineMap::get(&ctx, 2, 0, &[d1, d0]);
+        assert!(LoadOp::create(&body, loc, memref, None, &[n]).is_err());
+        assert!(StoreOp::create(&body, loc, n, memref, None, &[n, n]).is_err());
+        let store_op = StoreOp::create(
+            &body,
+            loc,
+            outer.get_results()[0],
+            memref,
+            Some(transposed),
+            &[n, n],
+        )
+        .unwrap();
+        assert!(store_op.get_map() == transposed);
+        assert!(store_op.get_map_operands() == vec![n, n]);
+
+        let next = ApplyOp::create(
+            &body,
+            loc,
+            AffineMap::get(&ctx, 1, 0, &[AffineExpr::add_expr_get(d0, c16)]),
+            &[n],
+        )
+        .unwrap()
+        .get_result();
+        assert!(next.get_type() == index_ty);
+        let two_results = AffineMap::get(&ctx, 1, 1, &[d0, s0]);
+        assert!(ApplyOp::create(&body, loc, two_results, &[n, n]).is_err());
+        let min_op = MinOp::create(&body, loc, two_results, &[n, next]).unwrap();
+        let max_op = MaxOp::create(&body, loc, two_results, &[n, next]).unwrap();
+        assert!(min_op.get_map() == two_results);
+        assert!(max_op.get_map_operands() == vec![n, next]);
+        assert!(MaxOp::create(&body, loc, two_results, &[n]).is_err());
+        assert!(ApplyOp::from_op(max_op.get_result().op_res_get_owner()).is_none());
+        assert!(i.get_type() == index_ty);
+        ReturnOp::create(&body, loc, &[zero]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+
+    #[test]
+    fn conditionals() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let func_ty = FunctionType::get(&ctx, &[index_ty, index_ty], &[index_ty]);
+        let func = FuncOp::create(&builder, loc, "clamp", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let x = func.get_argument(0).unwrap();
+        let n = func.get_argument(1).unwrap();
+
+        // x >= 0 and n - 1 - x >= 0
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let s0 = AffineExpr::symbol_expr_get(&ctx, 0);
+        let minus_one = AffineExpr::const_expr_get(&ctx, -1);
+        let upper = AffineExpr::add_expr_get(
+            AffineExpr::add_expr_get(s0, minus_one),
+            AffineExpr::mul_expr_get(d0, minus_one),
+        );
+        let in_bounds = IntegerSet::get(&ctx, 1, 1, &[], &[d0, upper]);
+        assert!(IfOp::create(&body, loc, in_bounds, &[x], |_| {}).is_err());
+        let if_op = IfOp::create(&body, loc, in_bounds, &[x, n], |_| {}).unwrap();
+        assert!(if_op.get_else_block().is_none());
+        assert!(if_op.get_condition() == in_bounds);
+
+        let clamped = IfOp::create_with_else(
+            &body,
+            loc,
+            in_bounds,
+            &[x, n],
+            &[index_ty],
+            |_| vec![x],
+            |b| vec![arith::ConstantOp::create_index(b, loc, 0).get_result()],
+        )
+        .unwrap();
+        assert!(clamped.get_operands() == vec![x, n]);
+        assert!(clamped.get_else_block().is_some());
+        ReturnOp::create(&body, loc, &clamped.get_results());
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
@@ -0,0 +1,433 @@
+use crate::attribute::*;
+use crate::builder::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::operation_state::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+use strum::EnumIter;
+use strum::IntoEnumIterator;
+
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CmpIPredicate {
+    Eq = 0,
+    Ne = 1,
+    Slt = 2,
+    Sle = 3,
+    Sgt = 4,
+    Sge = 5,
+    Ult = 6,
+    Ule = 7,
+    Ugt = 8,
+    Uge = 9,
+}
+
+impl CmpIPredicate {
+    pub fn to_i64(self) -> i64 {
+        self as i64
+    }
+    pub fn from_i64(value: i64) -> Option<Self> {
+        Self::iter().find(|pred| pred.to_i64() == value)
+    }
+}
+
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CmpFPredicate {
+    AlwaysFalse = 0,
+    Oeq = 1,
+    Ogt = 2,
+    Oge = 3,
+    Olt = 4,
+    Ole = 5,
+    One = 6,
+    Ord = 7,
+    Ueq = 8,
+    Ugt = 9,
+    Uge = 10,
+    Ult = 11,
+    Ule = 12,
+    Une = 13,
+    Uno = 14,
+    AlwaysTrue = 15,
+}
+
+impl CmpFPredicate {
+    pub fn to_i64(self) -> i64 {
+        self as i64
+    }
+    pub fn from_i64(value: i64) -> Option<Self> {
+        Self::iter().find(|pred| pred.to_i64() == value)
+    }
+}
+
+define_op_view!(ConstantOp, "arith.constant");
+
+impl<'ctx> ConstantOp<'ctx> {
+    /// Creates an `arith.constant` whose result has the type of `value`.
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, value: Attr<'ctx>) -> Self {
+        let ctx = builder.get_context();
+        let op = builder.create_operation(
+            "arith.constant",
+            loc,
+            &[],
+            &[value.get_type()],
+            &[NamedAttr::get(ctx, "value", value)],
+        );
+        Self { op }
+    }
+    /// Creates an `arith.constant` of integer or index type `ty`.
+    pub fn create_int(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        value: i64,
+        ty: Type<'ctx>,
+    ) -> Self {
+        Self::create(builder, loc, IntegerAttr::get(ty, value).into())
+    }
+    pub fn create_index(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, value: i64) -> Self {
+        let index_ty = IndexType::get(builder.get_context());
+        Self::create_int(builder, loc, value, index_ty.into())
+    }
+    pub fn create_bool(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, value: bool) -> Self {
+        let i1_ty = IntegerType::get(builder.get_context(), 1);
+        Self::create_int(builder, loc, value as i64, i1_ty.into())
+    }
+    /// Creates an `arith.constant` of float type `ty`.
+    pub fn create_float(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        value: f64,
+        ty: Type<'ctx>,
+    ) -> Self {
+        Self::create(
+            builder,
+            loc,
+            FloatAttr::f64_get(builder.get_context(), ty, value).into(),
+        )
+    }
+    pub fn get_value(&self) -> Attr<'ctx> {
+        self.op.get_attr_by_name("value")
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+/// Defines the op views of binary ops whose result has the type of their operands.
+macro_rules! define_binary_ops {
+    ($($view:ident, $name:literal;)*) => {
+        $(
+            define_op_view!($view, $name);
+
+            impl<'ctx> $view<'ctx> {
+                pub fn create(
+                    builder: &OpBuilder<'ctx>,
+                    loc: Location<'ctx>,
+                    lhs: Value<'ctx>,
+                    rhs: Value<'ctx>,
+                ) -> Self {
+                    let op =
+                        builder.create_operation($name, loc, &[lhs, rhs], &[lhs.get_type()], &[]);
+                    Self { op }
+                }
+                pub fn get_lhs(&self) -> Value<'ctx> {
+                    self.op.get_operand(0)
+                }
+                pub fn get_rhs(&self) -> Value<'ctx> {
+                    self.op.get_operand(1)
+                }
+                pub fn get_result(&self) -> Value<'ctx> {
+                    self.op.get_result(0)
+                }
+            }
+        )*
+    };
+}
+
+define_binary_ops! {
+    AddIOp, "arith.addi";
+    SubIOp, "arith.subi";
+    MulIOp, "arith.muli";
+    DivSIOp, "arith.divsi";
+    DivUIOp, "arith.divui";
+    CeilDivSIOp, "arith.ceildivsi";
+    FloorDivSIOp, "arith.floordivsi";
+    RemSIOp, "arith.remsi";
+    RemUIOp, "arith.remui";
+    AndIOp, "arith.andi";
+    OrIOp, "arith.ori";
+    XOrIOp, "arith.xori";
+    ShLIOp, "arith.shli";
+    ShRSIOp, "arith.shrsi";
+    ShRUIOp, "arith.shrui";
+    MaxSIOp, "arith.maxsi";
+    MaxUIOp, "arith.maxui";
+    MinSIOp, "arith.minsi";
+    MinUIOp, "arith.minui";
+    AddFOp, "arith.addf";
+    SubFOp, "arith.subf";
+    MulFOp, "arith.mulf";
+    DivFOp, "arith.divf";
+    RemFOp, "arith.remf";
+    MaximumFOp, "arith.maximumf";
+    MinimumFOp, "arith.minimumf";
+}
+
+define_op_view!(NegFOp, "arith.negf");
+
+impl<'ctx> NegFOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, operand: Value<'ctx>) -> Self {
+        let op =
+            builder.create_operation("arith.negf", loc, &[operand], &[operand.get_type()], &[]);
+        Self { op }
+    }
+    pub fn get_operand(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+/// Creates a comparison whose result type (`i1`, or a shaped type of `i1`) is inferred.
+fn create_cmp<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    name: &'static str,
+    loc: Location<'ctx>,
+    predicate: i64,
+    lhs: Value<'ctx>,
+    rhs: Value<'ctx>,
+) -> OperationRef<'ctx> {
+    let ctx = builder.get_context();
+    let i64_ty = IntegerType::get(ctx, 64);
+    let mut state = OperationState::get(name, loc);
+    state
+        .add_operands(&[lhs, rhs])
+        .add_attributes(&[NamedAttr::get(
+            ctx,
+            "predicate",
+            IntegerAttr::get(i64_ty.into(), predicate).into(),
+        )])
+        .enable_type_inference();
+    builder.create(&state)
+}
+
+fn get_predicate_value(op: OperationRef) -> i64 {
+    let attr: IntegerAttr = op.get_attr_by_name("predicate").dyn_cast();
+    attr.get_value_int()
+}
+
+define_op_view!(CmpIOp, "arith.cmpi");
+
+impl<'ctx> CmpIOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        predicate: CmpIPredicate,
+        lhs: Value<'ctx>,
+        rhs: Value<'ctx>,
+    ) -> Self {
+        let op = create_cmp(builder, "arith.cmpi", loc, predicate.to_i64(), lhs, rhs);
+        Self { op }
+    }
+    pub fn get_predicate(&self) -> CmpIPredicate {
+        CmpIPredicate::from_i64(get_predicate_value(self.op)).expect("invalid cmpi predicate")
+    }
+    pub fn get_lhs(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_rhs(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(CmpFOp, "arith.cmpf");
+
+impl<'ctx> CmpFOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        predicate: CmpFPredicate,
+        lhs: Value<'ctx>,
+        rhs: Value<'ctx>,
+    ) -> Self {
+        let op = create_cmp(builder, "arith.cmpf", loc, predicate.to_i64(), lhs, rhs);
+        Self { op }
+    }
+    pub fn get_predicate(&self) -> CmpFPredicate {
+        CmpFPredicate::from_i64(get_predicate_value(self.op)).expect("invalid cmpf predicate")
+    }
+    pub fn get_lhs(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_rhs(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(SelectOp, "arith.select");
+
+impl<'ctx> SelectOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: Value<'ctx>,
+        true_value: Value<'ctx>,
+        false_value: Value<'ctx>,
+    ) -> Self {
+        let op = builder.create_operation(
+            "arith.select",
+            loc,
+            &[condition, true_value, false_value],
+            &[true_value.get_type()],
+            &[],
+        );
+        Self { op }
+    }
+    pub fn get_condition(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_true_value(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_false_value(&self) -> Value<'ctx> {
+        self.op.get_operand(2)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+/// Defines the op views of cast ops, which convert their operand to the given type.
+macro_rules! define_cast_ops {
+    ($($view:ident, $name:literal;)*) => {
+        $(
+            define_op_view!($view, $name);
+
+            impl<'ctx> $view<'ctx> {
+                pub fn create(
+                    builder: &OpBuilder<'ctx>,
+                    loc: Location<'ctx>,
+                    value: Value<'ctx>,
+                    to: Type<'ctx>,
+                ) -> Self {
+                    let op = builder.create_operation($name, loc, &[value], &[to], &[]);
+                    Self { op }
+                }
+                pub fn get_in(&self) -> Value<'ctx> {
+                    self.op.get_operand(0)
+                }
+                pub fn get_out(&self) -> Value<'ctx> {
+                    self.op.get_result(0)
+                }
+            }
+        )*
+    };
+}
+
+define_cast_ops! {
+    IndexCastOp, "arith.index_cast";
+    IndexCastUIOp, "arith.index_castui";
+    ExtSIOp, "arith.extsi";
+    ExtUIOp, "arith.extui";
+    TruncIOp, "arith.trunci";
+    ExtFOp, "arith.extf";
+    TruncFOp, "arith.truncf";
+    SIToFPOp, "arith.sitofp";
+    UIToFPOp, "arith.uitofp";
+    FPToSIOp, "arith.fptosi";
+    FPToUIOp, "arith.fptoui";
+    BitcastOp, "arith.bitcast";
+}
+
+#[cfg(test)]
+mod arith_test {
+    use super::*;
+    use crate::context::*;
+    use crate::dialect::*;
+    use crate::module::*;
+
+    #[test]
+    fn build() {
+        let ctx = Context::create();
+        UpstreamDialectName::Arith.load_dialect(&ctx);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let i32_ty: Type = IntegerType::get(&ctx, 32).into();
+        let i64_ty: Type = IntegerType::get(&ctx, 64).into();
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let f64_ty: Type = FloatType::get(&ctx, FloatKind::F64).into();
+        let i1_ty: Type = IntegerType::get(&ctx, 1).into();
+
+        let c3 = ConstantOp::create_int(&builder, loc, 3, i32_ty).get_result();
+        let cst = ConstantOp::create_int(&builder, loc, 4, i32_ty);
+        let c4 = cst.get_result();
+        let add = AddIOp::create(&builder, loc, c3, c4);
+        let sum = add.get_result();
+        let prod = MulIOp::create(&builder, loc, sum, c4).get_result();
+        assert!(prod.get_type() == i32_ty);
+        let cmp = CmpIOp::create(&builder, loc, CmpIPredicate::Slt, sum, prod);
+        let cond = cmp.get_result();
+        assert!(cond.get_type() == i1_ty);
+        let sel = SelectOp::create(&builder, loc, cond, sum, prod);
+        let selected = sel.get_result();
+        let ext = ExtSIOp::create(&builder, loc, selected, i64_ty);
+        let wide = ext.get_out();
+        assert!(wide.get_type() == i64_ty);
+        let idx = IndexCastOp::create(&builder, loc, wide, IndexType::get(&ctx).into()).get_out();
+        let c1 = ConstantOp::create_index(&builder, loc, 1).get_result();
+        AddIOp::create(&builder, loc, idx, c1);
+
+        let f = ConstantOp::create_float(&builder, loc, 1.5, f64_ty).get_result();
+        let neg = NegFOp::create(&builder, loc, f).get_result();
+        let narrow = TruncFOp::create(&builder, loc, neg, f32_ty).get_out();
+        assert!(narrow.get_type() == f32_ty);
+        let fcmp = CmpFOp::create(&builder, loc, CmpFPredicate::Olt, f, neg);
+        let fcond = fcmp.get_result();
+        let t = ConstantOp::create_bool(&builder, loc, true).get_result();
+        AndIOp::create(&builder, loc, fcond, t);
+        assert!(module.get_operation_ref().verify().is_ok());
+
+        assert!(add.get_lhs() == c3);
+        assert!(add.get_rhs() == c4);
+        assert!(AddIOp::from_op(sum.op_res_get_owner()).is_some());
+        assert!(MulIOp::from_op(sum.op_res_get_owner()).is_none());
+        assert_eq!(cmp.get_predicate(), CmpIPredicate::Slt);
+        assert!(cmp.get_rhs() == prod);
+        assert_eq!(fcmp.get_predicate(), CmpFPredicate::Olt);
+        assert!(sel.get_condition() == cond);
+        assert!(ext.get_in() == selected);
+        let value: IntegerAttr = cst.get_value().dyn_cast();
+        assert_eq!(value.get_value_int(), 4);
+
+        let mut num_constants = 0;
+        module
+            .get_operation_ref()
+            .walk_typed::<ConstantOp, _>(WalkOrder::PreOrder, |_| {
+                num_constants += 1;
+                WalkResult::Advance
+            });
+        assert_eq!(num_constants, 5);
+    }
+
+    #[test]
+    fn predicates() {
+        for pred in CmpIPredicate::iter() {
+            assert_eq!(CmpIPredicate::from_i64(pred.to_i64()), Some(pred));
+        }
+        for pred in CmpFPredicate::iter() {
+            assert_eq!(CmpFPredicate::from_i64(pred.to_i64()), Some(pred));
+        }
+        assert_eq!(CmpIPredicate::from_i64(10), None);
+        assert_eq!(CmpFPredicate::AlwaysTrue.to_i64(), 15);
+    }
+}
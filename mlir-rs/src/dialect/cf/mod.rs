@@ -0,0 +1,436 @@
+use super::{get_operand_segment_sizes, read_segment_sizes};
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::operation_state::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+/// Ops that forward operands to the arguments of their successors. The forwarded operands are
+/// not checked against the successors when modified, the verifier is expected to catch
+/// mismatches once the rewrite is done. Out-of-range successor positions return `None` or an
+/// error.
+pub trait SuccessorOperands<'ctx> {
+    fn get_successor_operands(&self, pos: usize) -> Option<Vec<Value<'ctx>>>;
+    fn set_successor_operands(&self, pos: usize, operands: &[Value<'ctx>]) -> Result<(), String>;
+}
+
+fn check_successor_pos(op: OperationRef, pos: usize) -> Result<(), String> {
+    let num_successors = op.get_num_successors();
+    if pos >= num_successors {
+        return Err(format!(
+            "successor #{} out of range, {} has {} successors",
+            pos,
+            op.get_name(),
+            num_successors
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `operands` can be forwarded to the arguments of `dest`.
+fn check_dest_operands<'ctx>(dest: BlockRef<'ctx>, operands: &[Value<'ctx>]) -> Result<(), String> {
+    if dest.get_num_arguments() != operands.len() {
+        return Err(format!(
+            "expected {} operands for the successor, got {}",
+            dest.get_num_arguments(),
+            operands.len()
+        ));
+    }
+    for (pos, (arg, operand)) in dest.arguments().zip(operands).enumerate() {
+        if arg.get_type() != operand.get_type() {
+            return Err(format!(
+                "expected operand #{} of type {}, got {}",
+                pos,
+                arg.get_type(),
+                operand.get_type()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Creates a terminator branching to `successors` at the insertion point.
+fn create_branch_op<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    name: &'static str,
+    loc: Location<'ctx>,
+    operands: &[Value<'ctx>],
+    attributes: &[NamedAttr<'ctx>],
+    successors: &[BlockRef<'ctx>],
+) -> OperationRef<'ctx> {
+    let mut state = OperationState::get(name, loc);
+    state
+        .add_operands(operands)
+        .add_attributes(attributes)
+        .add_successors(successors);
+    builder.create(&state)
+}
+
+/// Returns the operands of the `group`-th operand group of `op`.
+fn get_segment<'ctx>(op: OperationRef<'ctx>, segments: &[usize], group: usize) -> Vec<Value<'ctx>> {
+    let start: usize = segments[..group].iter().sum();
+    op.operands().skip(start).take(segments[group]).collect()
+}
+
+/// Replaces the operands of the `group`-th operand group of `op`, returning the updated sizes
+/// of the groups.
+fn set_segment<'ctx>(
+    op: OperationRef<'ctx>,
+    segments: &[usize],
+    group: usize,
+    operands: &[Value<'ctx>],
+) -> Vec<usize> {
+    let start: usize = segments[..group].iter().sum();
+    let mut new_operands: Vec<Value> = op.operands().take(start).collect();
+    new_operands.extend_from_slice(operands);
+    new_operands.extend(op.operands().skip(start + segments[group]));
+    op.set_operands(&new_operands);
+    let mut segments = segments.to_vec();
+    segments[group] = operands.len();
+    segments
+}
+
+fn set_segment_sizes(op: OperationRef, attr_name: &str, segments: &[usize]) {
+    let ctx = op.get_context();
+    let segments: Vec<i32> = segments.iter().map(|size| *size as i32).collect();
+    op.set_attr_by_name(attr_name, DenseI32ArrayAttr::get(&ctx, &segments).into());
+}
+
+define_op_view!(BranchOp, "cf.br");
+
+impl<'ctx> BranchOp<'ctx> {
+    /// Creates a `cf.br` to `dest`, forwarding `operands` to its arguments.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        dest: BlockRef<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        check_dest_operands(dest, operands)?;
+        let op = create_branch_op(builder, "cf.br", loc, operands, &[], &[dest]);
+        Ok(Self { op })
+    }
+    pub fn get_dest(&self) -> BlockRef<'ctx> {
+        self.op.get_successor(0)
+    }
+    pub fn get_dest_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+impl<'ctx> SuccessorOperands<'ctx> for BranchOp<'ctx> {
+    fn get_successor_operands(&self, pos: usize) -> Option<Vec<Value<'ctx>>> {
+        check_successor_pos(self.op, pos).ok()?;
+        Some(self.get_dest_operands())
+    }
+    fn set_successor_operands(&self, pos: usize, operands: &[Value<'ctx>]) -> Result<(), String> {
+        check_successor_pos(self.op, pos)?;
+        self.op.set_operands(operands);
+        Ok(())
+    }
+}
+
+define_op_view!(CondBranchOp, "cf.cond_br");
+
+impl<'ctx> CondBranchOp<'ctx> {
+    /// Creates a `cf.cond_br` on the `i1` value `condition`, forwarding `true_operands` to
+    /// `true_dest` and `false_operands` to `false_dest`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: Value<'ctx>,
+        true_dest: BlockRef<'ctx>,
+        true_operands: &[Value<'ctx>],
+        false_dest: BlockRef<'ctx>,
+        false_operands: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let i1_ty: Type = IntegerType::get(builder.get_context(), 1).into();
+        if condition.get_type() != i1_ty {
+            return Err(format!(
+                "expected a condition of type i1, got {}",
+                condition.get_type()
+            ));
+        }
+        check_dest_operands(true_dest, true_operands)?;
+        check_dest_operands(false_dest, false_operands)?;
+        let mut operands = vec![condition];
+        operands.extend_from_slice(true_operands);
+        operands.extend_from_slice(false_operands);
+        let segments =
+            get_operand_segment_sizes(builder, &[1, true_operands.len(), false_operands.len()]);
+        let op = create_branch_op(
+            builder,
+            "cf.cond_br",
+            loc,
+            &operands,
+            &[segments],
+            &[true_dest, false_dest],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_condition(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_true_dest(&self) -> BlockRef<'ctx> {
+        self.op.get_successor(0)
+    }
+    pub fn get_false_dest(&self) -> BlockRef<'ctx> {
+        self.op.get_successor(1)
+    }
+    pub fn get_true_dest_operands(&self) -> Vec<Value<'ctx>> {
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        get_segment(self.op, &segments, 1)
+    }
+    pub fn get_false_dest_operands(&self) -> Vec<Value<'ctx>> {
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        get_segment(self.op, &segments, 2)
+    }
+}
+
+impl<'ctx> SuccessorOperands<'ctx> for CondBranchOp<'ctx> {
+    fn get_successor_operands(&self, pos: usize) -> Option<Vec<Value<'ctx>>> {
+        check_successor_pos(self.op, pos).ok()?;
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        Some(get_segment(self.op, &segments, pos + 1))
+    }
+    fn set_successor_operands(&self, pos: usize, operands: &[Value<'ctx>]) -> Result<(), String> {
+        check_successor_pos(self.op, pos)?;
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        let segments = set_segment(self.op, &segments, pos + 1, operands);
+        set_segment_sizes(self.op, "operandSegmentSizes", &segments);
+        Ok(())
+    }
+}
+
+/// A case of a `cf.switch`: the value of the flag, the destination and the operands forwarded
+/// to it.
+pub type SwitchCase<'a, 'ctx> = (i64, BlockRef<'ctx>, &'a [Value<'ctx>]);
+
+define_op_view!(SwitchOp, "cf.switch");
+
+impl<'ctx> SwitchOp<'ctx> {
+    /// Creates a `cf.switch` on the integer value `flag`, branching to the destination of the
+    /// case matching `flag`, or to `default_dest` if there is none.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        flag: Value<'ctx>,
+        default_dest: BlockRef<'ctx>,
+        default_operands: &[Value<'ctx>],
+        cases: &[SwitchCase<'_, 'ctx>],
+    ) -> Result<Self, String> {
+        let flag_ty = flag.get_type();
+        if !IsA::<IntegerType>::is_a_non_null(flag_ty) {
+            return Err(format!("expected an integer flag, got {}", flag_ty));
+        }
+        check_dest_operands(default_dest, default_operands)?;
+        let mut operands = vec![flag];
+        operands.extend_from_slice(default_operands);
+        let mut dests = vec![default_dest];
+        let mut case_values: Vec<Attr> = Vec::with_capacity(cases.len());
+        let mut case_segments = Vec::with_capacity(cases.len());
+        for (value, dest, case_operands) in cases {
+            check_dest_operands(*dest, case_operands)?;
+            operands.extend_from_slice(case_operands);
+            dests.push(*dest);
+            case_values.push(IntegerAttr::get(flag_ty, *value).into());
+            case_segments.push(case_operands.len());
+        }
+
+        let ctx = builder.get_context();
+        let case_segments: Vec<i32> = case_segments.iter().map(|size| *size as i32).collect();
+        let mut attrs = vec![
+            NamedAttr::get(
+                ctx,
+                "case_operand_segments",
+                DenseI32ArrayAttr::get(ctx, &case_segments).into(),
+            ),
+            get_operand_segment_sizes(
+                builder,
+                &[
+                    1,
+                    default_operands.len(),
+                    operands.len() - 1 - default_operands.len(),
+                ],
+            ),
+        ];
+        if !cases.is_empty() {
+            let case_values_ty = VectorType::get(&[cases.len() as i64], flag_ty);
+            attrs.push(NamedAttr::get(
+                ctx,
+                "case_values",
+                DenseElementsAttr::get(case_values_ty.into(), &case_values).into(),
+            ));
+        }
+        let op = create_branch_op(builder, "cf.switch", loc, &operands, &attrs, &dests);
+        Ok(Self { op })
+    }
+    pub fn get_flag(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_default_dest(&self) -> BlockRef<'ctx> {
+        self.op.get_successor(0)
+    }
+    pub fn get_default_operands(&self) -> Vec<Value<'ctx>> {
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        get_segment(self.op, &segments, 1)
+    }
+    pub fn get_num_cases(&self) -> usize {
+        self.op.get_num_successors() - 1
+    }
+    pub fn get_case_values(&self) -> Vec<i64> {
+        let attr = self.op.get_attr_by_name("case_values");
+        if attr.is_null() {
+            return vec![];
+        }
+        let values: DenseElementsAttr = attr.dyn_cast();
+        (0..values.get_num_elements())
+            .map(|pos| {
+                let value: IntegerAttr = values.get_value(&[pos as u64]).dyn_cast();
+                value.get_value_int()
+            })
+            .collect()
+    }
+    pub fn get_case_dest(&self, pos: usize) -> Option<BlockRef<'ctx>> {
+        if pos >= self.get_num_cases() {
+            return None;
+        }
+        Some(self.op.get_successor(pos + 1))
+    }
+    pub fn get_case_operands(&self, pos: usize) -> Option<Vec<Value<'ctx>>> {
+        if pos >= self.get_num_cases() {
+            return None;
+        }
+        self.get_successor_operands(pos + 1)
+    }
+}
+
+impl<'ctx> SuccessorOperands<'ctx> for SwitchOp<'ctx> {
+    /// The default destination is the successor #0, and the destination of the case #`i` the
+    /// successor #`i + 1`.
+    fn get_successor_operands(&self, pos: usize) -> Option<Vec<Value<'ctx>>> {
+        check_successor_pos(self.op, pos).ok()?;
+        if pos == 0 {
+            return Some(self.get_default_operands());
+        }
+        let segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        let case_segments = read_segment_sizes(self.op, "case_operand_segments");
+        let mut all_segments = vec![segments[0] + segments[1]];
+        all_segments.extend(case_segments);
+        Some(get_segment(self.op, &all_segments, pos))
+    }
+    fn set_successor_operands(&self, pos: usize, operands: &[Value<'ctx>]) -> Result<(), String> {
+        check_successor_pos(self.op, pos)?;
+        let mut segments = read_segment_sizes(self.op, "operandSegmentSizes");
+        if pos == 0 {
+            let segments = set_segment(self.op, &segments, 1, operands);
+            set_segment_sizes(self.op, "operandSegmentSizes", &segments);
+            return Ok(());
+        }
+        let case_segments = read_segment_sizes(self.op, "case_operand_segments");
+        let mut all_segments = vec![segments[0] + segments[1]];
+        all_segments.extend(case_segments);
+        let all_segments = set_segment(self.op, &all_segments, pos, operands);
+        segments[2] = all_segments[1..].iter().sum();
+        set_segment_sizes(self.op, "operandSegmentSizes", &segments);
+        set_segment_sizes(self.op, "case_operand_segments", &all_segments[1..]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod cf_test {
+    use super::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::ControlFlow,
+        UpstreamDialectName::Func,
+    ];
+
+    #[test]
+    fn branches() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let i1_ty: Type = IntegerType::get(&ctx, 1).into();
+        let i32_ty: Type = IntegerType::get(&ctx, 32).into();
+        let func_ty = FunctionType::get(&ctx, &[i32_ty, i1_ty], &[i32_ty]);
+        let func = FuncOp::create(&builder, loc, "select", func_ty, SymbolVisibility::Public);
+        let x = func.get_argument(0).unwrap();
+        let condition = func.get_argument(1).unwrap();
+        let mut body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let region = func.get_body();
+        let bb1 = body.create_block(region, &[i32_ty], &[loc]);
+        let bb2 = body.create_block(region, &[], &[]);
+        let exit = body.create_block(region, &[i32_ty], &[loc]);
+
+        body.set_insertion_point_to_end(func.get_entry_block());
+        assert!(CondBranchOp::create(&body, loc, x, bb1, &[x], bb2, &[]).is_err());
+        assert!(CondBranchOp::create(&body, loc, condition, bb1, &[], bb2, &[]).is_err());
+        let cond_br = CondBranchOp::create(&body, loc, condition, bb1, &[x], bb2, &[]).unwrap();
+        assert!(*cond_br.get_true_dest() == *bb1);
+        assert!(*cond_br.get_false_dest() == *bb2);
+        assert!(cond_br.get_true_dest_operands() == vec![x]);
+        assert!(cond_br.get_false_dest_operands().is_empty());
+
+        body.set_insertion_point_to_end(bb1);
+        let arg = bb1.get_argument(0);
+        assert!(BranchOp::create(&body, loc, exit, &[condition]).is_err());
+        let br = BranchOp::create(&body, loc, exit, &[arg]).unwrap();
+        assert!(*br.get_dest() == *exit);
+        assert!(br.get_dest_operands() == vec![arg]);
+
+        body.set_insertion_point_to_end(bb2);
+        let cases: [SwitchCase; 2] = [(1, bb1, &[x]), (2, exit, &[x])];
+        SwitchOp::create(&body, loc, condition, exit, &[x], &cases[..1])
+            .unwrap()
+            .get_op()
+            .erase();
+        let switch = SwitchOp::create(&body, loc, x, exit, &[x], &cases).unwrap();
+        assert_eq!(switch.get_num_cases(), 2);
+        assert_eq!(switch.get_case_values(), vec![1, 2]);
+        assert!(*switch.get_case_dest(1).unwrap() == *exit);
+        assert!(switch.get_case_dest(2).is_none());
+        assert!(switch.get_case_operands(0) == Some(vec![x]));
+        assert!(switch.get_case_operands(2).is_none());
+        assert!(switch.get_default_operands() == vec![x]);
+
+        body.set_insertion_point_to_end(exit);
+        ReturnOp::create(&body, loc, &[exit.get_argument(0)]);
+        assert!(module.get_operation_ref().verify().is_ok());
+
+        // Rewrite the CFG: forward a new value to the exit block from every predecessor.
+        body.set_insertion_point(cond_br.get_op());
+        let c0 = arith::ConstantOp::create_int(&body, loc, 0, i32_ty).get_result();
+        cond_br.get_op().set_successor(1, exit);
+        cond_br.set_successor_operands(1, &[c0]).unwrap();
+        assert!(cond_br.set_successor_operands(2, &[c0]).is_err());
+        assert!(cond_br.get_successor_operands(2).is_none());
+        assert!(cond_br.get_false_dest_operands() == vec![c0]);
+        assert!(cond_br.get_true_dest_operands() == vec![x]);
+        switch.set_successor_operands(1, &[c0]).unwrap();
+        switch.set_successor_operands(0, &[]).unwrap();
+        assert!(switch.set_successor_operands(3, &[]).is_err());
+        switch.get_op().set_successor(0, bb2);
+        assert!(switch.get_default_operands().is_empty());
+        assert!(switch.get_case_operands(0) == Some(vec![c0]));
+        assert!(switch.get_successor_operands(2) == Some(vec![x]));
+        br.set_successor_operands(0, &[c0]).unwrap();
+        assert!(br.set_successor_operands(1, &[c0]).is_err());
+        assert!(br.get_successor_operands(1).is_none());
+        assert!(br.get_dest_operands() == vec![c0]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
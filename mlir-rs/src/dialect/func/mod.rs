@@ -0,0 +1,410 @@
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::context::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::operation_state::*;
+use crate::r#type::*;
+use crate::region::*;
+use crate::symbol_table::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+define_op_view!(FuncOp, "func.func");
+
+impl<'ctx> FuncOp<'ctx> {
+    /// Creates a `func.func` at the insertion point of `builder`, with an entry block whose
+    /// arguments have the input types of `ty`. The insertion point is left unchanged.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        name: &str,
+        ty: FunctionType<'ctx>,
+        visibility: SymbolVisibility,
+    ) -> Self {
+        let func = Self::create_without_body(builder, loc, name, ty, visibility);
+        let arg_types: Vec<Type> = (0..ty.get_num_inputs()).map(|i| ty.get_input(i)).collect();
+        let arg_locs = vec![loc; arg_types.len()];
+        func.get_body()
+            .append_owned_block(Block::create(&arg_types, &arg_locs));
+        func
+    }
+    /// Creates a private `func.func` without body, which declares an external function.
+    pub fn declare(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        name: &str,
+        ty: FunctionType<'ctx>,
+    ) -> Self {
+        Self::create_without_body(builder, loc, name, ty, SymbolVisibility::Private)
+    }
+    fn create_without_body(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        name: &str,
+        ty: FunctionType<'ctx>,
+        visibility: SymbolVisibility,
+    ) -> Self {
+        let ctx = builder.get_context();
+        let mut attrs = vec![
+            NamedAttr::get(ctx, "sym_name", StringAttr::get(ctx, name).into()),
+            NamedAttr::get(ctx, "function_type", TypeAttr::get(ty.into()).into()),
+        ];
+        if visibility != SymbolVisibility::Public {
+            attrs.push(NamedAttr::get(
+                ctx,
+                "sym_visibility",
+                StringAttr::get(ctx, visibility.as_str()).into(),
+            ));
+        }
+        let mut state = OperationState::get("func.func", loc);
+        state
+            .add_attributes(&attrs)
+            .add_owned_regions(vec![Region::create()]);
+        Self {
+            op: builder.create(&state),
+        }
+    }
+
+    pub fn get_name(&self) -> &'ctx str {
+        let attr: StringAttr = self.op.get_attr_by_name("sym_name").dyn_cast();
+        attr.get_value()
+    }
+    pub fn get_function_type(&self) -> FunctionType<'ctx> {
+        let attr: TypeAttr = self.op.get_attr_by_name("function_type").dyn_cast();
+        attr.get_value().dyn_cast()
+    }
+    /// Returns the visibility of the function, or `None` if `sym_visibility` is not a valid
+    /// visibility.
+    pub fn get_visibility(&self) -> Option<SymbolVisibility> {
+        let attr = self.op.get_attr_by_name("sym_visibility");
+        if attr.is_null() {
+            return Some(SymbolVisibility::Public);
+        }
+        let attr: StringAttr = attr.dyn_cast_or_null();
+        if attr.is_null() {
+            return None;
+        }
+        attr.get_value().parse().ok()
+    }
+    pub fn set_visibility(&self, visibility: SymbolVisibility) {
+        if visibility == SymbolVisibility::Public {
+            self.op.remove_attr_by_name("sym_visibility");
+        } else {
+            let ctx = self.op.get_context();
+            self.op.set_attr_by_name(
+                "sym_visibility",
+                StringAttr::get(&ctx, visibility.as_str()).into(),
+            );
+        }
+    }
+    pub fn get_body(&self) -> RegionRef<'ctx> {
+        self.op.get_region(0)
+    }
+    /// Whether the function is a declaration without body.
+    pub fn is_external(&self) -> bool {
+        self.get_body().get_first_block().is_null()
+    }
+    pub fn get_entry_block(&self) -> BlockRef<'ctx> {
+        self.get_body().get_first_block()
+    }
+    pub fn get_num_arguments(&self) -> usize {
+        self.get_function_type().get_num_inputs()
+    }
+    pub fn get_num_results(&self) -> usize {
+        self.get_function_type().get_num_results()
+    }
+    /// Returns the block argument `pos`, or `None` if the function is external or `pos` is out of
+    /// range.
+    pub fn get_argument(&self, pos: usize) -> Option<Value<'ctx>> {
+        if self.is_external() || pos >= self.get_entry_block().get_num_arguments() {
+            return None;
+        }
+        Some(self.get_entry_block().get_argument(pos))
+    }
+    /// Returns the block arguments, external functions have none.
+    pub fn arguments(&self) -> impl DoubleEndedIterator<Item = Value<'ctx>> + ExactSizeIterator {
+        let entry = self.get_entry_block();
+        let num = if entry.is_null() {
+            0
+        } else {
+            entry.get_num_arguments()
+        };
+        (0..num).map(move |pos| entry.get_argument(pos))
+    }
+
+    /// Returns the attribute dictionary of argument `pos`, which is empty if it has none, or
+    /// `None` if `pos` is out of range.
+    pub fn get_arg_attrs(&self, pos: usize) -> Option<DictionaryAttr<'ctx>> {
+        get_attr_dict(self.op, "arg_attrs", self.get_num_arguments(), pos)
+    }
+    pub fn set_arg_attrs(&self, pos: usize, attrs: DictionaryAttr<'ctx>) -> Result<(), String> {
+        set_attr_dict(self.op, "arg_attrs", self.get_num_arguments(), pos, attrs)
+    }
+    /// Sets a single attribute of argument `pos`, keeping the others.
+    pub fn set_arg_attr(&self, pos: usize, name: &str, attr: Attr<'ctx>) -> Result<(), String> {
+        let attrs = self
+            .get_arg_attrs(pos)
+            .ok_or_else(|| format!("argument {} out of range", pos))?;
+        self.set_arg_attrs(pos, with_attr(self.op, attrs, name, attr))
+    }
+    pub fn get_result_attrs(&self, pos: usize) -> Option<DictionaryAttr<'ctx>> {
+        get_attr_dict(self.op, "res_attrs", self.get_num_results(), pos)
+    }
+    pub fn set_result_attrs(&self, pos: usize, attrs: DictionaryAttr<'ctx>) -> Result<(), String> {
+        set_attr_dict(self.op, "res_attrs", self.get_num_results(), pos, attrs)
+    }
+    pub fn set_result_attr(&self, pos: usize, name: &str, attr: Attr<'ctx>) -> Result<(), String> {
+        let attrs = self
+            .get_result_attrs(pos)
+            .ok_or_else(|| format!("result {} out of range", pos))?;
+        self.set_result_attrs(pos, with_attr(self.op, attrs, name, attr))
+    }
+}
+
+fn get_attr_dict<'ctx>(
+    op: OperationRef<'ctx>,
+    array_name: &str,
+    num: usize,
+    pos: usize,
+) -> Option<DictionaryAttr<'ctx>> {
+    if pos >= num {
+        return None;
+    }
+    let array: ArrayAttr = op.get_attr_by_name(array_name).dyn_cast_or_null();
+    if array.is_null() || pos >= array.len() {
+        let ctx = op.get_context();
+        return Some(same_context(op, DictionaryAttr::get(&ctx, &[])));
+    }
+    Some(array.get_element(pos).dyn_cast())
+}
+
+fn set_attr_dict<'ctx>(
+    op: OperationRef<'ctx>,
+    array_name: &str,
+    num: usize,
+    pos: usize,
+    attrs: DictionaryAttr<'ctx>,
+) -> Result<(), String> {
+    if pos >= num {
+        return Err(format!(
+            "position {} out of range, expected less than {}",
+            pos, num
+        ));
+    }
+    let dicts: Vec<Attr> = (0..num)
+        .map(|i| {
+            if i == pos {
+                attrs.into()
+            } else {
+                get_attr_dict(op, array_name, num, i).unwrap().into()
+            }
+        })
+        .collect();
+    let ctx = op.get_context();
+    op.set_attr_by_name(array_name, ArrayAttr::get(&ctx, &dicts).into());
+    Ok(())
+}
+
+fn with_attr<'ctx>(
+    op: OperationRef<'ctx>,
+    dict: DictionaryAttr<'ctx>,
+    name: &str,
+    attr: Attr<'ctx>,
+) -> DictionaryAttr<'ctx> {
+    let mut attrs: Vec<NamedAttr> = (0..dict.len())
+        .map(|i| dict.get_element(i))
+        .filter(|named| named.name.str() != name)
+        .collect();
+    let ctx = op.get_context();
+    attrs.push(NamedAttr::get(&ctx, name, attr));
+    same_context(op, DictionaryAttr::get(&ctx, &attrs))
+}
+
+/// Rebinds a dictionary created through the `ContextRef` of `op` to the lifetime of `op`.
+fn same_context<'ctx>(op: OperationRef<'ctx>, dict: DictionaryAttr) -> DictionaryAttr<'ctx> {
+    Attr::from_handle_same_context(dict.handle.handle, &op).dyn_cast()
+}
+
+define_op_view!(CallOp, "func.call");
+
+impl<'ctx> CallOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        callee: &str,
+        results: &[Type<'ctx>],
+        operands: &[Value<'ctx>],
+    ) -> Self {
+        let ctx = builder.get_context();
+        let callee = NamedAttr::get(ctx, "callee", FlatSymbolRefAttr::get(ctx, callee).into());
+        Self {
+            op: builder.create_operation("func.call", loc, operands, results, &[callee]),
+        }
+    }
+    /// Creates a call of `func`, with the result types of its function type.
+    pub fn create_for(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        func: FuncOp<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Self {
+        let ty = func.get_function_type();
+        let results: Vec<Type> = (0..ty.get_num_results())
+            .map(|i| ty.get_result(i))
+            .collect();
+        Self::create(builder, loc, func.get_name(), &results, operands)
+    }
+    pub fn get_callee(&self) -> &'ctx str {
+        let attr: FlatSymbolRefAttr = self.op.get_attr_by_name("callee").dyn_cast();
+        attr.get_value()
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+    pub fn get_result(&self, pos: usize) -> Value<'ctx> {
+        self.op.get_result(pos)
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+define_op_view!(ReturnOp, "func.return");
+
+impl<'ctx> ReturnOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Self {
+        Self {
+            op: builder.create_operation("func.return", loc, operands, &[], &[]),
+        }
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+#[cfg(test)]
+mod func_test {
+    use super::*;
+    use crate::dialect::arith;
+    use crate::dialect::*;
+    use crate::module::*;
+
+    #[test]
+    fn build() {
+        let ctx = Context::create();
+        UpstreamDialectName::Arith.load_dialect(&ctx);
+        UpstreamDialectName::Func.load_dialect(&ctx);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let i32_ty: Type = IntegerType::get(&ctx, 32).into();
+        let add_ty = FunctionType::get(&ctx, &[i32_ty, i32_ty], &[i32_ty]);
+
+        let add = FuncOp::create(&builder, loc, "add", add_ty, SymbolVisibility::Public);
+        assert_eq!(add.get_name(), "add");
+        assert!(add.get_function_type() == add_ty);
+        assert_eq!(add.get_visibility(), Some(SymbolVisibility::Public));
+        assert!(!add.is_external());
+        assert_eq!(add.arguments().len(), 2);
+        {
+            let body = OpBuilder::at_block_end(&ctx, add.get_entry_block());
+            let sum = arith::AddIOp::create(
+                &body,
+                loc,
+                add.get_argument(0).unwrap(),
+                add.get_argument(1).unwrap(),
+            )
+            .get_result();
+            ReturnOp::create(&body, loc, &[sum]);
+        }
+
+        let ext = FuncOp::declare(&builder, loc, "ext", add_ty);
+        assert!(ext.is_external());
+        assert!(ext.get_argument(0).is_none());
+        assert_eq!(ext.arguments().len(), 0);
+        assert_eq!(ext.get_arg_attrs(1).unwrap().len(), 0);
+        assert!(ext.get_arg_attrs(2).is_none());
+        assert_eq!(ext.get_visibility(), Some(SymbolVisibility::Private));
+
+        let main_ty = FunctionType::get(&ctx, &[], &[i32_ty]);
+        let main = FuncOp::create(&builder, loc, "main", main_ty, SymbolVisibility::Private);
+        let body = OpBuilder::at_block_end(&ctx, main.get_entry_block());
+        let c = arith::ConstantOp::create_int(&body, loc, 1, i32_ty).get_result();
+        let call = CallOp::create_for(&body, loc, add, &[c, c]);
+        assert_eq!(call.get_callee(), "add");
+        assert_eq!(call.get_results().len(), 1);
+        let ext_call = CallOp::create(&body, loc, "ext", &[i32_ty], &[call.get_result(0), c]);
+        let ret = ReturnOp::create(&body, loc, &[ext_call.get_result(0)]);
+        assert!(ret.get_operands() == vec![ext_call.get_result(0)]);
+        main.set_visibility(SymbolVisibility::Public);
+        assert_eq!(main.get_visibility(), Some(SymbolVisibility::Public));
+        main.op
+            .set_attr_by_name("sym_visibility", StringAttr::get(&ctx, "hidden").into());
+        assert_eq!(main.get_visibility(), None);
+        main.set_visibility(SymbolVisibility::Public);
+
+        let unit: Attr = UnitAttr::get(&ctx).into();
+        add.set_arg_attr(1, "test.noalias", unit).unwrap();
+        add.set_arg_attr(1, "test.align", IntegerAttr::get(i32_ty, 4).into())
+            .unwrap();
+        assert!(add.set_arg_attr(2, "test.noalias", unit).is_err());
+        assert_eq!(add.get_arg_attrs(0).unwrap().len(), 0);
+        assert_eq!(add.get_arg_attrs(1).unwrap().len(), 2);
+        assert!(
+            add.get_arg_attrs(1)
+                .unwrap()
+                .get_element_by_name("test.noalias")
+                == unit
+        );
+        assert!(add.get_argument(2).is_none());
+        add.set_result_attr(0, "test.result", unit).unwrap();
+        assert_eq!(add.get_result_attrs(0).unwrap().len(), 1);
+        assert!(add.get_result_attrs(1).is_none());
+        assert!(add.set_result_attr(1, "test.result", unit).is_err());
+        assert!(module.get_operation_ref().verify().is_ok());
+
+        let mut callees = vec![];
+        module
+            .get_operation_ref()
+            .walk_typed::<CallOp, _>(WalkOrder::PreOrder, |call| {
+                callees.push(call.get_callee());
+                WalkResult::Advance
+            });
+        assert_eq!(callees, vec!["add", "ext"]);
+    }
+
+    #[test]
+    fn from_op() {
+        let ctx = Context::create();
+        UpstreamDialectName::Func.load_dialect(&ctx);
+        let module = Module::create_parse(
+            &ctx,
+            r#"
+func.func private @f(%a: i64 {test.arg}) -> (i64, i64) {
+  return %a, %a : i64, i64
+}
+"#,
+        )
+        .unwrap();
+        let op = module.get_body().get_first_operation();
+        let func = FuncOp::from_op(op).unwrap();
+        assert_eq!(func.get_name(), "f");
+        assert_eq!(func.get_visibility(), Some(SymbolVisibility::Private));
+        assert_eq!(func.get_num_arguments(), 1);
+        assert_eq!(func.get_num_results(), 2);
+        assert_eq!(func.get_arg_attrs(0).unwrap().len(), 1);
+        assert_eq!(func.get_result_attrs(1).unwrap().len(), 0);
+        let ret = ReturnOp::from_op(func.get_entry_block().get_terminator()).unwrap();
+        assert!(
+            ret.get_operands()
+                == vec![func.get_argument(0).unwrap(), func.get_argument(0).unwrap()]
+        );
+        assert!(CallOp::from_op(op).is_none());
+    }
+}
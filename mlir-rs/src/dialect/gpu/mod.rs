@@ -0,0 +1,752 @@
+use super::{append_block, create_region_op, get_operand_segment, get_operand_segment_sizes};
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::common::*;
+use crate::context::*;
+use crate::diagnostic::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::support::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+use mlir_capi::Dialect_::GPU;
+use mlir_capi::IR::*;
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+use strum::EnumIter;
+
+/// Returns the `!gpu.async.token` type, fails if the gpu dialect is not loaded in `ctx`.
+pub fn get_async_token_type(ctx: &Context) -> Result<Type, Diagnostics> {
+    Type::parse(ctx, "!gpu.async.token")
+}
+
+/// Marks `op`, usually a builtin module, as containing `gpu.module`s, which is needed for
+/// `gpu.launch_func` to refer to kernels.
+pub fn set_container_module(op: OperationRef) {
+    let ctx = op.get_context();
+    op.set_attr_by_name("gpu.container_module", UnitAttr::get(&ctx).into());
+}
+
+/// Quotes `s` as an MLIR string literal, escaping quotes, backslashes and non-printable bytes.
+fn quote_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for byte in s.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                quoted.push('\\');
+                quoted.push(byte as char);
+            }
+            0x20..=0x7e => quoted.push(byte as char),
+            _ => quoted.push_str(&format!("\\{:02X}", byte)),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the `#nvvm.target` attribute, which requires the nvvm dialect to be loaded in `ctx`.
+pub fn nvvm_target_attr_get<'ctx>(
+    ctx: &'ctx Context,
+    triple: &str,
+    chip: &str,
+    features: &str,
+    opt_level: u32,
+) -> Result<Attr<'ctx>, Diagnostics<'ctx>> {
+    Attr::parse(
+        ctx,
+        &format!(
+            "#nvvm.target<O = {}, triple = {}, chip = {}, features = {}>",
+            opt_level,
+            quote_string(triple),
+            quote_string(chip),
+            quote_string(features)
+        ),
+    )
+}
+
+/// Returns the `#rocdl.target` attribute, which requires the rocdl dialect to be loaded in
+/// `ctx`.
+pub fn rocdl_target_attr_get<'ctx>(
+    ctx: &'ctx Context,
+    triple: &str,
+    chip: &str,
+    opt_level: u32,
+) -> Result<Attr<'ctx>, Diagnostics<'ctx>> {
+    Attr::parse(
+        ctx,
+        &format!(
+            "#rocdl.target<O = {}, triple = {}, chip = {}>",
+            opt_level,
+            quote_string(triple),
+            quote_string(chip)
+        ),
+    )
+}
+
+unsafe fn is_a_gpu_object_attr(attr: Attr) -> CBool {
+    GPU::FFIVal_::mlirAttributeIsAGPUObjectAttr(attr)
+}
+
+mlir_impl_macros::define_dialect_attrs!((GPUObject, is_a_gpu_object_attr));
+
+/// The format of the object held by a `#gpu.object`.
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompilationTarget {
+    Offload = 1,
+    Assembly = 2,
+    Binary = 3,
+    Fatbin = 4,
+}
+
+impl CompilationTarget {
+    pub fn from_u32(value: u32) -> Option<Self> {
+        <Self as strum::IntoEnumIterator>::iter().find(|format| *format as u32 == value)
+    }
+}
+
+impl<'ctx> GPUObjectAttr<'ctx> {
+    /// Creates a `#gpu.object` holding `object` compiled for `target`. `properties` is either a
+    /// null attribute or a `DictionaryAttr`.
+    pub fn get(
+        ctx: &'ctx Context,
+        target: Attr<'ctx>,
+        format: CompilationTarget,
+        object: &[u8],
+        properties: Attr<'ctx>,
+    ) -> Self {
+        let handle = unsafe {
+            GPU::FFIVal_::mlirGPUObjectAttrGet(
+                ctx,
+                target,
+                format as u32,
+                StrRef::from(object),
+                properties,
+            )
+        };
+        let attr = unsafe { Attr::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(attr) }
+    }
+    pub fn get_target(self) -> Attr<'ctx> {
+        let handle = unsafe { GPU::FFIVal_::mlirGPUObjectAttrGetTarget(self) };
+        Attr::from_handle_same_context(handle, &self)
+    }
+    pub fn get_format(self) -> CompilationTarget {
+        let format: u32 = unsafe { GPU::FFIVal_::mlirGPUObjectAttrGetFormat(self) };
+        CompilationTarget::from_u32(format).expect("invalid object format")
+    }
+    pub fn get_object(self) -> &'ctx [u8] {
+        let str_ref: StrRef = unsafe { GPU::FFIVal_::mlirGPUObjectAttrGetObject(self) };
+        str_ref.into()
+    }
+    pub fn has_properties(self) -> bool {
+        to_rbool(unsafe { GPU::FFIVal_::mlirGPUObjectAttrHasProperties(self) })
+    }
+    pub fn get_properties(self) -> DictionaryAttr<'ctx> {
+        let handle = unsafe { GPU::FFIVal_::mlirGPUObjectAttrGetProperties(self) };
+        Attr::from_handle_same_context(handle, &self).dyn_cast()
+    }
+}
+
+/// Returns the optional async token result of `op`.
+fn get_async_token<'ctx>(op: OperationRef<'ctx>) -> Option<Value<'ctx>> {
+    if op.get_num_results() == 0 {
+        None
+    } else {
+        Some(op.get_result(0))
+    }
+}
+
+fn get_async_result_types<'ctx>(
+    ctx: &'ctx Context,
+    is_async: bool,
+) -> Result<Vec<Type<'ctx>>, String> {
+    if is_async {
+        let ty = get_async_token_type(ctx).map_err(|diags| diags.to_string())?;
+        Ok(vec![ty])
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Returns the number of async dependencies of `op`, which come first in its operands.
+fn get_num_async_dependencies(op: OperationRef) -> usize {
+    get_operand_segment(op, 0).len()
+}
+
+define_op_view!(GPUModuleOp, "gpu.module");
+
+impl<'ctx> GPUModuleOp<'ctx> {
+    /// Creates a `gpu.module` with an empty body, whose kernels are compiled for `targets`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        name: &str,
+        targets: &[Attr<'ctx>],
+    ) -> Self {
+        let ctx = builder.get_context();
+        let mut attrs = vec![NamedAttr::get(
+            ctx,
+            "sym_name",
+            StringAttr::get(ctx, name).into(),
+        )];
+        if !targets.is_empty() {
+            attrs.push(NamedAttr::get(
+                ctx,
+                "targets",
+                ArrayAttr::get(ctx, targets).into(),
+            ));
+        }
+        let op = create_region_op(builder, "gpu.module", loc, &[], &[], &attrs, 1);
+        append_block(op.get_region(0), &[], loc);
+        Self { op }
+    }
+    pub fn get_name(&self) -> &'ctx str {
+        let attr: StringAttr = self.op.get_attr_by_name("sym_name").dyn_cast();
+        attr.get_value()
+    }
+    pub fn get_targets(&self) -> Vec<Attr<'ctx>> {
+        let attr = self.op.get_attr_by_name("targets");
+        if attr.is_null() {
+            return vec![];
+        }
+        let targets: ArrayAttr = attr.dyn_cast();
+        (0..targets.len())
+            .map(|pos| targets.get_element(pos))
+            .collect()
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+}
+
+define_op_view!(GPUFuncOp, "gpu.func");
+
+impl<'ctx> GPUFuncOp<'ctx> {
+    /// Creates a `gpu.func` with an entry block whose arguments have the input types of `ty`.
+    /// Kernels are marked with the `gpu.kernel` attribute and can be launched from the host.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        name: &str,
+        ty: FunctionType<'ctx>,
+        is_kernel: bool,
+    ) -> Self {
+        let ctx = builder.get_context();
+        let mut attrs = vec![
+            NamedAttr::get(ctx, "sym_name", StringAttr::get(ctx, name).into()),
+            NamedAttr::get(ctx, "function_type", TypeAttr::get(ty.into()).into()),
+        ];
+        if is_kernel {
+            attrs.push(NamedAttr::get(ctx, "gpu.kernel", UnitAttr::get(ctx).into()));
+        }
+        let op = create_region_op(builder, "gpu.func", loc, &[], &[], &attrs, 1);
+        let arg_types: Vec<Type> = (0..ty.get_num_inputs()).map(|i| ty.get_input(i)).collect();
+        append_block(op.get_region(0), &arg_types, loc);
+        Self { op }
+    }
+    pub fn get_name(&self) -> &'ctx str {
+        let attr: StringAttr = self.op.get_attr_by_name("sym_name").dyn_cast();
+        attr.get_value()
+    }
+    pub fn get_function_type(&self) -> FunctionType<'ctx> {
+        let attr: TypeAttr = self.op.get_attr_by_name("function_type").dyn_cast();
+        attr.get_value().dyn_cast()
+    }
+    pub fn is_kernel(&self) -> bool {
+        !self.op.get_attr_by_name("gpu.kernel").is_null()
+    }
+    pub fn get_entry_block(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    /// Returns the block argument `pos`, or `None` if it is out of range.
+    pub fn get_argument(&self, pos: usize) -> Option<Value<'ctx>> {
+        let entry = self.get_entry_block();
+        if entry.is_null() || pos >= entry.get_num_arguments() {
+            return None;
+        }
+        Some(entry.get_argument(pos))
+    }
+}
+
+define_op_view!(ReturnOp, "gpu.return");
+
+impl<'ctx> ReturnOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> Self {
+        Self {
+            op: builder.create_operation("gpu.return", loc, operands, &[], &[]),
+        }
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+define_op_view!(TerminatorOp, "gpu.terminator");
+
+impl<'ctx> TerminatorOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>) -> Self {
+        Self {
+            op: builder.create_operation("gpu.terminator", loc, &[], &[], &[]),
+        }
+    }
+}
+
+/// The index values available in the body of a `gpu.launch`, each in the x, y, z order.
+#[derive(Copy, Clone)]
+pub struct LaunchIds<'ctx> {
+    pub block_ids: [Value<'ctx>; 3],
+    pub thread_ids: [Value<'ctx>; 3],
+    pub grid_size: [Value<'ctx>; 3],
+    pub block_size: [Value<'ctx>; 3],
+}
+
+define_op_view!(LaunchOp, "gpu.launch");
+
+impl<'ctx> LaunchOp<'ctx> {
+    /// Creates a `gpu.launch` of a `grid_size` grid of `block_size` blocks, whose body is
+    /// populated by `body`. If `is_async`, the launch returns an async token and doesn't block.
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        grid_size: [Value<'ctx>; 3],
+        block_size: [Value<'ctx>; 3],
+        async_dependencies: &[Value<'ctx>],
+        is_async: bool,
+        body: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&OpBuilder<'ctx>, &LaunchIds<'ctx>),
+    {
+        let ctx = builder.get_context();
+        let mut operands = async_dependencies.to_vec();
+        operands.extend_from_slice(&grid_size);
+        operands.extend_from_slice(&block_size);
+        let segments = get_operand_segment_sizes(
+            builder,
+            &[async_dependencies.len(), 1, 1, 1, 1, 1, 1, 0, 0, 0, 0],
+        );
+        let op = create_region_op(
+            builder,
+            "gpu.launch",
+            loc,
+            &operands,
+            &get_async_result_types(ctx, is_async)?,
+            &[segments],
+            1,
+        );
+
+        let index_ty: Type = IndexType::get(ctx).into();
+        let block = append_block(op.get_region(0), &[index_ty; 12], loc);
+        let args: Vec<Value> = block.arguments().collect();
+        let ids = LaunchIds {
+            block_ids: [args[0], args[1], args[2]],
+            thread_ids: [args[3], args[4], args[5]],
+            grid_size: [args[6], args[7], args[8]],
+            block_size: [args[9], args[10], args[11]],
+        };
+        let body_builder = OpBuilder::at_block_end(ctx, block);
+        body(&body_builder, &ids);
+        TerminatorOp::create(&body_builder, loc);
+        Ok(Self { op })
+    }
+    pub fn get_async_token(&self) -> Option<Value<'ctx>> {
+        get_async_token(self.op)
+    }
+    pub fn get_async_dependencies(&self) -> Vec<Value<'ctx>> {
+        let num_dependencies = get_num_async_dependencies(self.op);
+        self.op.operands().take(num_dependencies).collect()
+    }
+    pub fn get_grid_size(&self) -> [Value<'ctx>; 3] {
+        let start = get_num_async_dependencies(self.op);
+        [0, 1, 2].map(|pos| self.op.get_operand(start + pos))
+    }
+    pub fn get_block_size(&self) -> [Value<'ctx>; 3] {
+        let start = get_num_async_dependencies(self.op) + 3;
+        [0, 1, 2].map(|pos| self.op.get_operand(start + pos))
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+}
+
+define_op_view!(LaunchFuncOp, "gpu.launch_func");
+
+impl<'ctx> LaunchFuncOp<'ctx> {
+    /// Creates a `gpu.launch_func` of `kernel`, which must be a kernel in `module`, passing it
+    /// `kernel_operands`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        module: GPUModuleOp<'ctx>,
+        kernel: GPUFuncOp<'ctx>,
+        grid_size: [Value<'ctx>; 3],
+        block_size: [Value<'ctx>; 3],
+        kernel_operands: &[Value<'ctx>],
+        async_dependencies: &[Value<'ctx>],
+        is_async: bool,
+    ) -> Result<Self, String> {
+        if !kernel.is_kernel() {
+            return Err(format!("{} is not a kernel", kernel.get_name()));
+        }
+        let kernel_ty = kernel.get_function_type();
+        let types_match = kernel_ty.get_num_inputs() == kernel_operands.len()
+            && kernel_operands
+                .iter()
+                .enumerate()
+                .all(|(pos, operand)| operand.get_type() == kernel_ty.get_input(pos));
+        if !types_match {
+            return Err(format!(
+                "kernel operands do not match the type {} of {}",
+                Into::<Type>::into(kernel_ty),
+                kernel.get_name()
+            ));
+        }
+
+        let ctx = builder.get_context();
+        let mut operands = async_dependencies.to_vec();
+        operands.extend_from_slice(&grid_size);
+        operands.extend_from_slice(&block_size);
+        operands.extend_from_slice(kernel_operands);
+        let kernel_ref = SymbolRefAttr::get(
+            ctx,
+            module.get_name(),
+            &[FlatSymbolRefAttr::get(ctx, kernel.get_name()).into()],
+        );
+        let attrs = [
+            NamedAttr::get(ctx, "kernel", kernel_ref.into()),
+            get_operand_segment_sizes(
+                builder,
+                &[
+                    async_dependencies.len(),
+                    1,
+                    1,
+                    1,
+                    1,
+                    1,
+                    1,
+                    0,
+                    0,
+                    0,
+                    0,
+                    kernel_operands.len(),
+                    0,
+                ],
+            ),
+        ];
+        let op = builder.create_operation(
+            "gpu.launch_func",
+            loc,
+            &operands,
+            &get_async_result_types(ctx, is_async)?,
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_async_token(&self) -> Option<Value<'ctx>> {
+        get_async_token(self.op)
+    }
+    pub fn get_async_dependencies(&self) -> Vec<Value<'ctx>> {
+        let num_dependencies = get_num_async_dependencies(self.op);
+        self.op.operands().take(num_dependencies).collect()
+    }
+    pub fn get_kernel_module_name(&self) -> &'ctx str {
+        let attr: SymbolRefAttr = self.op.get_attr_by_name("kernel").dyn_cast();
+        attr.get_root_ref()
+    }
+    pub fn get_kernel_name(&self) -> &'ctx str {
+        let attr: SymbolRefAttr = self.op.get_attr_by_name("kernel").dyn_cast();
+        attr.get_leaf_ref()
+    }
+    pub fn get_kernel_operands(&self) -> Vec<Value<'ctx>> {
+        // The kernel operands follow the async dependencies, the grid and block sizes, the
+        // optional cluster sizes and the optional dynamic shared memory size.
+        let segment = get_operand_segment(self.op, 11);
+        self.op
+            .operands()
+            .skip(segment.start)
+            .take(segment.len())
+            .collect()
+    }
+}
+
+define_op_view!(BarrierOp, "gpu.barrier");
+
+impl<'ctx> BarrierOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>) -> Self {
+        Self {
+            op: builder.create_operation("gpu.barrier", loc, &[], &[], &[]),
+        }
+    }
+}
+
+define_op_view!(WaitOp, "gpu.wait");
+
+impl<'ctx> WaitOp<'ctx> {
+    /// Creates a `gpu.wait` on `async_dependencies`. If `is_async`, the wait returns an async
+    /// token instead of blocking the host.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        async_dependencies: &[Value<'ctx>],
+        is_async: bool,
+    ) -> Result<Self, String> {
+        let ctx = builder.get_context();
+        let op = builder.create_operation(
+            "gpu.wait",
+            loc,
+            async_dependencies,
+            &get_async_result_types(ctx, is_async)?,
+            &[],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_async_token(&self) -> Option<Value<'ctx>> {
+        get_async_token(self.op)
+    }
+    pub fn get_async_dependencies(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+define_op_view!(BinaryOp, "gpu.binary");
+
+impl<'ctx> BinaryOp<'ctx> {
+    /// Creates a `gpu.binary` holding the compiled `objects` of a `gpu.module`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        name: &str,
+        objects: &[GPUObjectAttr<'ctx>],
+    ) -> Self {
+        let ctx = builder.get_context();
+        let objects: Vec<Attr> = objects.iter().map(|object| (*object).into()).collect();
+        let attrs = [
+            NamedAttr::get(ctx, "sym_name", StringAttr::get(ctx, name).into()),
+            NamedAttr::get(ctx, "objects", ArrayAttr::get(ctx, &objects).into()),
+        ];
+        Self {
+            op: builder.create_operation("gpu.binary", loc, &[], &[], &attrs),
+        }
+    }
+    pub fn get_objects(&self) -> Vec<GPUObjectAttr<'ctx>> {
+        let objects: ArrayAttr = self.op.get_attr_by_name("objects").dyn_cast();
+        (0..objects.len())
+            .map(|pos| objects.get_element(pos).dyn_cast())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod gpu_test {
+    use super::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::FuncOp;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::GPU,
+        UpstreamDialectName::MemRef,
+        UpstreamDialectName::NVVM,
+    ];
+
+    #[test]
+    fn kernel_and_launch_func() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        set_container_module(module.get_operation_ref());
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let buffer_ty: Type = MemRefType::contiguous_get(f32_ty, &[64], Attr::create_null()).into();
+
+        let target =
+            nvvm_target_attr_get(&ctx, "nvptx64-nvidia-cuda", "sm_80", "+ptx76", 2).unwrap();
+        let gpu_module = GPUModuleOp::create(&builder, loc, "kernels", &[target]);
+        assert!(gpu_module.get_targets() == vec![target]);
+        let module_builder = OpBuilder::at_block_end(&ctx, gpu_module.get_body());
+        let kernel_ty = FunctionType::get(&ctx, &[buffer_ty, f32_ty], &[]);
+        let kernel = GPUFuncOp::create(&module_builder, loc, "scale", kernel_ty, true);
+        assert!(kernel.is_kernel());
+        assert!(kernel.get_argument(1).unwrap().get_type() == f32_ty);
+        assert!(kernel.get_argument(2).is_none());
+        let kernel_builder = OpBuilder::at_block_end(&ctx, kernel.get_entry_block());
+        BarrierOp::create(&kernel_builder, loc);
+        ReturnOp::create(&kernel_builder, loc, &[]);
+        let helper_ty = FunctionType::get(&ctx, &[], &[]);
+        let helper = GPUFuncOp::create(&module_builder, loc, "helper", helper_ty, false);
+        ReturnOp::create(
+            &OpBuilder::at_block_end(&ctx, helper.get_entry_block()),
+            loc,
+            &[],
+        );
+
+        let host_ty = FunctionType::get(&ctx, &[buffer_ty, f32_ty], &[]);
+        let host = FuncOp::create(&builder, loc, "host", host_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, host.get_entry_block());
+        let c1 = arith::ConstantOp::create_index(&body, loc, 1).get_result();
+        let c64 = arith::ConstantOp::create_index(&body, loc, 64).get_result();
+        let operands = [host.get_argument(0).unwrap(), host.get_argument(1).unwrap()];
+        let start = WaitOp::create(&body, loc, &[], true).unwrap();
+        let token = start.get_async_token().unwrap();
+        assert!(token.get_type() == get_async_token_type(&ctx).unwrap());
+        let launch = LaunchFuncOp::create(
+            &body,
+            loc,
+            gpu_module,
+            kernel,
+            [c1, c1, c1],
+            [c64, c1, c1],
+            &operands,
+            &[token],
+            true,
+        )
+        .unwrap();
+        assert_eq!(launch.get_kernel_module_name(), "kernels");
+        assert_eq!(launch.get_kernel_name(), "scale");
+        assert!(launch.get_kernel_operands() == operands.to_vec());
+        assert!(launch.get_async_dependencies() == vec![token]);
+        let end = WaitOp::create(&body, loc, &[launch.get_async_token().unwrap()], false).unwrap();
+        assert!(end.get_async_token().is_none());
+        assert!(LaunchFuncOp::create(
+            &body,
+            loc,
+            gpu_module,
+            kernel,
+            [c1, c1, c1],
+            [c64, c1, c1],
+            &operands[..1],
+            &[],
+            false,
+        )
+        .is_err());
+        assert!(LaunchFuncOp::create(
+            &body,
+            loc,
+            gpu_module,
+            helper,
+            [c1, c1, c1],
+            [c64, c1, c1],
+            &[],
+            &[],
+            false,
+        )
+        .is_err());
+        crate::dialect::func::ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+
+    #[test]
+    fn launch() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let buffer_ty: Type = MemRefType::contiguous_get(f32_ty, &[64], Attr::create_null()).into();
+        let func_ty = FunctionType::get(&ctx, &[buffer_ty], &[]);
+        let func = FuncOp::create(&builder, loc, "fill", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let buffer = func.get_argument(0).unwrap();
+        let c1 = arith::ConstantOp::create_index(&body, loc, 1).get_result();
+        let c64 = arith::ConstantOp::create_index(&body, loc, 64).get_result();
+        let mut thread_id = None;
+        let launch = LaunchOp::create(
+            &body,
+            loc,
+            [c1, c1, c1],
+            [c64, c1, c1],
+            &[],
+            false,
+            |b, ids| {
+                thread_id = Some(ids.thread_ids[0]);
+                let zero = arith::ConstantOp::create_float(b, loc, 0.0, f32_ty).get_result();
+                crate::dialect::memref::StoreOp::create(b, loc, zero, buffer, &[ids.thread_ids[0]])
+                    .unwrap();
+                BarrierOp::create(b, loc);
+            },
+        )
+        .unwrap();
+        assert!(launch.get_async_token().is_none());
+        assert!(launch.get_grid_size() == [c1, c1, c1]);
+        assert!(launch.get_block_size() == [c64, c1, c1]);
+        assert!(launch.get_body().get_argument(3) == thread_id.unwrap());
+        let token = WaitOp::create(&body, loc, &[], true)
+            .unwrap()
+            .get_async_token()
+            .unwrap();
+        let async_launch = LaunchOp::create(
+            &body,
+            loc,
+            [c1, c1, c1],
+            [c1, c1, c1],
+            &[token],
+            true,
+            |_, _| {},
+        )
+        .unwrap();
+        assert!(async_launch.get_async_dependencies() == vec![token]);
+        WaitOp::create(
+            &body,
+            loc,
+            &[async_launch.get_async_token().unwrap()],
+            false,
+        )
+        .unwrap();
+        crate::dialect::func::ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+
+    #[test]
+    fn object_attr() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let target = nvvm_target_attr_get(&ctx, "nvptx64-nvidia-cuda", "sm_90", "", 3).unwrap();
+        let object = GPUObjectAttr::get(
+            &ctx,
+            target,
+            CompilationTarget::Binary,
+            b"\x7fELF\x00",
+            Attr::create_null(),
+        );
+        assert!(object.get_target() == target);
+        assert_eq!(object.get_format(), CompilationTarget::Binary);
+        assert_eq!(object.get_object(), b"\x7fELF\x00");
+        assert!(!object.has_properties());
+        let binary = BinaryOp::create(&builder, loc, "kernels", &[object]);
+        assert!(binary.get_objects() == vec![object]);
+        assert!(module.get_operation_ref().verify().is_ok());
+        assert!(rocdl_target_attr_get(&ctx, "amdgcn-amd-amdhsa", "gfx90a", 2).is_err());
+    }
+
+    #[test]
+    fn target_attr_quoting() {
+        assert_eq!(
+            quote_string("a\"b\\c\n\u{e9}"),
+            "\"a\\\"b\\\\c\\0A\\C3\\A9\""
+        );
+        let ctx = create_context_with(DIALECTS);
+        let features = "+ptx76,\"\\";
+        let target =
+            nvvm_target_attr_get(&ctx, "nvptx64-nvidia-cuda", "sm_80", features, 2).unwrap();
+        let reparsed = Attr::parse(&ctx, &target.to_string()).unwrap();
+        assert!(reparsed == target);
+    }
+}
@@ -0,0 +1,448 @@
+use super::{append_block, create_region_op, get_operand_segment, get_operand_segment_sizes};
+use crate::affine_map::*;
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::context::*;
+use crate::diagnostic::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+use mlir_capi::Dialect_::Linalg;
+
+use std::str::FromStr;
+
+use strum::EnumIter;
+
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IteratorType {
+    Parallel,
+    Reduction,
+}
+
+impl IteratorType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IteratorType::Parallel => "parallel",
+            IteratorType::Reduction => "reduction",
+        }
+    }
+    /// Returns the `#linalg.iterator_type` attribute, fails if the linalg dialect is not loaded
+    /// in `ctx`.
+    pub fn get_attr<'ctx>(self, ctx: &'ctx Context) -> Result<Attr<'ctx>, Diagnostics<'ctx>> {
+        Attr::parse(ctx, &format!("#linalg.iterator_type<{}>", self.as_str()))
+    }
+    fn from_attr(attr: Attr) -> Option<Self> {
+        let ctx = ContextRef::from_handle_same_context(attr.get_context_handle(), &attr);
+        <Self as strum::IntoEnumIterator>::iter()
+            .find(|iterator_type| iterator_type.get_attr(&ctx).map_or(false, |a| a == attr))
+    }
+}
+
+impl FromStr for IteratorType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "parallel" => Ok(IteratorType::Parallel),
+            "reduction" => Ok(IteratorType::Reduction),
+            _ => Err(format!("invalid iterator type '{}'", s)),
+        }
+    }
+}
+
+/// Populates the region of the named linalg operation `op`, which must have been created with
+/// a single empty region.
+fn fill_builtin_named_op_region(op: OperationRef) {
+    unsafe {
+        Linalg::FFIVoid_::mlirLinalgFillBuiltinNamedOpRegion(op);
+    }
+}
+
+/// Returns the rank and the element type of an operand of a linalg operation, which is either a
+/// ranked tensor or memref, or a scalar.
+fn get_rank_and_element_type(value: Value) -> (usize, Type) {
+    let ty = value.get_type();
+    let tensor_ty: RankedTensorType = ty.dyn_cast();
+    if !tensor_ty.is_null() {
+        return (tensor_ty.get_rank() as usize, tensor_ty.get_element_type());
+    }
+    let memref_ty: MemRefType = ty.dyn_cast();
+    if !memref_ty.is_null() {
+        return (memref_ty.get_rank() as usize, memref_ty.get_element_type());
+    }
+    (0, ty)
+}
+
+/// Returns the types of the tensor operands in `outputs`, which are the result types of linalg
+/// operations.
+fn get_result_types<'ctx>(outputs: &[Value<'ctx>]) -> Vec<Type<'ctx>> {
+    outputs
+        .iter()
+        .map(|output| output.get_type())
+        .filter(|ty| IsA::<RankedTensorType>::is_a_non_null(*ty))
+        .collect()
+}
+
+define_op_view!(YieldOp, "linalg.yield");
+
+impl<'ctx> YieldOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, values: &[Value<'ctx>]) -> Self {
+        Self {
+            op: builder.create_operation("linalg.yield", loc, values, &[], &[]),
+        }
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+define_op_view!(GenericOp, "linalg.generic");
+
+impl<'ctx> GenericOp<'ctx> {
+    /// Creates a `linalg.generic` reading `inputs` and writing `outputs`, with one indexing map
+    /// per operand and one iterator type per loop. The body closure receives the scalar block
+    /// arguments, the inputs followed by the outputs, and returns the values yielded for the
+    /// outputs.
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        inputs: &[Value<'ctx>],
+        outputs: &[Value<'ctx>],
+        indexing_maps: &[AffineMap<'ctx>],
+        iterator_types: &[IteratorType],
+        body: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&OpBuilder<'ctx>, &[Value<'ctx>]) -> Vec<Value<'ctx>>,
+    {
+        let mut operands = inputs.to_vec();
+        operands.extend_from_slice(outputs);
+        if indexing_maps.len() != operands.len() {
+            return Err(format!(
+                "expected {} indexing maps, got {}",
+                operands.len(),
+                indexing_maps.len()
+            ));
+        }
+        let mut arg_types = Vec::with_capacity(operands.len());
+        for (pos, (operand, map)) in operands.iter().zip(indexing_maps).enumerate() {
+            let (rank, elem_ty) = get_rank_and_element_type(*operand);
+            if map.get_num_dims() != iterator_types.len() {
+                return Err(format!(
+                    "expected indexing map #{} to have {} dims, got {}",
+                    pos,
+                    iterator_types.len(),
+                    map
+                ));
+            }
+            if map.get_num_results() != rank {
+                return Err(format!(
+                    "expected indexing map #{} to have {} results, got {}",
+                    pos, rank, map
+                ));
+            }
+            arg_types.push(elem_ty);
+        }
+
+        let ctx = builder.get_context();
+        let maps: Vec<Attr> = indexing_maps
+            .iter()
+            .map(|map| AffineMapAttr::get(*map).into())
+            .collect();
+        let iterators: Vec<Attr> = iterator_types
+            .iter()
+            .map(|iterator_type| iterator_type.get_attr(ctx))
+            .collect::<Result<_, _>>()
+            .map_err(|diags| diags.to_string())?;
+        let attrs = [
+            NamedAttr::get(ctx, "indexing_maps", ArrayAttr::get(ctx, &maps).into()),
+            NamedAttr::get(
+                ctx,
+                "iterator_types",
+                ArrayAttr::get(ctx, &iterators).into(),
+            ),
+            get_operand_segment_sizes(builder, &[inputs.len(), outputs.len()]),
+        ];
+        let result_types = get_result_types(outputs);
+        let op = create_region_op(
+            builder,
+            "linalg.generic",
+            loc,
+            &operands,
+            &result_types,
+            &attrs,
+            1,
+        );
+
+        let block = append_block(op.get_region(0), &arg_types, loc);
+        let body_builder = OpBuilder::at_block_end(ctx, block);
+        let args: Vec<Value> = block.arguments().collect();
+        let yielded = body(&body_builder, &args);
+        YieldOp::create(&body_builder, loc, &yielded);
+        Ok(Self { op })
+    }
+    fn get_num_inputs(&self) -> usize {
+        get_operand_segment(self.op, 0).len()
+    }
+    pub fn get_inputs(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().take(self.get_num_inputs()).collect()
+    }
+    pub fn get_outputs(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(self.get_num_inputs()).collect()
+    }
+    pub fn get_indexing_maps(&self) -> Vec<AffineMap<'ctx>> {
+        let maps: ArrayAttr = self.op.get_attr_by_name("indexing_maps").dyn_cast();
+        (0..maps.len())
+            .map(|pos| {
+                let map: AffineMapAttr = maps.get_element(pos).dyn_cast();
+                map.get_value()
+            })
+            .collect()
+    }
+    pub fn get_iterator_types(&self) -> Vec<IteratorType> {
+        let iterators: ArrayAttr = self.op.get_attr_by_name("iterator_types").dyn_cast();
+        (0..iterators.len())
+            .map(|pos| {
+                IteratorType::from_attr(iterators.get_element(pos)).expect("invalid iterator type")
+            })
+            .collect()
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+/// Creates a named linalg operation and populates its region.
+fn create_named_op<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    name: &'static str,
+    loc: Location<'ctx>,
+    inputs: &[Value<'ctx>],
+    outputs: &[Value<'ctx>],
+) -> OperationRef<'ctx> {
+    let mut operands = inputs.to_vec();
+    operands.extend_from_slice(outputs);
+    let segments = get_operand_segment_sizes(builder, &[inputs.len(), outputs.len()]);
+    let result_types = get_result_types(outputs);
+    let op = create_region_op(builder, name, loc, &operands, &result_types, &[segments], 1);
+    fill_builtin_named_op_region(op);
+    op
+}
+
+fn get_matrix_shape(value: Value) -> Result<(i64, i64), String> {
+    let ty = value.get_type();
+    let tensor_ty: RankedTensorType = ty.dyn_cast();
+    let memref_ty: MemRefType = ty.dyn_cast();
+    let shape = if !tensor_ty.is_null() && tensor_ty.get_rank() == 2 {
+        (tensor_ty.get_dim_size(0), tensor_ty.get_dim_size(1))
+    } else if !memref_ty.is_null() && memref_ty.get_rank() == 2 {
+        (memref_ty.get_dim_size(0), memref_ty.get_dim_size(1))
+    } else {
+        return Err(format!("expected a 2-D tensor or memref, got {}", ty));
+    };
+    Ok(shape)
+}
+
+fn check_same_size(lhs: i64, rhs: i64) -> bool {
+    RankedTensorType::is_dynamic_size(lhs) || RankedTensorType::is_dynamic_size(rhs) || lhs == rhs
+}
+
+define_op_view!(MatmulOp, "linalg.matmul");
+
+impl<'ctx> MatmulOp<'ctx> {
+    /// Creates a `linalg.matmul` accumulating `lhs * rhs` into `init`. Static dimensions of the
+    /// `MxK`, `KxN` and `MxN` operands must agree.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lhs: Value<'ctx>,
+        rhs: Value<'ctx>,
+        init: Value<'ctx>,
+    ) -> Result<Self, String> {
+        let (m, k) = get_matrix_shape(lhs)?;
+        let (rhs_k, n) = get_matrix_shape(rhs)?;
+        let (init_m, init_n) = get_matrix_shape(init)?;
+        if !check_same_size(k, rhs_k) || !check_same_size(m, init_m) || !check_same_size(n, init_n)
+        {
+            return Err(format!(
+                "incompatible matmul operands {}, {} and {}",
+                lhs.get_type(),
+                rhs.get_type(),
+                init.get_type()
+            ));
+        }
+        let op = create_named_op(builder, "linalg.matmul", loc, &[lhs, rhs], &[init]);
+        Ok(Self { op })
+    }
+    pub fn get_lhs(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_rhs(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_init(&self) -> Value<'ctx> {
+        self.op.get_operand(2)
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+define_op_view!(FillOp, "linalg.fill");
+
+impl<'ctx> FillOp<'ctx> {
+    /// Creates a `linalg.fill` of `output` with the scalar `value`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        value: Value<'ctx>,
+        output: Value<'ctx>,
+    ) -> Result<Self, String> {
+        let (rank, elem_ty) = get_rank_and_element_type(output);
+        if rank == 0 && output.get_type() == elem_ty {
+            return Err(format!(
+                "expected a tensor or memref output, got {}",
+                output.get_type()
+            ));
+        }
+        if value.get_type() != elem_ty {
+            return Err(format!(
+                "expected a value of type {}, got {}",
+                elem_ty,
+                value.get_type()
+            ));
+        }
+        let op = create_named_op(builder, "linalg.fill", loc, &[value], &[output]);
+        Ok(Self { op })
+    }
+    pub fn get_value(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_output(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+#[cfg(test)]
+mod linalg_test {
+    use super::*;
+    use crate::affine_expr::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::tensor;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    use strum::IntoEnumIterator;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::Linalg,
+        UpstreamDialectName::Tensor,
+    ];
+
+    #[test]
+    fn iterator_type() {
+        let ctx = create_context_with(DIALECTS);
+        for iterator_type in IteratorType::iter() {
+            let attr = iterator_type.get_attr(&ctx).unwrap();
+            assert_eq!(IteratorType::from_attr(attr), Some(iterator_type));
+            assert_eq!(
+                iterator_type.as_str().parse::<IteratorType>(),
+                Ok(iterator_type)
+            );
+        }
+        let empty_ctx = Context::create();
+        assert!(IteratorType::Parallel.get_attr(&empty_ctx).is_err());
+    }
+
+    #[test]
+    fn generic_matmul_fill() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let matrix_ty: Type = RankedTensorType::get(&[4, 4], f32_ty, Attr::create_null()).into();
+        let func_ty = FunctionType::get(&ctx, &[matrix_ty, matrix_ty], &[matrix_ty]);
+        let func = FuncOp::create(&builder, loc, "mm", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let a = func.get_argument(0).unwrap();
+        let b = func.get_argument(1).unwrap();
+
+        let init = tensor::EmptyOp::create(&body, loc, matrix_ty.dyn_cast(), &[])
+            .unwrap()
+            .get_result();
+        let zero = arith::ConstantOp::create_float(&body, loc, 0.0, f32_ty).get_result();
+        let fill = FillOp::create(&body, loc, zero, init).unwrap();
+        assert!(FillOp::create(&body, loc, a, init).is_err());
+        let filled = fill.get_results()[0];
+        let matmul = MatmulOp::create(&body, loc, a, b, filled).unwrap();
+        assert!(matmul.get_init() == filled);
+        assert_eq!(
+            matmul
+                .get_op()
+                .get_region(0)
+                .get_first_block()
+                .get_num_arguments(),
+            3
+        );
+
+        let identity = AffineMap::multi_dim_identity_get(&ctx, 2);
+        let parallel = [IteratorType::Parallel, IteratorType::Parallel];
+        let maps = [identity, identity, identity];
+        let generic = GenericOp::create(
+            &body,
+            loc,
+            &[matmul.get_results()[0], a],
+            &[init],
+            &maps,
+            &parallel,
+            |b, args| vec![arith::AddFOp::create(b, loc, args[0], args[1]).get_result()],
+        )
+        .unwrap();
+        assert_eq!(generic.get_inputs().len(), 2);
+        assert!(generic.get_outputs() == vec![init]);
+        assert!(generic.get_indexing_maps() == maps.to_vec());
+        assert_eq!(generic.get_iterator_types(), parallel.to_vec());
+        assert_eq!(generic.get_body().get_num_arguments(), 3);
+
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let transpose = AffineMap::permutation_get(&ctx, &[1, 0]);
+        let row = AffineMap::get(&ctx, 2, 0, &[d0]);
+        assert!(GenericOp::create(
+            &body,
+            loc,
+            &[a],
+            &[init],
+            &[transpose, row],
+            &parallel,
+            |_, args| vec![args[0]],
+        )
+        .is_err());
+        assert!(GenericOp::create(
+            &body,
+            loc,
+            &[a],
+            &[init],
+            &[transpose],
+            &parallel,
+            |_, _| { vec![] }
+        )
+        .is_err());
+        ReturnOp::create(&body, loc, &generic.get_results());
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
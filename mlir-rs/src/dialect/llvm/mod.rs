@@ -0,0 +1,477 @@
+use crate::attribute::*;
+use crate::common::*;
+use crate::context::*;
+use crate::diagnostic::*;
+use crate::location::*;
+use crate::r#type::*;
+use crate::support::*;
+use crate::type_cast::*;
+
+use mlir_capi::Dialect_::LLVM;
+use mlir_capi::IR::*;
+
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use strum::EnumIter;
+
+unsafe fn is_a_llvm_pointer_type(ty: Type) -> CBool {
+    LLVM::FFIVal_::mlirTypeIsALLVMPointerType(ty)
+}
+
+unsafe fn is_a_llvm_struct_type(ty: Type) -> CBool {
+    LLVM::FFIVal_::mlirTypeIsALLVMStructType(ty)
+}
+
+unsafe fn is_a_llvm_array_type(ty: Type) -> CBool {
+    mlir_capi_extra::mlirTypeIsALLVMArrayType(ty.into())
+}
+
+unsafe fn is_a_llvm_function_type(ty: Type) -> CBool {
+    mlir_capi_extra::mlirTypeIsALLVMFunctionType(ty.into())
+}
+
+unsafe fn is_a_llvm_void_type(ty: Type) -> CBool {
+    mlir_capi_extra::mlirTypeIsALLVMVoidType(ty.into())
+}
+
+mlir_impl_macros::define_dialect_types!(
+    (LLVMPointer, is_a_llvm_pointer_type),
+    (LLVMStruct, is_a_llvm_struct_type),
+    (LLVMArray, is_a_llvm_array_type),
+    (LLVMFunction, is_a_llvm_function_type),
+    (LLVMVoid, is_a_llvm_void_type),
+);
+
+impl<'ctx> LLVMPointerType<'ctx> {
+    pub fn get(ctx: &'ctx Context, address_space: u32) -> Self {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMPointerTypeGet(ctx, address_space) };
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    pub fn get_address_space(self) -> u32 {
+        unsafe { LLVM::FFIVal_::mlirLLVMPointerTypeGetAddressSpace(self) }
+    }
+}
+
+impl<'ctx> LLVMVoidType<'ctx> {
+    pub fn get(ctx: &'ctx Context) -> Self {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMVoidTypeGet(ctx) };
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+}
+
+impl<'ctx> LLVMArrayType<'ctx> {
+    pub fn get(elem_type: Type<'ctx>, num_elements: u32) -> Self {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMArrayTypeGet(elem_type, num_elements) };
+        let ty = Type::from_handle_same_context(handle, &elem_type);
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    pub fn get_element_type(self) -> Type<'ctx> {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMArrayTypeGetElementType(self) };
+        Type::from_handle_same_context(handle, &self)
+    }
+    pub fn get_num_elements(self) -> u32 {
+        unsafe { mlir_capi_extra::mlirLLVMArrayTypeGetNumElements(self.into()) }
+    }
+}
+
+impl<'ctx> LLVMFunctionType<'ctx> {
+    pub fn get(result_type: Type<'ctx>, inputs: &[Type<'ctx>], is_var_arg: bool) -> Self {
+        let handle = unsafe {
+            LLVM::FFIVal_::mlirLLVMFunctionTypeGet(
+                result_type,
+                inputs.len() as i64,
+                inputs.as_ptr() as *const _,
+                to_cbool(is_var_arg),
+            )
+        };
+        let ty = Type::from_handle_same_context(handle, &result_type);
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    pub fn get_num_inputs(self) -> usize {
+        (unsafe { LLVM::FFIVal_::<i64>::mlirLLVMFunctionTypeGetNumInputs(self) }) as _
+    }
+    pub fn get_input(self, pos: usize) -> Type<'ctx> {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMFunctionTypeGetInput(self, pos as i64) };
+        Type::from_handle_same_context(handle, &self)
+    }
+    pub fn get_return_type(self) -> Type<'ctx> {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMFunctionTypeGetReturnType(self) };
+        Type::from_handle_same_context(handle, &self)
+    }
+    pub fn is_var_arg(self) -> bool {
+        to_rbool(unsafe { LLVM::FFIVal_::mlirLLVMFunctionTypeIsVarArg(self) })
+    }
+}
+
+impl<'ctx> LLVMStructType<'ctx> {
+    /// Creates a literal struct, which is identified by its body.
+    pub fn literal_get(ctx: &'ctx Context, field_types: &[Type<'ctx>], is_packed: bool) -> Self {
+        let handle = unsafe {
+            LLVM::FFIVal_::mlirLLVMStructTypeLiteralGet(
+                ctx,
+                field_types.len() as i64,
+                field_types.as_ptr() as *const _,
+                to_cbool(is_packed),
+            )
+        };
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    pub fn literal_get_checked(
+        loc: Location<'ctx>,
+        field_types: &[Type<'ctx>],
+        is_packed: bool,
+    ) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new_for(&loc);
+        let handle = unsafe {
+            LLVM::FFIVal_::mlirLLVMStructTypeLiteralGetChecked(
+                loc,
+                field_types.len() as i64,
+                field_types.as_ptr() as *const _,
+                to_cbool(is_packed),
+            )
+        };
+        let ty = Type::from_handle_same_context(handle, &loc);
+        if ty.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(unsafe { IsA::<Self>::cast(ty) })
+        }
+    }
+    /// Gets the identified struct named `name`, which is created without body if it doesn't
+    /// exist yet.
+    pub fn identified_get(ctx: &'ctx Context, name: &str) -> Self {
+        let handle =
+            unsafe { LLVM::FFIVal_::mlirLLVMStructTypeIdentifiedGet(ctx, StrRef::from(name)) };
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    /// Creates a new identified struct with the given body, whose name is `name` with a suffix
+    /// appended if `name` is already in use.
+    pub fn identified_new_get(
+        ctx: &'ctx Context,
+        name: &str,
+        field_types: &[Type<'ctx>],
+        is_packed: bool,
+    ) -> Self {
+        let handle = unsafe {
+            LLVM::FFIVal_::mlirLLVMStructTypeIdentifiedNewGet(
+                ctx,
+                StrRef::from(name),
+                field_types.len() as i64,
+                field_types.as_ptr() as *const _,
+                to_cbool(is_packed),
+            )
+        };
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    pub fn opaque_get(ctx: &'ctx Context, name: &str) -> Self {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMStructTypeOpaqueGet(ctx, StrRef::from(name)) };
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        unsafe { IsA::<Self>::cast(ty) }
+    }
+    /// Sets the body of an identified struct. Fails if the struct already has a different body.
+    pub fn set_body(self, field_types: &[Type<'ctx>], is_packed: bool) -> LogicalResult {
+        unsafe {
+            LLVM::FFIVal_::mlirLLVMStructTypeSetBody(
+                self,
+                field_types.len() as i64,
+                field_types.as_ptr() as *const _,
+                to_cbool(is_packed),
+            )
+        }
+    }
+    pub fn is_literal(self) -> bool {
+        to_rbool(unsafe { LLVM::FFIVal_::mlirLLVMStructTypeIsLiteral(self) })
+    }
+    pub fn is_packed(self) -> bool {
+        to_rbool(unsafe { LLVM::FFIVal_::mlirLLVMStructTypeIsPacked(self) })
+    }
+    pub fn is_opaque(self) -> bool {
+        to_rbool(unsafe { LLVM::FFIVal_::mlirLLVMStructTypeIsOpaque(self) })
+    }
+    pub fn get_identifier(self) -> &'ctx str {
+        let str_ref: StrRef = unsafe { LLVM::FFIVal_::mlirLLVMStructTypeGetIdentifier(self) };
+        str_ref.to_str()
+    }
+    pub fn get_num_element_types(self) -> usize {
+        (unsafe { LLVM::FFIVal_::<i64>::mlirLLVMStructTypeGetNumElementTypes(self) }) as _
+    }
+    pub fn get_element_type(self, pos: usize) -> Type<'ctx> {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMStructTypeGetElementType(self, pos as i64) };
+        Type::from_handle_same_context(handle, &self)
+    }
+}
+
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Linkage {
+    Private = 0,
+    Internal = 1,
+    AvailableExternally = 2,
+    Linkonce = 3,
+    Weak = 4,
+    Common = 5,
+    Appending = 6,
+    ExternWeak = 7,
+    LinkonceODR = 8,
+    WeakODR = 9,
+    External = 10,
+}
+
+impl Linkage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Linkage::Private => "private",
+            Linkage::Internal => "internal",
+            Linkage::AvailableExternally => "available_externally",
+            Linkage::Linkonce => "linkonce",
+            Linkage::Weak => "weak",
+            Linkage::Common => "common",
+            Linkage::Appending => "appending",
+            Linkage::ExternWeak => "extern_weak",
+            Linkage::LinkonceODR => "linkonce_odr",
+            Linkage::WeakODR => "weak_odr",
+            Linkage::External => "external",
+        }
+    }
+    /// Returns the `#llvm.linkage` attribute, which requires the LLVM dialect to be loaded in
+    /// `ctx`.
+    pub fn get_attr<'ctx>(self, ctx: &'ctx Context) -> Attr<'ctx> {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMLinkageAttrGet(ctx, self as u32) };
+        unsafe { Attr::from_handle_and_phantom(handle, PhantomData::default()) }
+    }
+    pub fn from_u32(value: u32) -> Option<Self> {
+        <Self as strum::IntoEnumIterator>::iter().find(|linkage| *linkage as u32 == value)
+    }
+    /// Returns the linkage held by `attr`, or `None` if `attr` isn't a `#llvm.linkage`.
+    pub fn from_attr(attr: Attr) -> Option<Self> {
+        if attr.is_null() {
+            return None;
+        }
+        unsafe {
+            if !to_rbool(mlir_capi_extra::mlirAttributeIsALLVMLinkageAttr(
+                attr.into(),
+            )) {
+                return None;
+            }
+            Self::from_u32(mlir_capi_extra::mlirLLVMLinkageAttrGetLinkage(attr.into()))
+        }
+    }
+}
+
+impl FromStr for Linkage {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as strum::IntoEnumIterator>::iter()
+            .find(|linkage| linkage.as_str() == s)
+            .ok_or_else(|| format!("invalid linkage '{}'", s))
+    }
+}
+
+/// LLVM calling conventions, numbered as in LLVM.
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CConv {
+    C = 0,
+    Fast = 8,
+    Cold = 9,
+    GHC = 10,
+    HiPE = 11,
+    AnyReg = 13,
+    PreserveMost = 14,
+    PreserveAll = 15,
+    Swift = 16,
+    CxxFastTls = 17,
+    Tail = 18,
+    CFGuardCheck = 19,
+    SwiftTail = 20,
+    X86StdCall = 64,
+    X86FastCall = 65,
+    ArmApcs = 66,
+    ArmAapcs = 67,
+    ArmAapcsVfp = 68,
+    X86ThisCall = 70,
+    PtxKernel = 71,
+    PtxDevice = 72,
+    SpirFunc = 75,
+    SpirKernel = 76,
+    IntelOclBi = 77,
+    X86_64SysV = 78,
+    Win64 = 79,
+    X86VectorCall = 80,
+    AmdgpuKernel = 91,
+}
+
+impl CConv {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CConv::C => "ccc",
+            CConv::Fast => "fastcc",
+            CConv::Cold => "coldcc",
+            CConv::GHC => "cc_10",
+            CConv::HiPE => "cc_11",
+            CConv::AnyReg => "anyregcc",
+            CConv::PreserveMost => "preserve_mostcc",
+            CConv::PreserveAll => "preserve_allcc",
+            CConv::Swift => "swiftcc",
+            CConv::CxxFastTls => "cxx_fast_tlscc",
+            CConv::Tail => "tailcc",
+            CConv::CFGuardCheck => "cfguard_checkcc",
+            CConv::SwiftTail => "swifttailcc",
+            CConv::X86StdCall => "x86_stdcallcc",
+            CConv::X86FastCall => "x86_fastcallcc",
+            CConv::ArmApcs => "arm_apcscc",
+            CConv::ArmAapcs => "arm_aapcscc",
+            CConv::ArmAapcsVfp => "arm_aapcs_vfpcc",
+            CConv::X86ThisCall => "x86_thiscallcc",
+            CConv::PtxKernel => "ptx_kernelcc",
+            CConv::PtxDevice => "ptx_devicecc",
+            CConv::SpirFunc => "spir_funccc",
+            CConv::SpirKernel => "spir_kernelcc",
+            CConv::IntelOclBi => "intel_ocl_bicc",
+            CConv::X86_64SysV => "x86_64_sysvcc",
+            CConv::Win64 => "win64cc",
+            CConv::X86VectorCall => "x86_vectorcallcc",
+            CConv::AmdgpuKernel => "amdgpu_kernelcc",
+        }
+    }
+    /// Returns the `#llvm.cconv` attribute, which requires the LLVM dialect to be loaded in
+    /// `ctx`.
+    pub fn get_attr<'ctx>(self, ctx: &'ctx Context) -> Attr<'ctx> {
+        let handle = unsafe { LLVM::FFIVal_::mlirLLVMCConvAttrGet(ctx, self as u32) };
+        unsafe { Attr::from_handle_and_phantom(handle, PhantomData::default()) }
+    }
+    pub fn from_u32(value: u32) -> Option<Self> {
+        <Self as strum::IntoEnumIterator>::iter().find(|cconv| *cconv as u32 == value)
+    }
+    /// Returns the calling convention held by `attr`, or `None` if `attr` isn't a `#llvm.cconv`.
+    pub fn from_attr(attr: Attr) -> Option<Self> {
+        if attr.is_null() {
+            return None;
+        }
+        unsafe {
+            if !to_rbool(mlir_capi_extra::mlirAttributeIsALLVMCConvAttr(attr.into())) {
+                return None;
+            }
+            Self::from_u32(mlir_capi_extra::mlirLLVMCConvAttrGetCallingConv(
+                attr.into(),
+            ))
+        }
+    }
+}
+
+impl FromStr for CConv {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as strum::IntoEnumIterator>::iter()
+            .find(|cconv| cconv.as_str() == s)
+            .ok_or_else(|| format!("invalid calling convention '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod llvm_test {
+    use super::*;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::*;
+    use strum::IntoEnumIterator;
+
+    const DIALECTS: &[UpstreamDialectName] = &[UpstreamDialectName::LLVM];
+
+    #[test]
+    fn pointer_array_function_void() {
+        let ctx = create_context_with(DIALECTS);
+        let i32_ty: Type = IntegerType::get(&ctx, 32).into();
+        let ptr_ty = LLVMPointerType::get(&ctx, 1);
+        assert_eq!(ptr_ty.get_address_space(), 1);
+        assert!(ptr_ty == Type::parse(&ctx, "!llvm.ptr<1>").unwrap());
+        let parsed_ptr: LLVMPointerType = Type::parse(&ctx, "!llvm.ptr").unwrap().dyn_cast();
+        assert!(!parsed_ptr.is_null());
+        assert_eq!(parsed_ptr.get_address_space(), 0);
+
+        let array_ty = LLVMArrayType::get(i32_ty, 4);
+        assert!(array_ty.get_element_type() == i32_ty);
+        assert_eq!(array_ty.get_num_elements(), 4);
+        assert_eq!(array_ty.to_string(), "!llvm.array<4 x i32>");
+
+        let void_ty = LLVMVoidType::get(&ctx);
+        assert!(IsA::<LLVMVoidType>::is_a_non_null(Into::<Type>::into(
+            void_ty
+        )));
+        let func_ty = LLVMFunctionType::get(void_ty.into(), &[ptr_ty.into(), i32_ty], true);
+        assert_eq!(func_ty.get_num_inputs(), 2);
+        assert!(func_ty.get_input(1) == i32_ty);
+        assert!(func_ty.get_return_type() == void_ty);
+        assert!(func_ty.is_var_arg());
+        assert!(!LLVMFunctionType::get(i32_ty, &[i32_ty], false).is_var_arg());
+
+        let i32_as_ptr: LLVMPointerType = i32_ty.dyn_cast();
+        assert!(i32_as_ptr.is_null());
+        let array_as_func: LLVMFunctionType = Into::<Type>::into(array_ty).dyn_cast();
+        assert!(array_as_func.is_null());
+        let ptr_as_void: LLVMVoidType = Into::<Type>::into(ptr_ty).dyn_cast();
+        assert!(ptr_as_void.is_null());
+    }
+
+    #[test]
+    fn structs() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let i8_ty: Type = IntegerType::get(&ctx, 8).into();
+        let i64_ty: Type = IntegerType::get(&ctx, 64).into();
+        let literal = LLVMStructType::literal_get(&ctx, &[i8_ty, i64_ty], true);
+        assert!(literal.is_literal());
+        assert!(literal.is_packed());
+        assert_eq!(literal.get_num_element_types(), 2);
+        assert!(literal.get_element_type(1) == i64_ty);
+        assert!(LLVMStructType::literal_get_checked(loc, &[i8_ty], false).is_ok());
+        let void_ty: Type = LLVMVoidType::get(&ctx).into();
+        assert!(LLVMStructType::literal_get_checked(loc, &[i8_ty, void_ty], false).is_err());
+
+        let node = LLVMStructType::identified_get(&ctx, "node");
+        assert!(!node.is_literal());
+        assert!(node.is_opaque());
+        assert_eq!(node.get_identifier(), "node");
+        let ptr_ty: Type = LLVMPointerType::get(&ctx, 0).into();
+        assert!(node.set_body(&[i64_ty, ptr_ty], false).is_success());
+        assert!(!node.is_opaque());
+        assert!(node.set_body(&[i64_ty], false).is_failure());
+        assert!(node == LLVMStructType::identified_get(&ctx, "node"));
+        let renamed = LLVMStructType::identified_new_get(&ctx, "node", &[i8_ty], false);
+        assert!(renamed.get_identifier() != "node");
+        let opaque = LLVMStructType::opaque_get(&ctx, "opaque");
+        assert!(opaque.is_opaque());
+    }
+
+    #[test]
+    fn linkage_cconv() {
+        let ctx = create_context_with(DIALECTS);
+        for linkage in Linkage::iter() {
+            let attr = linkage.get_attr(&ctx);
+            assert_eq!(Linkage::from_attr(attr), Some(linkage));
+        }
+        for cconv in CConv::iter() {
+            let attr = cconv.get_attr(&ctx);
+            assert_eq!(CConv::from_attr(attr), Some(cconv));
+        }
+        assert_eq!(
+            Linkage::Internal.get_attr(&ctx).to_string(),
+            "#llvm.linkage<internal>"
+        );
+        let cconv_attr = CConv::Fast.get_attr(&ctx);
+        assert_eq!(Linkage::from_attr(cconv_attr), None);
+        let linkage_attr = Linkage::Weak.get_attr(&ctx);
+        assert_eq!(CConv::from_attr(linkage_attr), None);
+        assert_eq!(
+            Linkage::from_u32(Linkage::WeakODR as u32),
+            Some(Linkage::WeakODR)
+        );
+        assert_eq!(CConv::from_u32(12), None);
+        assert_eq!("fastcc".parse::<CConv>(), Ok(CConv::Fast));
+        assert!("fast".parse::<CConv>().is_err());
+    }
+}
@@ -0,0 +1,540 @@
+use super::{
+    check_dynamic_sizes, check_indices, check_num_values, check_static_sizes, get_mixed_values,
+    get_offset_size_stride_attrs, get_operand_segment_sizes,
+};
+use crate::attribute::*;
+use crate::builder::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+fn get_memref_type<'ctx>(value: Value<'ctx>) -> Result<MemRefType<'ctx>, String> {
+    let ty: MemRefType = value.get_type().dyn_cast();
+    if ty.is_null() {
+        Err(format!(
+            "expected a ranked memref, got {}",
+            value.get_type()
+        ))
+    } else {
+        Ok(ty)
+    }
+}
+
+/// Returns the element type and memory space of a ranked or unranked memref.
+fn get_element_type_and_memory_space<'ctx>(
+    value: Value<'ctx>,
+) -> Result<(Type<'ctx>, Attr<'ctx>), String> {
+    let ty = value.get_type();
+    let ranked: MemRefType = ty.dyn_cast();
+    if !ranked.is_null() {
+        return Ok((ranked.get_element_type(), ranked.get_memory_space()));
+    }
+    let unranked: UnrankedMemRefType = ty.dyn_cast();
+    if !unranked.is_null() {
+        return Ok((unranked.get_element_type(), unranked.get_memory_space()));
+    }
+    Err(format!("expected a memref, got {}", ty))
+}
+
+fn wrap_stride_or_offset(value: i64) -> Option<i64> {
+    if MemRefType::is_dynamic_stride_or_offset(value) {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn unwrap_stride_or_offset(value: Option<i64>) -> i64 {
+    value.unwrap_or_else(MemRefType::get_dynamic_stride_or_offset)
+}
+
+fn unwrap_size(value: Option<i64>) -> i64 {
+    value.unwrap_or_else(MemRefType::get_dynamic_size)
+}
+
+fn create_alloc<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    name: &'static str,
+    loc: Location<'ctx>,
+    ty: MemRefType<'ctx>,
+    dynamic_sizes: &[Value<'ctx>],
+) -> Result<OperationRef<'ctx>, String> {
+    check_dynamic_sizes(ty, dynamic_sizes)?;
+    let segments = get_operand_segment_sizes(builder, &[dynamic_sizes.len(), 0]);
+    Ok(builder.create_operation(name, loc, dynamic_sizes, &[ty.into()], &[segments]))
+}
+
+define_op_view!(AllocOp, "memref.alloc");
+
+impl<'ctx> AllocOp<'ctx> {
+    /// Creates a `memref.alloc` of type `ty`, with one operand in `dynamic_sizes` for each dynamic
+    /// dimension of `ty`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        ty: MemRefType<'ctx>,
+        dynamic_sizes: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let op = create_alloc(builder, "memref.alloc", loc, ty, dynamic_sizes)?;
+        Ok(Self { op })
+    }
+    pub fn get_dynamic_sizes(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(AllocaOp, "memref.alloca");
+
+impl<'ctx> AllocaOp<'ctx> {
+    /// Creates a `memref.alloca` of type `ty`, with one operand in `dynamic_sizes` for each dynamic
+    /// dimension of `ty`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        ty: MemRefType<'ctx>,
+        dynamic_sizes: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let op = create_alloc(builder, "memref.alloca", loc, ty, dynamic_sizes)?;
+        Ok(Self { op })
+    }
+    pub fn get_dynamic_sizes(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(DeallocOp, "memref.dealloc");
+
+impl<'ctx> DeallocOp<'ctx> {
+    /// Creates a `memref.dealloc` of `memref`, which may be ranked or unranked.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        memref: Value<'ctx>,
+    ) -> Result<Self, String> {
+        get_element_type_and_memory_space(memref)?;
+        let op = builder.create_operation("memref.dealloc", loc, &[memref], &[], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_memref(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+}
+
+define_op_view!(LoadOp, "memref.load");
+
+impl<'ctx> LoadOp<'ctx> {
+    /// Creates a `memref.load` of the element at `indices`, which must provide one index for each
+    /// dimension of `memref`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        memref: Value<'ctx>,
+        indices: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let ty = get_memref_type(memref)?;
+        check_indices(ty, indices)?;
+        let mut operands = vec![memref];
+        operands.extend_from_slice(indices);
+        let op =
+            builder.create_operation("memref.load", loc, &operands, &[ty.get_element_type()], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_memref(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_indices(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(1).collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(StoreOp, "memref.store");
+
+impl<'ctx> StoreOp<'ctx> {
+    /// Creates a `memref.store` of `value` to the element at `indices`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        value: Value<'ctx>,
+        memref: Value<'ctx>,
+        indices: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let ty = get_memref_type(memref)?;
+        check_indices(ty, indices)?;
+        if value.get_type() != ty.get_element_type() {
+            return Err(format!(
+                "expected a value of type {}, got {}",
+                ty.get_element_type(),
+                value.get_type()
+            ));
+        }
+        let mut operands = vec![value, memref];
+        operands.extend_from_slice(indices);
+        let op = builder.create_operation("memref.store", loc, &operands, &[], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_value(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_memref(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_indices(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(2).collect()
+    }
+}
+
+define_op_view!(SubViewOp, "memref.subview");
+
+impl<'ctx> SubViewOp<'ctx> {
+    /// Creates a `memref.subview` of `source`. The result has a strided layout computed from the
+    /// layout of `source`, with dynamic strides and offset wherever they cannot be folded.
+    /// Rank-reducing subviews are not supported.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        offsets: &[OpFoldResult<'ctx>],
+        sizes: &[OpFoldResult<'ctx>],
+        strides: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let source_ty = get_memref_type(source)?;
+        let rank = source_ty.get_rank() as usize;
+        check_num_values("offsets", offsets, rank)?;
+        check_num_values("sizes", sizes, rank)?;
+        check_num_values("strides", strides, rank)?;
+        check_static_sizes(sizes)?;
+        let (source_strides, source_offset) = source_ty
+            .get_strides_and_offset()
+            .ok_or_else(|| format!("expected a strided memref, got {}", source_ty))?;
+
+        let mut result_offset = wrap_stride_or_offset(source_offset);
+        let mut result_strides = Vec::with_capacity(rank);
+        for ((offset, stride), source_stride) in offsets.iter().zip(strides).zip(source_strides) {
+            let source_stride = wrap_stride_or_offset(source_stride);
+            let offset = offset
+                .get_static()
+                .zip(source_stride)
+                .and_then(|(offset, source_stride)| offset.checked_mul(source_stride));
+            result_offset = result_offset
+                .zip(offset)
+                .and_then(|(lhs, rhs)| lhs.checked_add(rhs));
+            let stride = stride
+                .get_static()
+                .zip(source_stride)
+                .and_then(|(stride, source_stride)| stride.checked_mul(source_stride));
+            result_strides.push(unwrap_stride_or_offset(stride));
+        }
+        let result_sizes: Vec<i64> = sizes
+            .iter()
+            .map(|size| unwrap_size(size.get_static()))
+            .collect();
+        let layout = StridedLayoutAttr::get(
+            builder.get_context(),
+            unwrap_stride_or_offset(result_offset),
+            &result_strides,
+        );
+        let result_ty = MemRefType::get(
+            source_ty.get_element_type(),
+            &result_sizes,
+            layout.into(),
+            source_ty.get_memory_space(),
+        );
+
+        let (dynamic_values, attrs) =
+            get_offset_size_stride_attrs(builder, &[1], offsets, sizes, strides);
+        let mut operands = vec![source];
+        operands.extend(dynamic_values);
+        let op = builder.create_operation(
+            "memref.subview",
+            loc,
+            &operands,
+            &[result_ty.into()],
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+    /// Returns the offsets, sizes and strides, in that order.
+    pub fn get_mixed_offsets_sizes_strides(
+        &self,
+    ) -> (
+        Vec<OpFoldResult<'ctx>>,
+        Vec<OpFoldResult<'ctx>>,
+        Vec<OpFoldResult<'ctx>>,
+    ) {
+        let mut dynamic_values = self.op.operands().skip(1);
+        let dynamic_stride_or_offset = MemRefType::get_dynamic_stride_or_offset();
+        let offsets = get_mixed_values(
+            self.op,
+            "static_offsets",
+            &mut dynamic_values,
+            dynamic_stride_or_offset,
+        );
+        let sizes = get_mixed_values(
+            self.op,
+            "static_sizes",
+            &mut dynamic_values,
+            MemRefType::get_dynamic_size(),
+        );
+        let strides = get_mixed_values(
+            self.op,
+            "static_strides",
+            &mut dynamic_values,
+            dynamic_stride_or_offset,
+        );
+        (offsets, sizes, strides)
+    }
+}
+
+define_op_view!(CastOp, "memref.cast");
+
+impl<'ctx> CastOp<'ctx> {
+    /// Creates a `memref.cast` of `source` to `result_ty`. When both types are ranked they must
+    /// have the same element type and rank, and their static dimensions must agree.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        result_ty: Type<'ctx>,
+    ) -> Result<Self, String> {
+        let (source_elem_ty, _) = get_element_type_and_memory_space(source)?;
+        let ranked_result_ty: MemRefType = result_ty.dyn_cast();
+        let unranked_result_ty: UnrankedMemRefType = result_ty.dyn_cast();
+        let result_elem_ty = if !ranked_result_ty.is_null() {
+            ranked_result_ty.get_element_type()
+        } else if !unranked_result_ty.is_null() {
+            unranked_result_ty.get_element_type()
+        } else {
+            return Err(format!("expected a memref result type, got {}", result_ty));
+        };
+        if source_elem_ty != result_elem_ty {
+            return Err(format!(
+                "cannot cast {} to {}: element types differ",
+                source.get_type(),
+                result_ty
+            ));
+        }
+        let ranked_source_ty: MemRefType = source.get_type().dyn_cast();
+        if !ranked_source_ty.is_null() && !ranked_result_ty.is_null() {
+            let rank = ranked_source_ty.get_rank();
+            let compatible = rank == ranked_result_ty.get_rank()
+                && (0..rank as usize).all(|dim| {
+                    ranked_source_ty.is_dynamic_dim(dim)
+                        || ranked_result_ty.is_dynamic_dim(dim)
+                        || ranked_source_ty.get_dim_size(dim) == ranked_result_ty.get_dim_size(dim)
+                });
+            if !compatible {
+                return Err(format!(
+                    "cannot cast {} to {}: shapes are incompatible",
+                    source.get_type(),
+                    result_ty
+                ));
+            }
+        }
+        let op = builder.create_operation("memref.cast", loc, &[source], &[result_ty], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(ReinterpretCastOp, "memref.reinterpret_cast");
+
+impl<'ctx> ReinterpretCastOp<'ctx> {
+    /// Creates a `memref.reinterpret_cast` of `source` into a memref with the given `offset`,
+    /// `sizes` and `strides`, in the memory space of `source`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        offset: OpFoldResult<'ctx>,
+        sizes: &[OpFoldResult<'ctx>],
+        strides: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let (elem_ty, memory_space) = get_element_type_and_memory_space(source)?;
+        check_num_values("strides", strides, sizes.len())?;
+        check_static_sizes(sizes)?;
+        let result_sizes: Vec<i64> = sizes
+            .iter()
+            .map(|size| unwrap_size(size.get_static()))
+            .collect();
+        let result_strides: Vec<i64> = strides
+            .iter()
+            .map(|stride| unwrap_stride_or_offset(stride.get_static()))
+            .collect();
+        let layout = StridedLayoutAttr::get(
+            builder.get_context(),
+            unwrap_stride_or_offset(offset.get_static()),
+            &result_strides,
+        );
+        let result_ty = MemRefType::get(elem_ty, &result_sizes, layout.into(), memory_space);
+
+        let (dynamic_values, attrs) =
+            get_offset_size_stride_attrs(builder, &[1], &[offset], sizes, strides);
+        let mut operands = vec![source];
+        operands.extend(dynamic_values);
+        let op = builder.create_operation(
+            "memref.reinterpret_cast",
+            loc,
+            &operands,
+            &[result_ty.into()],
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+#[cfg(test)]
+mod memref_test {
+    use super::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::MemRef,
+    ];
+
+    #[test]
+    fn alloc_load_store() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let func_ty = FunctionType::get(&ctx, &[index_ty], &[]);
+        let func = FuncOp::create(&builder, loc, "copy", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let n = func.get_argument(0).unwrap();
+        let dynamic = MemRefType::get_dynamic_size();
+        let ty = MemRefType::contiguous_get(f32_ty, &[4, dynamic], Attr::create_null());
+
+        assert!(AllocOp::create(&body, loc, ty, &[]).is_err());
+        assert!(AllocaOp::create(&body, loc, ty, &[n, n]).is_err());
+        let alloc_op = AllocOp::create(&body, loc, ty, &[n]).unwrap();
+        let src = alloc_op.get_result();
+        let dst = AllocaOp::create(&body, loc, ty, &[n]).unwrap().get_result();
+        let c0 = arith::ConstantOp::create_index(&body, loc, 0).get_result();
+        assert!(LoadOp::create(&body, loc, src, &[c0]).is_err());
+        let load_op = LoadOp::create(&body, loc, src, &[c0, c0]).unwrap();
+        let value = load_op.get_result();
+        assert!(value.get_type() == f32_ty);
+        assert!(StoreOp::create(&body, loc, c0, dst, &[c0, c0]).is_err());
+        let store_op = StoreOp::create(&body, loc, value, dst, &[c0, c0]).unwrap();
+        assert!(store_op.get_value() == value);
+        let dealloc_op = DeallocOp::create(&body, loc, src).unwrap();
+        assert!(dealloc_op.get_memref() == src);
+        assert!(DeallocOp::create(&body, loc, c0).is_err());
+        ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+
+        assert!(AllocOp::from_op(src.op_res_get_owner()).is_some());
+        assert!(alloc_op.get_dynamic_sizes() == vec![n]);
+        assert!(load_op.get_memref() == src);
+        assert_eq!(load_op.get_indices().len(), 2);
+    }
+
+    #[test]
+    fn subview_cast() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let source_ty = MemRefType::contiguous_get(f32_ty, &[8, 16], Attr::create_null());
+        let func_ty = FunctionType::get(&ctx, &[source_ty.into(), index_ty], &[]);
+        let func = FuncOp::create(&builder, loc, "view", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let source = func.get_argument(0).unwrap();
+        let i = func.get_argument(1).unwrap();
+
+        assert!(
+            SubViewOp::create(&body, loc, source, &[0.into()], &[4.into()], &[1.into()]).is_err()
+        );
+        let view = SubViewOp::create(
+            &body,
+            loc,
+            source,
+            &[2.into(), 4.into()],
+            &[4.into(), 8.into()],
+            &[1.into(), 2.into()],
+        )
+        .unwrap()
+        .get_result();
+        let view_ty: MemRefType = view.get_type().dyn_cast();
+        assert_eq!(view_ty.get_strides_and_offset(), Some((vec![16, 2], 36)));
+        let subview_op = SubViewOp::create(
+            &body,
+            loc,
+            source,
+            &[i.into(), 0.into()],
+            &[i.into(), 16.into()],
+            &[1.into(), 1.into()],
+        )
+        .unwrap();
+        let dynamic_view = subview_op.get_result();
+        assert!(subview_op.get_source() == source);
+        let dynamic_view_ty: MemRefType = dynamic_view.get_type().dyn_cast();
+        assert!(dynamic_view_ty.is_dynamic_dim(0));
+        let (strides, offset) = dynamic_view_ty.get_strides_and_offset().unwrap();
+        assert_eq!(strides, vec![16, 1]);
+        assert!(MemRefType::is_dynamic_stride_or_offset(offset));
+        let (offsets, sizes, _) = subview_op.get_mixed_offsets_sizes_strides();
+        assert!(offsets == vec![OpFoldResult::Dynamic(i), OpFoldResult::Static(0)]);
+        assert!(sizes == vec![OpFoldResult::Dynamic(i), OpFoldResult::Static(16)]);
+
+        let dynamic = MemRefType::get_dynamic_size();
+        let cast_ty = MemRefType::contiguous_get(f32_ty, &[dynamic, 16], Attr::create_null());
+        let cast_ty: Type = cast_ty.into();
+        let casted = CastOp::create(&body, loc, source, cast_ty)
+            .unwrap()
+            .get_result();
+        assert!(casted.get_type() == cast_ty);
+        let bad_ty = MemRefType::contiguous_get(f32_ty, &[4, 16], Attr::create_null());
+        assert!(CastOp::create(&body, loc, source, bad_ty.into()).is_err());
+        let flat =
+            ReinterpretCastOp::create(&body, loc, source, 0.into(), &[128.into()], &[1.into()])
+                .unwrap()
+                .get_result();
+        let flat_ty: MemRefType = flat.get_type().dyn_cast();
+        assert_eq!(flat_ty.get_dim_size(0), 128);
+        assert!(
+            ReinterpretCastOp::create(&body, loc, source, 0.into(), &[128.into()], &[]).is_err()
+        );
+        ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
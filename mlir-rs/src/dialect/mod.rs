@@ -1,7 +1,17 @@
+use crate::attribute::{DenseArrrayAttr, DenseI32ArrayAttr, DenseI64ArrayAttr, NamedAttr};
+use crate::block::{Block, BlockRef};
+use crate::builder::OpBuilder;
 use crate::common;
 use crate::common::*;
 use crate::context::*;
+use crate::location::Location;
+use crate::operation::OperationRef;
+use crate::operation_state::OperationState;
+use crate::r#type::{MemRefType, ShapedTypeTrait, Type};
+use crate::region::{Region, RegionRef};
 use crate::support::*;
+use crate::type_cast::IsA;
+use crate::value::{OpFoldResult, Value};
 use mlir_capi;
 use mlir_capi::Dialect_::*;
 use mlir_capi::IR::*;
@@ -9,6 +19,18 @@ use std::cmp::{Eq, PartialEq};
 use std::convert::{From, Into};
 use std::marker::PhantomData;
 
+pub mod affine;
+pub mod arith;
+pub mod cf;
+pub mod func;
+pub mod gpu;
+pub mod linalg;
+pub mod llvm;
+pub mod memref;
+pub mod scf;
+pub mod tensor;
+pub mod vector;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct Dialect<'ctx> {
@@ -104,6 +126,13 @@ impl DialectRegistry {
     pub fn create() -> Self {
         unsafe { mlir_capi::IR::FFIVal_::mlirDialectRegistryCreate() }
     }
+    /// Inserts all upstream dialects, including those without a dialect handle in the C API,
+    /// such as affine.
+    pub fn insert_all_upstream_dialects(&self) {
+        unsafe {
+            mlir_capi::RegisterEverything::FFIVoid_::mlirRegisterAllDialects(self);
+        }
+    }
     pub fn is_null(self) -> bool {
         common::is_null(self.handle.ptr)
     }
@@ -130,6 +159,7 @@ mod dialect_registry_test {
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone)]
 pub enum UpstreamDialectName {
+    Affine,
     AMDGPU,
     Arith,
     Async,
@@ -157,9 +187,12 @@ pub enum UpstreamDialectName {
     Vector,
 }
 
-pub fn get_handle_for_upstream_dialect(dialect: UpstreamDialectName) -> DialectHandle {
+/// Returns the dialect handle of `dialect`, or `None` for affine, which has no dialect handle in
+/// the C API. `UpstreamDialectName::load_dialect` loads any upstream dialect.
+pub fn get_handle_for_upstream_dialect(dialect: UpstreamDialectName) -> Option<DialectHandle> {
     let handle = unsafe {
         match dialect {
+            UpstreamDialectName::Affine => return None,
             UpstreamDialectName::AMDGPU => AMDGPU::mlirGetDialectHandle__amdgpu__(),
             UpstreamDialectName::Arith => Arith::mlirGetDialectHandle__arith__(),
             UpstreamDialectName::Async => Async::mlirGetDialectHandle__async__(),
@@ -189,7 +222,209 @@ pub fn get_handle_for_upstream_dialect(dialect: UpstreamDialectName) -> DialectH
             UpstreamDialectName::Vector => Vector::mlirGetDialectHandle__vector__(),
         }
     };
-    DialectHandle { handle }
+    Some(DialectHandle { handle })
+}
+
+impl UpstreamDialectName {
+    /// Loads the dialect in `ctx`, see `affine::load_dialect` for affine.
+    pub fn load_dialect(self, ctx: &Context) -> Dialect {
+        match get_handle_for_upstream_dialect(self) {
+            Some(handle) => handle.load_dialect(ctx),
+            None => affine::load_dialect(ctx),
+        }
+    }
+}
+
+/// Registers the LLVM IR translation interfaces of all upstream dialects with `ctx`, which is
+/// needed before a module can be translated to LLVM IR or JIT-compiled.
+pub fn register_all_llvm_translations(ctx: &Context) {
+    unsafe {
+        mlir_capi::RegisterEverything::FFIVoid_::mlirRegisterAllLLVMTranslations(ctx);
+    }
+}
+
+/// Appends a block with arguments of the given types to `region`.
+pub(crate) fn append_block<'ctx>(
+    region: RegionRef<'ctx>,
+    arg_types: &[Type<'ctx>],
+    loc: Location<'ctx>,
+) -> BlockRef<'ctx> {
+    let arg_locs = vec![loc; arg_types.len()];
+    let block = Block::create(arg_types, &arg_locs);
+    let block_ref = unsafe { BlockRef::wrap(block.handle, PhantomData::default()) };
+    region.append_owned_block(block);
+    block_ref
+}
+
+/// Creates an operation with `num_regions` empty regions at the insertion point.
+pub(crate) fn create_region_op<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    name: &'static str,
+    loc: Location<'ctx>,
+    operands: &[Value<'ctx>],
+    results: &[Type<'ctx>],
+    attributes: &[NamedAttr<'ctx>],
+    num_regions: usize,
+) -> OperationRef<'ctx> {
+    let mut state = OperationState::get(name, loc);
+    state
+        .add_operands(operands)
+        .add_results(results)
+        .add_attributes(attributes)
+        .add_owned_regions((0..num_regions).map(|_| Region::create()).collect());
+    builder.create(&state)
+}
+
+/// Creates the `operandSegmentSizes` attribute of ops with several variadic operand groups.
+pub(crate) fn get_operand_segment_sizes<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    sizes: &[usize],
+) -> NamedAttr<'ctx> {
+    let ctx = builder.get_context();
+    let sizes: Vec<i32> = sizes.iter().map(|size| *size as i32).collect();
+    NamedAttr::get(
+        ctx,
+        "operandSegmentSizes",
+        DenseI32ArrayAttr::get(ctx, &sizes).into(),
+    )
+}
+
+/// Reads the sizes of the operand groups of `op` from its `operandSegmentSizes` attribute, or
+/// from another dense i32 array attribute named `attr_name`.
+pub(crate) fn read_segment_sizes(op: OperationRef, attr_name: &str) -> Vec<usize> {
+    let segments: DenseI32ArrayAttr = op.get_attr_by_name(attr_name).dyn_cast();
+    (0..segments.get_num_elements())
+        .map(|pos| segments.get_element(pos) as usize)
+        .collect()
+}
+
+/// Returns the range of the operands of `op` in its `group`-th operand group.
+pub(crate) fn get_operand_segment(op: OperationRef, group: usize) -> std::ops::Range<usize> {
+    let segments = read_segment_sizes(op, "operandSegmentSizes");
+    let start: usize = segments[..group].iter().sum();
+    start..start + segments[group]
+}
+
+/// Checks that one dynamic size operand is provided for each dynamic dimension of `ty`.
+pub(crate) fn check_dynamic_sizes<'ctx, T: ShapedTypeTrait<'ctx>>(
+    ty: T,
+    dynamic_sizes: &[Value<'ctx>],
+) -> Result<(), String> {
+    let num_dynamic_dims = (0..ty.get_rank() as usize)
+        .filter(|dim| ty.is_dynamic_dim(*dim))
+        .count();
+    if num_dynamic_dims != dynamic_sizes.len() {
+        return Err(format!(
+            "expected {} dynamic size operands, got {}",
+            num_dynamic_dims,
+            dynamic_sizes.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that one index is provided for each dimension of `ty`.
+pub(crate) fn check_indices<'ctx, T: ShapedTypeTrait<'ctx>>(
+    ty: T,
+    indices: &[Value<'ctx>],
+) -> Result<(), String> {
+    if ty.get_rank() as usize != indices.len() {
+        return Err(format!(
+            "expected {} indices, got {}",
+            ty.get_rank(),
+            indices.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `values` has `rank` elements.
+pub(crate) fn check_num_values(
+    kind: &str,
+    values: &[OpFoldResult],
+    rank: usize,
+) -> Result<(), String> {
+    if values.len() != rank {
+        return Err(format!("expected {} {}, got {}", rank, kind, values.len()));
+    }
+    Ok(())
+}
+
+/// Checks that the static values in `sizes` are non-negative.
+pub(crate) fn check_static_sizes(sizes: &[OpFoldResult]) -> Result<(), String> {
+    match sizes
+        .iter()
+        .find_map(|size| size.get_static().filter(|size| *size < 0))
+    {
+        Some(size) => Err(format!("expected a non-negative size, got {}", size)),
+        None => Ok(()),
+    }
+}
+
+/// Rebuilds the mixed static/dynamic values stored in the dense array attribute `attr_name`,
+/// taking the dynamic ones from `dynamic_values` in order.
+pub(crate) fn get_mixed_values<'ctx>(
+    op: OperationRef<'ctx>,
+    attr_name: &str,
+    dynamic_values: &mut dyn Iterator<Item = Value<'ctx>>,
+    dynamic_marker: i64,
+) -> Vec<OpFoldResult<'ctx>> {
+    let attr: DenseI64ArrayAttr = op.get_attr_by_name(attr_name).dyn_cast();
+    (0..attr.get_num_elements())
+        .map(|pos| match attr.get_element(pos) {
+            value if value == dynamic_marker => {
+                OpFoldResult::Dynamic(dynamic_values.next().expect("missing dynamic value"))
+            }
+            value => OpFoldResult::Static(value),
+        })
+        .collect()
+}
+
+/// Returns the static offsets, sizes and strides attributes, and the `operandSegmentSizes`
+/// attribute, of ops taking `num_leading_operands` operands followed by the dynamic offsets,
+/// sizes and strides.
+pub(crate) fn get_offset_size_stride_attrs<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    num_leading_operands: &[usize],
+    offsets: &[OpFoldResult<'ctx>],
+    sizes: &[OpFoldResult<'ctx>],
+    strides: &[OpFoldResult<'ctx>],
+) -> (Vec<Value<'ctx>>, Vec<NamedAttr<'ctx>>) {
+    let ctx = builder.get_context();
+    let (dynamic_offsets, static_offsets) =
+        OpFoldResult::dispatch(offsets, MemRefType::get_dynamic_stride_or_offset());
+    let (dynamic_sizes, static_sizes) =
+        OpFoldResult::dispatch(sizes, MemRefType::get_dynamic_size());
+    let (dynamic_strides, static_strides) =
+        OpFoldResult::dispatch(strides, MemRefType::get_dynamic_stride_or_offset());
+    let mut segments = num_leading_operands.to_vec();
+    segments.extend([
+        dynamic_offsets.len(),
+        dynamic_sizes.len(),
+        dynamic_strides.len(),
+    ]);
+    let attrs = vec![
+        NamedAttr::get(
+            ctx,
+            "static_offsets",
+            DenseI64ArrayAttr::get(ctx, &static_offsets).into(),
+        ),
+        NamedAttr::get(
+            ctx,
+            "static_sizes",
+            DenseI64ArrayAttr::get(ctx, &static_sizes).into(),
+        ),
+        NamedAttr::get(
+            ctx,
+            "static_strides",
+            DenseI64ArrayAttr::get(ctx, &static_strides).into(),
+        ),
+        get_operand_segment_sizes(builder, &segments),
+    ];
+    let mut dynamic_values = dynamic_offsets;
+    dynamic_values.extend(dynamic_sizes);
+    dynamic_values.extend(dynamic_strides);
+    (dynamic_values, attrs)
 }
 
 #[cfg(test)]
@@ -197,6 +432,7 @@ pub mod dialect_test {
     use super::*;
     pub fn get_all_dialect_info() -> Vec<(UpstreamDialectName, &'static str)> {
         [
+            (UpstreamDialectName::Affine, "affine"),
             (UpstreamDialectName::AMDGPU, "amdgpu"),
             (UpstreamDialectName::Arith, "arith"),
             (UpstreamDialectName::Async, "async"),
@@ -223,14 +459,21 @@ pub mod dialect_test {
         ]
         .to_vec()
     }
+    /// Creates a context with `dialects` loaded.
+    pub fn create_context_with(dialects: &[UpstreamDialectName]) -> Context {
+        let ctx = Context::create();
+        for dialect in dialects {
+            dialect.load_dialect(&ctx);
+        }
+        ctx
+    }
     #[test]
     fn dialect_load() {
         let infos = get_all_dialect_info();
         let ctx = Context::create();
         let mut dialects = Vec::new();
         for info in infos {
-            let dialect_handle = get_handle_for_upstream_dialect(info.0);
-            dialect_handle.load_dialect(&ctx);
+            info.0.load_dialect(&ctx);
             let dialect = ctx.get_or_load_dialect(info.1);
             assert!(!dialect.is_null());
             assert_eq!(dialect.get_namespace(), info.1);
@@ -245,4 +488,19 @@ pub mod dialect_test {
             }
         }
     }
+
+    #[test]
+    fn append_block() {
+        let ctx = Context::create();
+        UpstreamDialectName::Func.load_dialect(&ctx);
+        let loc = Location::unknown_get(&ctx);
+        let module =
+            crate::module::Module::create_parse(&ctx, "func.func @f() {\n  return\n}").unwrap();
+        let region = module.get_body().get_first_operation().get_region(0);
+        let i32_ty = Type::parse(&ctx, "i32").unwrap();
+        let block = super::append_block(region, &[i32_ty], loc);
+        assert!(*block != *region.get_first_block());
+        assert!(*block == *region.get_first_block().get_next_in_region());
+        assert_eq!(block.get_num_arguments(), 1);
+    }
 }
@@ -0,0 +1,500 @@
+use super::{append_block, create_region_op, get_operand_segment_sizes};
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::value::*;
+
+fn get_types<'ctx>(values: &[Value<'ctx>]) -> Vec<Type<'ctx>> {
+    values.iter().map(|value| value.get_type()).collect()
+}
+
+define_op_view!(YieldOp, "scf.yield");
+
+impl<'ctx> YieldOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, values: &[Value<'ctx>]) -> Self {
+        Self {
+            op: builder.create_operation("scf.yield", loc, values, &[], &[]),
+        }
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+define_op_view!(ForOp, "scf.for");
+
+impl<'ctx> ForOp<'ctx> {
+    /// Creates an `scf.for` from `lower_bound` to `upper_bound`, whose body is populated by
+    /// `body`. The closure receives the induction variable and the loop-carried values, and
+    /// returns the values yielded to the next iteration.
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lower_bound: Value<'ctx>,
+        upper_bound: Value<'ctx>,
+        step: Value<'ctx>,
+        iter_args: &[Value<'ctx>],
+        body: F,
+    ) -> Self
+    where
+        F: FnOnce(&OpBuilder<'ctx>, Value<'ctx>, &[Value<'ctx>]) -> Vec<Value<'ctx>>,
+    {
+        let mut operands = vec![lower_bound, upper_bound, step];
+        operands.extend_from_slice(iter_args);
+        let result_types = get_types(iter_args);
+        let op = create_region_op(builder, "scf.for", loc, &operands, &result_types, &[], 1);
+
+        let mut arg_types = vec![lower_bound.get_type()];
+        arg_types.extend_from_slice(&result_types);
+        let block = append_block(op.get_region(0), &arg_types, loc);
+        let body_builder = OpBuilder::at_block_end(builder.get_context(), block);
+        let args: Vec<Value> = block.arguments().collect();
+        let yielded = body(&body_builder, args[0], &args[1..]);
+        YieldOp::create(&body_builder, loc, &yielded);
+        Self { op }
+    }
+    pub fn get_lower_bound(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_upper_bound(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_step(&self) -> Value<'ctx> {
+        self.op.get_operand(2)
+    }
+    pub fn get_init_args(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(3).collect()
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    pub fn get_induction_var(&self) -> Value<'ctx> {
+        self.get_body().get_argument(0)
+    }
+    pub fn get_region_iter_args(&self) -> Vec<Value<'ctx>> {
+        self.get_body().arguments().skip(1).collect()
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+define_op_view!(IfOp, "scf.if");
+
+impl<'ctx> IfOp<'ctx> {
+    /// Creates an `scf.if` without else branch, which cannot have results.
+    pub fn create<T>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: Value<'ctx>,
+        then_body: T,
+    ) -> Self
+    where
+        T: FnOnce(&OpBuilder<'ctx>),
+    {
+        let op = create_region_op(builder, "scf.if", loc, &[condition], &[], &[], 2);
+        let then_block = append_block(op.get_region(0), &[], loc);
+        let then_builder = OpBuilder::at_block_end(builder.get_context(), then_block);
+        then_body(&then_builder);
+        YieldOp::create(&then_builder, loc, &[]);
+        Self { op }
+    }
+    /// Creates an `scf.if` with both branches, each closure returns the values the branch
+    /// yields as the results of the `scf.if`.
+    pub fn create_with_else<T, E>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: Value<'ctx>,
+        result_types: &[Type<'ctx>],
+        then_body: T,
+        else_body: E,
+    ) -> Self
+    where
+        T: FnOnce(&OpBuilder<'ctx>) -> Vec<Value<'ctx>>,
+        E: FnOnce(&OpBuilder<'ctx>) -> Vec<Value<'ctx>>,
+    {
+        let op = create_region_op(builder, "scf.if", loc, &[condition], result_types, &[], 2);
+        let then_block = append_block(op.get_region(0), &[], loc);
+        let then_builder = OpBuilder::at_block_end(builder.get_context(), then_block);
+        let yielded = then_body(&then_builder);
+        YieldOp::create(&then_builder, loc, &yielded);
+        let else_block = append_block(op.get_region(1), &[], loc);
+        let else_builder = OpBuilder::at_block_end(builder.get_context(), else_block);
+        let yielded = else_body(&else_builder);
+        YieldOp::create(&else_builder, loc, &yielded);
+        Self { op }
+    }
+    pub fn get_condition(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_then_block(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    /// Returns the else block, or `None` if there is no else branch.
+    pub fn get_else_block(&self) -> Option<BlockRef<'ctx>> {
+        let block = self.op.get_region(1).get_first_block();
+        if block.is_null() {
+            None
+        } else {
+            Some(block)
+        }
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+define_op_view!(ConditionOp, "scf.condition");
+
+impl<'ctx> ConditionOp<'ctx> {
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        condition: Value<'ctx>,
+        args: &[Value<'ctx>],
+    ) -> Self {
+        let mut operands = vec![condition];
+        operands.extend_from_slice(args);
+        Self {
+            op: builder.create_operation("scf.condition", loc, &operands, &[], &[]),
+        }
+    }
+    pub fn get_condition(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_args(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(1).collect()
+    }
+}
+
+define_op_view!(WhileOp, "scf.while");
+
+impl<'ctx> WhileOp<'ctx> {
+    /// Creates an `scf.while`. `before` receives the loop-carried values and returns the
+    /// condition together with the values forwarded to `after` (or returned as the results of
+    /// the loop, which have `result_types`). `after` returns the next loop-carried values.
+    pub fn create<B, A>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        inits: &[Value<'ctx>],
+        result_types: &[Type<'ctx>],
+        before: B,
+        after: A,
+    ) -> Self
+    where
+        B: FnOnce(&OpBuilder<'ctx>, &[Value<'ctx>]) -> (Value<'ctx>, Vec<Value<'ctx>>),
+        A: FnOnce(&OpBuilder<'ctx>, &[Value<'ctx>]) -> Vec<Value<'ctx>>,
+    {
+        let op = create_region_op(builder, "scf.while", loc, inits, result_types, &[], 2);
+        let before_block = append_block(op.get_region(0), &get_types(inits), loc);
+        let before_builder = OpBuilder::at_block_end(builder.get_context(), before_block);
+        let args: Vec<Value> = before_block.arguments().collect();
+        let (condition, forwarded) = before(&before_builder, &args);
+        ConditionOp::create(&before_builder, loc, condition, &forwarded);
+
+        let after_block = append_block(op.get_region(1), result_types, loc);
+        let after_builder = OpBuilder::at_block_end(builder.get_context(), after_block);
+        let args: Vec<Value> = after_block.arguments().collect();
+        let yielded = after(&after_builder, &args);
+        YieldOp::create(&after_builder, loc, &yielded);
+        Self { op }
+    }
+    pub fn get_inits(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+    pub fn get_before_block(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    pub fn get_after_block(&self) -> BlockRef<'ctx> {
+        self.op.get_region(1).get_first_block()
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+/// Checks that multi-dimensional loop bounds have the same rank, and returns it.
+fn check_bounds(
+    lower_bounds: &[Value],
+    upper_bounds: &[Value],
+    steps: &[Value],
+) -> Result<usize, String> {
+    let rank = lower_bounds.len();
+    if upper_bounds.len() != rank || steps.len() != rank {
+        return Err(format!(
+            "expected as many upper bounds and steps as lower bounds, got {}, {} and {}",
+            rank,
+            upper_bounds.len(),
+            steps.len()
+        ));
+    }
+    Ok(rank)
+}
+
+define_op_view!(ParallelOp, "scf.parallel");
+
+impl<'ctx> ParallelOp<'ctx> {
+    /// Creates an `scf.parallel` without reductions, `body` receives the induction variables.
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lower_bounds: &[Value<'ctx>],
+        upper_bounds: &[Value<'ctx>],
+        steps: &[Value<'ctx>],
+        body: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&OpBuilder<'ctx>, &[Value<'ctx>]),
+    {
+        let rank = check_bounds(lower_bounds, upper_bounds, steps)?;
+        let operands = [lower_bounds, upper_bounds, steps].concat();
+        let segments = get_operand_segment_sizes(builder, &[rank, rank, rank, 0]);
+        let op = create_region_op(builder, "scf.parallel", loc, &operands, &[], &[segments], 1);
+
+        let index_ty: Type = IndexType::get(builder.get_context()).into();
+        let block = append_block(op.get_region(0), &vec![index_ty; rank], loc);
+        let body_builder = OpBuilder::at_block_end(builder.get_context(), block);
+        let ivs: Vec<Value> = block.arguments().collect();
+        body(&body_builder, &ivs);
+        body_builder.create_operation("scf.reduce", loc, &[], &[], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    pub fn get_induction_vars(&self) -> Vec<Value<'ctx>> {
+        self.get_body().arguments().collect()
+    }
+}
+
+define_op_view!(ForallOp, "scf.forall");
+
+impl<'ctx> ForallOp<'ctx> {
+    /// Creates an `scf.forall` with dynamic bounds. `body` receives the induction variables
+    /// and the shared outputs, and the `scf.forall.in_parallel` terminator is created with an
+    /// empty body, see `get_in_parallel_block`.
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lower_bounds: &[Value<'ctx>],
+        upper_bounds: &[Value<'ctx>],
+        steps: &[Value<'ctx>],
+        outputs: &[Value<'ctx>],
+        body: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&OpBuilder<'ctx>, &[Value<'ctx>], &[Value<'ctx>]),
+    {
+        let rank = check_bounds(lower_bounds, upper_bounds, steps)?;
+        let ctx = builder.get_context();
+        let dynamic = vec![RankedTensorType::get_dynamic_size(); rank];
+        let static_attr =
+            |name| NamedAttr::get(ctx, name, DenseI64ArrayAttr::get(ctx, &dynamic).into());
+        let attrs = [
+            static_attr("staticLowerBound"),
+            static_attr("staticUpperBound"),
+            static_attr("staticStep"),
+            get_operand_segment_sizes(builder, &[rank, rank, rank, outputs.len()]),
+        ];
+        let operands = [lower_bounds, upper_bounds, steps, outputs].concat();
+        let op = create_region_op(
+            builder,
+            "scf.forall",
+            loc,
+            &operands,
+            &get_types(outputs),
+            &attrs,
+            1,
+        );
+
+        let index_ty: Type = IndexType::get(ctx).into();
+        let mut arg_types = vec![index_ty; rank];
+        arg_types.extend(get_types(outputs));
+        let block = append_block(op.get_region(0), &arg_types, loc);
+        let body_builder = OpBuilder::at_block_end(ctx, block);
+        let args: Vec<Value> = block.arguments().collect();
+        body(&body_builder, &args[..rank], &args[rank..]);
+        let in_parallel = create_region_op(
+            &body_builder,
+            "scf.forall.in_parallel",
+            loc,
+            &[],
+            &[],
+            &[],
+            1,
+        );
+        append_block(in_parallel.get_region(0), &[], loc);
+        Ok(Self { op })
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    pub fn get_rank(&self) -> usize {
+        self.get_body().get_num_arguments() - self.op.get_num_results()
+    }
+    pub fn get_induction_vars(&self) -> Vec<Value<'ctx>> {
+        self.get_body().arguments().take(self.get_rank()).collect()
+    }
+    pub fn get_region_out_args(&self) -> Vec<Value<'ctx>> {
+        self.get_body().arguments().skip(self.get_rank()).collect()
+    }
+    /// The block of the `scf.forall.in_parallel` terminator, where the results are inserted
+    /// into the shared outputs.
+    pub fn get_in_parallel_block(&self) -> BlockRef<'ctx> {
+        self.get_body()
+            .get_terminator()
+            .get_region(0)
+            .get_first_block()
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+#[cfg(test)]
+mod scf_test {
+    use super::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+    use crate::type_cast::*;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::SCF,
+        UpstreamDialectName::Tensor,
+    ];
+
+    #[test]
+    fn for_if() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let i32_ty: Type = IntegerType::get(&ctx, 32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let func_ty = FunctionType::get(&ctx, &[index_ty], &[i32_ty]);
+        let func = FuncOp::create(&builder, loc, "sum", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let c0 = arith::ConstantOp::create_index(&body, loc, 0).get_result();
+        let c1 = arith::ConstantOp::create_index(&body, loc, 1).get_result();
+        let zero = arith::ConstantOp::create_int(&body, loc, 0, i32_ty).get_result();
+        let for_op = ForOp::create(
+            &body,
+            loc,
+            c0,
+            func.get_argument(0).unwrap(),
+            c1,
+            &[zero],
+            |b, iv, args| {
+                let iv = arith::IndexCastOp::create(b, loc, iv, i32_ty).get_out();
+                let is_zero = arith::CmpIOp::create(b, loc, arith::CmpIPredicate::Eq, iv, args[0])
+                    .get_result();
+                IfOp::create(b, loc, is_zero, |_| {});
+                let next = IfOp::create_with_else(
+                    b,
+                    loc,
+                    is_zero,
+                    &[i32_ty],
+                    |_| vec![args[0]],
+                    |b| vec![arith::AddIOp::create(b, loc, args[0], iv).get_result()],
+                );
+                next.get_results()
+            },
+        );
+        ReturnOp::create(&body, loc, &for_op.get_results());
+        assert!(module.get_operation_ref().verify().is_ok());
+
+        assert!(for_op.get_upper_bound() == func.get_argument(0).unwrap());
+        assert!(for_op.get_init_args() == vec![zero]);
+        assert_eq!(for_op.get_region_iter_args().len(), 1);
+        assert!(for_op.get_induction_var().get_type() == index_ty);
+        let yield_op = YieldOp::from_op(for_op.get_body().get_terminator()).unwrap();
+        assert_eq!(yield_op.get_operands().len(), 1);
+        let mut ifs = vec![];
+        module
+            .get_operation_ref()
+            .walk_typed::<IfOp, _>(WalkOrder::PreOrder, |if_op| {
+                ifs.push(if_op);
+                WalkResult::Advance
+            });
+        assert_eq!(ifs.len(), 2);
+        assert!(ifs[0].get_else_block().is_none());
+        assert!(ifs[1].get_else_block().is_some());
+    }
+
+    #[test]
+    fn while_parallel_forall() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let i32_ty: Type = IntegerType::get(&ctx, 32).into();
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let tensor_ty: Type = RankedTensorType::get(&[8], f32_ty, Attr::create_null()).into();
+        let func_ty = FunctionType::get(&ctx, &[tensor_ty], &[]);
+        let func = FuncOp::create(&builder, loc, "loops", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let c0 = arith::ConstantOp::create_index(&body, loc, 0).get_result();
+        let c1 = arith::ConstantOp::create_index(&body, loc, 1).get_result();
+        let c8 = arith::ConstantOp::create_index(&body, loc, 8).get_result();
+        let ten = arith::ConstantOp::create_int(&body, loc, 10, i32_ty).get_result();
+
+        let while_op = WhileOp::create(
+            &body,
+            loc,
+            &[ten],
+            &[i32_ty],
+            |b, args| {
+                let zero = arith::ConstantOp::create_int(b, loc, 0, i32_ty).get_result();
+                let cond = arith::CmpIOp::create(b, loc, arith::CmpIPredicate::Sgt, args[0], zero)
+                    .get_result();
+                (cond, vec![args[0]])
+            },
+            |b, args| {
+                let one = arith::ConstantOp::create_int(b, loc, 1, i32_ty).get_result();
+                vec![arith::SubIOp::create(b, loc, args[0], one).get_result()]
+            },
+        );
+        assert_eq!(while_op.get_results().len(), 1);
+        let condition = ConditionOp::from_op(while_op.get_before_block().get_terminator()).unwrap();
+        assert_eq!(condition.get_args().len(), 1);
+
+        let mut num_ivs = 0;
+        let parallel = ParallelOp::create(&body, loc, &[c0, c0], &[c8, c8], &[c1, c1], |_, ivs| {
+            num_ivs = ivs.len();
+        })
+        .unwrap();
+        assert_eq!(num_ivs, 2);
+        assert_eq!(parallel.get_induction_vars().len(), 2);
+
+        let forall = ForallOp::create(
+            &body,
+            loc,
+            &[c0],
+            &[c8],
+            &[c1],
+            &[func.get_argument(0).unwrap()],
+            |_, ivs, outs| {
+                assert_eq!(ivs.len(), 1);
+                assert!(outs[0].get_type() == tensor_ty);
+            },
+        )
+        .unwrap();
+        assert_eq!(forall.get_rank(), 1);
+        assert_eq!(forall.get_region_out_args().len(), 1);
+        assert!(forall.get_results()[0].get_type() == tensor_ty);
+        assert!(!forall.get_in_parallel_block().is_null());
+        assert!(ParallelOp::create(&body, loc, &[c0, c0], &[c8], &[c1, c1], |_, _| {}).is_err());
+        assert!(ForallOp::create(&body, loc, &[c0], &[c8], &[], &[], |_, _, _| {}).is_err());
+        ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
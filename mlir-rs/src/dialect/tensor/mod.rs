@@ -0,0 +1,702 @@
+use super::{
+    check_dynamic_sizes, check_indices, check_num_values, check_static_sizes, get_mixed_values,
+    get_offset_size_stride_attrs,
+};
+use crate::attribute::*;
+use crate::builder::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+fn get_tensor_type<'ctx>(value: Value<'ctx>) -> Result<RankedTensorType<'ctx>, String> {
+    let ty: RankedTensorType = value.get_type().dyn_cast();
+    if ty.is_null() {
+        Err(format!(
+            "expected a ranked tensor, got {}",
+            value.get_type()
+        ))
+    } else {
+        Ok(ty)
+    }
+}
+
+fn get_shape<'ctx>(ty: RankedTensorType<'ctx>) -> Vec<i64> {
+    (0..ty.get_rank() as usize)
+        .map(|dim| ty.get_dim_size(dim))
+        .collect()
+}
+
+/// Multiplies the dimension sizes in `sizes`, returning a dynamic size if any of them is. Fails
+/// if the product overflows.
+fn get_product(sizes: &[i64]) -> Result<i64, String> {
+    if sizes
+        .iter()
+        .any(|size| RankedTensorType::is_dynamic_size(*size))
+    {
+        return Ok(RankedTensorType::get_dynamic_size());
+    }
+    sizes
+        .iter()
+        .try_fold(1i64, |product, size| product.checked_mul(*size))
+        .ok_or_else(|| format!("the number of elements of shape {:?} overflows", sizes))
+}
+
+define_op_view!(EmptyOp, "tensor.empty");
+
+impl<'ctx> EmptyOp<'ctx> {
+    /// Creates a `tensor.empty` of type `ty`, with one operand in `dynamic_sizes` for each dynamic
+    /// dimension of `ty`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        ty: RankedTensorType<'ctx>,
+        dynamic_sizes: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        check_dynamic_sizes(ty, dynamic_sizes)?;
+        let op = builder.create_operation("tensor.empty", loc, dynamic_sizes, &[ty.into()], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_dynamic_sizes(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(ExtractOp, "tensor.extract");
+
+impl<'ctx> ExtractOp<'ctx> {
+    /// Creates a `tensor.extract` of the element at `indices`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        tensor: Value<'ctx>,
+        indices: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let ty = get_tensor_type(tensor)?;
+        check_indices(ty, indices)?;
+        let mut operands = vec![tensor];
+        operands.extend_from_slice(indices);
+        let op = builder.create_operation(
+            "tensor.extract",
+            loc,
+            &operands,
+            &[ty.get_element_type()],
+            &[],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_tensor(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_indices(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(1).collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(InsertOp, "tensor.insert");
+
+impl<'ctx> InsertOp<'ctx> {
+    /// Creates a `tensor.insert` of `scalar` into `dest` at `indices`, whose result is the
+    /// updated tensor.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        scalar: Value<'ctx>,
+        dest: Value<'ctx>,
+        indices: &[Value<'ctx>],
+    ) -> Result<Self, String> {
+        let ty = get_tensor_type(dest)?;
+        check_indices(ty, indices)?;
+        if scalar.get_type() != ty.get_element_type() {
+            return Err(format!(
+                "expected a value of type {}, got {}",
+                ty.get_element_type(),
+                scalar.get_type()
+            ));
+        }
+        let mut operands = vec![scalar, dest];
+        operands.extend_from_slice(indices);
+        let op = builder.create_operation("tensor.insert", loc, &operands, &[ty.into()], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_scalar(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_dest(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_indices(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().skip(2).collect()
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+fn get_mixed_offsets_sizes_strides<'ctx>(
+    op: OperationRef<'ctx>,
+    num_leading_operands: usize,
+) -> (
+    Vec<OpFoldResult<'ctx>>,
+    Vec<OpFoldResult<'ctx>>,
+    Vec<OpFoldResult<'ctx>>,
+) {
+    let mut dynamic_values = op.operands().skip(num_leading_operands);
+    let dynamic_stride_or_offset = RankedTensorType::get_dynamic_stride_or_offset();
+    let offsets = get_mixed_values(
+        op,
+        "static_offsets",
+        &mut dynamic_values,
+        dynamic_stride_or_offset,
+    );
+    let sizes = get_mixed_values(
+        op,
+        "static_sizes",
+        &mut dynamic_values,
+        RankedTensorType::get_dynamic_size(),
+    );
+    let strides = get_mixed_values(
+        op,
+        "static_strides",
+        &mut dynamic_values,
+        dynamic_stride_or_offset,
+    );
+    (offsets, sizes, strides)
+}
+
+fn check_slice(
+    rank: usize,
+    offsets: &[OpFoldResult],
+    sizes: &[OpFoldResult],
+    strides: &[OpFoldResult],
+) -> Result<(), String> {
+    check_num_values("offsets", offsets, rank)?;
+    check_num_values("sizes", sizes, rank)?;
+    check_num_values("strides", strides, rank)?;
+    check_static_sizes(sizes)
+}
+
+define_op_view!(ExtractSliceOp, "tensor.extract_slice");
+
+impl<'ctx> ExtractSliceOp<'ctx> {
+    /// Creates a `tensor.extract_slice` of `source`, whose result shape is given by `sizes`.
+    /// Rank-reducing slices are not supported.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        offsets: &[OpFoldResult<'ctx>],
+        sizes: &[OpFoldResult<'ctx>],
+        strides: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let source_ty = get_tensor_type(source)?;
+        check_slice(source_ty.get_rank() as usize, offsets, sizes, strides)?;
+        let result_shape: Vec<i64> = sizes
+            .iter()
+            .map(|size| {
+                size.get_static()
+                    .unwrap_or_else(RankedTensorType::get_dynamic_size)
+            })
+            .collect();
+        let result_ty = RankedTensorType::get(
+            &result_shape,
+            source_ty.get_element_type(),
+            source_ty.get_encoding(),
+        );
+
+        let (dynamic_values, attrs) =
+            get_offset_size_stride_attrs(builder, &[1], offsets, sizes, strides);
+        let mut operands = vec![source];
+        operands.extend(dynamic_values);
+        let op = builder.create_operation(
+            "tensor.extract_slice",
+            loc,
+            &operands,
+            &[result_ty.into()],
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+    /// Returns the offsets, sizes and strides, in that order.
+    pub fn get_mixed_offsets_sizes_strides(
+        &self,
+    ) -> (
+        Vec<OpFoldResult<'ctx>>,
+        Vec<OpFoldResult<'ctx>>,
+        Vec<OpFoldResult<'ctx>>,
+    ) {
+        get_mixed_offsets_sizes_strides(self.op, 1)
+    }
+}
+
+define_op_view!(InsertSliceOp, "tensor.insert_slice");
+
+impl<'ctx> InsertSliceOp<'ctx> {
+    /// Creates a `tensor.insert_slice` of `source` into `dest`, whose result is the updated
+    /// tensor. The static values in `sizes` must match the shape of `source`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        dest: Value<'ctx>,
+        offsets: &[OpFoldResult<'ctx>],
+        sizes: &[OpFoldResult<'ctx>],
+        strides: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let source_ty = get_tensor_type(source)?;
+        let dest_ty = get_tensor_type(dest)?;
+        check_slice(dest_ty.get_rank() as usize, offsets, sizes, strides)?;
+        if source_ty.get_element_type() != dest_ty.get_element_type() {
+            return Err(format!(
+                "cannot insert {} into {}: element types differ",
+                source_ty, dest_ty
+            ));
+        }
+        let source_shape = get_shape(source_ty);
+        let sizes_match = source_shape.len() == sizes.len()
+            && source_shape
+                .iter()
+                .zip(sizes)
+                .all(|(dim, size)| match size.get_static() {
+                    Some(size) => *dim == size,
+                    None => RankedTensorType::is_dynamic_size(*dim),
+                });
+        if !sizes_match {
+            return Err(format!("sizes do not match the shape of {}", source_ty));
+        }
+
+        let (dynamic_values, attrs) =
+            get_offset_size_stride_attrs(builder, &[1, 1], offsets, sizes, strides);
+        let mut operands = vec![source, dest];
+        operands.extend(dynamic_values);
+        let op = builder.create_operation(
+            "tensor.insert_slice",
+            loc,
+            &operands,
+            &[dest_ty.into()],
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_dest(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+    /// Returns the offsets, sizes and strides, in that order.
+    pub fn get_mixed_offsets_sizes_strides(
+        &self,
+    ) -> (
+        Vec<OpFoldResult<'ctx>>,
+        Vec<OpFoldResult<'ctx>>,
+        Vec<OpFoldResult<'ctx>>,
+    ) {
+        get_mixed_offsets_sizes_strides(self.op, 2)
+    }
+}
+
+define_op_view!(ReshapeOp, "tensor.reshape");
+
+impl<'ctx> ReshapeOp<'ctx> {
+    /// Creates a `tensor.reshape` of `source` to `result_ty`, where `shape` is a 1-D tensor holding
+    /// the result shape. Statically known element counts of `source` and `result_ty` must agree.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        shape: Value<'ctx>,
+        result_ty: RankedTensorType<'ctx>,
+    ) -> Result<Self, String> {
+        let source_ty = get_tensor_type(source)?;
+        let shape_ty = get_tensor_type(shape)?;
+        if shape_ty.get_rank() != 1 {
+            return Err(format!("expected a 1-D shape operand, got {}", shape_ty));
+        }
+        if !shape_ty.is_dynamic_dim(0) && shape_ty.get_dim_size(0) != result_ty.get_rank() {
+            return Err(format!(
+                "shape operand {} does not match the rank of {}",
+                shape_ty, result_ty
+            ));
+        }
+        let num_source_elements = get_product(&get_shape(source_ty))?;
+        let num_result_elements = get_product(&get_shape(result_ty))?;
+        if !RankedTensorType::is_dynamic_size(num_source_elements)
+            && !RankedTensorType::is_dynamic_size(num_result_elements)
+            && num_source_elements != num_result_elements
+        {
+            return Err(format!(
+                "cannot reshape {} to {}: element counts differ",
+                source_ty, result_ty
+            ));
+        }
+        let op = builder.create_operation(
+            "tensor.reshape",
+            loc,
+            &[source, shape],
+            &[result_ty.into()],
+            &[],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_shape(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+/// Checks that `reassociation` partitions the dimensions `0..rank` into contiguous, non-empty
+/// groups.
+fn check_reassociation(reassociation: &[Vec<i64>], rank: usize) -> Result<(), String> {
+    let dims: Vec<i64> = reassociation.iter().flatten().copied().collect();
+    let is_contiguous = dims.iter().enumerate().all(|(pos, dim)| pos as i64 == *dim);
+    if reassociation.iter().any(|group| group.is_empty()) || !is_contiguous || dims.len() != rank {
+        return Err(format!(
+            "expected a reassociation of the dimensions 0..{} into contiguous groups, got {:?}",
+            rank, reassociation
+        ));
+    }
+    Ok(())
+}
+
+fn get_reassociation_attr<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    reassociation: &[Vec<i64>],
+) -> NamedAttr<'ctx> {
+    let ctx = builder.get_context();
+    let i64_ty: Type = IntegerType::get(ctx, 64).into();
+    let groups: Vec<Attr> = reassociation
+        .iter()
+        .map(|group| {
+            let dims: Vec<Attr> = group
+                .iter()
+                .map(|dim| IntegerAttr::get(i64_ty, *dim).into())
+                .collect();
+            ArrayAttr::get(ctx, &dims).into()
+        })
+        .collect();
+    NamedAttr::get(ctx, "reassociation", ArrayAttr::get(ctx, &groups).into())
+}
+
+fn get_reassociation<'ctx>(op: OperationRef<'ctx>) -> Vec<Vec<i64>> {
+    let groups: ArrayAttr = op.get_attr_by_name("reassociation").dyn_cast();
+    (0..groups.len())
+        .map(|pos| {
+            let group: ArrayAttr = groups.get_element(pos).dyn_cast();
+            (0..group.len())
+                .map(|pos| {
+                    let dim: IntegerAttr = group.get_element(pos).dyn_cast();
+                    dim.get_value_int()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+define_op_view!(CollapseShapeOp, "tensor.collapse_shape");
+
+impl<'ctx> CollapseShapeOp<'ctx> {
+    /// Creates a `tensor.collapse_shape` of `source`, where each group in `reassociation` lists the
+    /// source dimensions folded into one result dimension.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        reassociation: &[Vec<i64>],
+    ) -> Result<Self, String> {
+        let source_ty = get_tensor_type(source)?;
+        check_reassociation(reassociation, source_ty.get_rank() as usize)?;
+        let source_shape = get_shape(source_ty);
+        let mut start = 0;
+        let result_shape: Vec<i64> = reassociation
+            .iter()
+            .map(|group| {
+                let size = get_product(&source_shape[start..start + group.len()]);
+                start += group.len();
+                size
+            })
+            .collect::<Result<_, _>>()?;
+        let result_ty = RankedTensorType::get(
+            &result_shape,
+            source_ty.get_element_type(),
+            source_ty.get_encoding(),
+        );
+        let op = builder.create_operation(
+            "tensor.collapse_shape",
+            loc,
+            &[source],
+            &[result_ty.into()],
+            &[get_reassociation_attr(builder, reassociation)],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_reassociation(&self) -> Vec<Vec<i64>> {
+        get_reassociation(self.op)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(ExpandShapeOp, "tensor.expand_shape");
+
+impl<'ctx> ExpandShapeOp<'ctx> {
+    /// Creates a `tensor.expand_shape` of `source` to `output_shape`, where each group in
+    /// `reassociation` lists the result dimensions that one source dimension is expanded into.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        reassociation: &[Vec<i64>],
+        output_shape: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let source_ty = get_tensor_type(source)?;
+        check_static_sizes(output_shape)?;
+        check_reassociation(reassociation, output_shape.len())?;
+        if reassociation.len() != source_ty.get_rank() as usize {
+            return Err(format!(
+                "expected {} reassociation groups, got {}",
+                source_ty.get_rank(),
+                reassociation.len()
+            ));
+        }
+        let (dynamic_sizes, result_shape) =
+            OpFoldResult::dispatch(output_shape, RankedTensorType::get_dynamic_size());
+        let mut start = 0;
+        for (dim, group) in reassociation.iter().enumerate() {
+            let source_size = source_ty.get_dim_size(dim);
+            let size = get_product(&result_shape[start..start + group.len()])?;
+            start += group.len();
+            if RankedTensorType::is_dynamic_size(source_size)
+                != RankedTensorType::is_dynamic_size(size)
+                || source_size != size
+            {
+                return Err(format!(
+                    "output shape does not match dimension {} of {}",
+                    dim, source_ty
+                ));
+            }
+        }
+        let result_ty = RankedTensorType::get(
+            &result_shape,
+            source_ty.get_element_type(),
+            source_ty.get_encoding(),
+        );
+        let ctx = builder.get_context();
+        let mut operands = vec![source];
+        operands.extend(dynamic_sizes);
+        let op = builder.create_operation(
+            "tensor.expand_shape",
+            loc,
+            &operands,
+            &[result_ty.into()],
+            &[
+                get_reassociation_attr(builder, reassociation),
+                NamedAttr::get(
+                    ctx,
+                    "static_output_shape",
+                    DenseI64ArrayAttr::get(ctx, &result_shape).into(),
+                ),
+            ],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_reassociation(&self) -> Vec<Vec<i64>> {
+        get_reassociation(self.op)
+    }
+    pub fn get_output_shape(&self) -> Vec<OpFoldResult<'ctx>> {
+        let mut dynamic_values = self.op.operands().skip(1);
+        get_mixed_values(
+            self.op,
+            "static_output_shape",
+            &mut dynamic_values,
+            RankedTensorType::get_dynamic_size(),
+        )
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+#[cfg(test)]
+mod tensor_test {
+    use super::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::Tensor,
+    ];
+
+    #[test]
+    fn empty_extract_insert() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let dynamic = RankedTensorType::get_dynamic_size();
+        let ty = RankedTensorType::get(&[dynamic, 4], f32_ty, Attr::create_null());
+        let func_ty = FunctionType::get(&ctx, &[index_ty, f32_ty], &[ty.into()]);
+        let func = FuncOp::create(&builder, loc, "fill", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let n = func.get_argument(0).unwrap();
+        let scalar = func.get_argument(1).unwrap();
+
+        assert!(EmptyOp::create(&body, loc, ty, &[]).is_err());
+        let empty_op = EmptyOp::create(&body, loc, ty, &[n]).unwrap();
+        let init = empty_op.get_result();
+        let c0 = arith::ConstantOp::create_index(&body, loc, 0).get_result();
+        assert!(InsertOp::create(&body, loc, scalar, init, &[c0]).is_err());
+        assert!(InsertOp::create(&body, loc, c0, init, &[c0, c0]).is_err());
+        let updated = InsertOp::create(&body, loc, scalar, init, &[c0, c0])
+            .unwrap()
+            .get_result();
+        assert!(updated.get_type() == ty);
+        let extract_op = ExtractOp::create(&body, loc, updated, &[c0, c0]).unwrap();
+        let element = extract_op.get_result();
+        assert!(element.get_type() == f32_ty);
+        ReturnOp::create(&body, loc, &[updated]);
+        assert!(module.get_operation_ref().verify().is_ok());
+
+        assert!(EmptyOp::from_op(init.op_res_get_owner()).is_some());
+        assert!(empty_op.get_dynamic_sizes() == vec![n]);
+        assert!(extract_op.get_tensor() == updated);
+    }
+
+    #[test]
+    fn slices_and_reshapes() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let source_ty = RankedTensorType::get(&[8, 16], f32_ty, Attr::create_null());
+        let shape_ty = RankedTensorType::get(&[1], index_ty, Attr::create_null());
+        let func_ty = FunctionType::get(&ctx, &[source_ty.into(), index_ty, shape_ty.into()], &[]);
+        let func = FuncOp::create(&builder, loc, "slices", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let source = func.get_argument(0).unwrap();
+        let i = func.get_argument(1).unwrap();
+        let shape = func.get_argument(2).unwrap();
+
+        let slice_op = ExtractSliceOp::create(
+            &body,
+            loc,
+            source,
+            &[i.into(), 0.into()],
+            &[4.into(), i.into()],
+            &[1.into(), 1.into()],
+        )
+        .unwrap();
+        let slice = slice_op.get_result();
+        let slice_ty: RankedTensorType = slice.get_type().dyn_cast();
+        assert_eq!(slice_ty.get_dim_size(0), 4);
+        assert!(slice_ty.is_dynamic_dim(1));
+        let (offsets, sizes, strides) = slice_op.get_mixed_offsets_sizes_strides();
+        assert!(offsets == vec![OpFoldResult::Dynamic(i), OpFoldResult::Static(0)]);
+        assert!(sizes == vec![OpFoldResult::Static(4), OpFoldResult::Dynamic(i)]);
+        assert!(strides == vec![OpFoldResult::Static(1), OpFoldResult::Static(1)]);
+        assert!(
+            ExtractSliceOp::create(&body, loc, source, &[0.into()], &[4.into()], &[1.into()])
+                .is_err()
+        );
+
+        let offsets: [OpFoldResult; 2] = [0.into(), 0.into()];
+        let strides: [OpFoldResult; 2] = [1.into(), 1.into()];
+        let inserted = InsertSliceOp::create(
+            &body,
+            loc,
+            slice,
+            source,
+            &offsets,
+            &[4.into(), i.into()],
+            &strides,
+        )
+        .unwrap()
+        .get_result();
+        assert!(inserted.get_type() == source_ty);
+        assert!(InsertSliceOp::create(
+            &body,
+            loc,
+            slice,
+            source,
+            &offsets,
+            &[4.into(), 8.into()],
+            &strides
+        )
+        .is_err());
+
+        let collapse_op = CollapseShapeOp::create(&body, loc, source, &[vec![0, 1]]).unwrap();
+        let collapsed = collapse_op.get_result();
+        let collapsed_ty: RankedTensorType = collapsed.get_type().dyn_cast();
+        assert_eq!(collapsed_ty.get_dim_size(0), 128);
+        assert!(CollapseShapeOp::create(&body, loc, source, &[vec![1, 0]]).is_err());
+        assert_eq!(collapse_op.get_reassociation(), vec![vec![0, 1]]);
+
+        let expanded = ExpandShapeOp::create(
+            &body,
+            loc,
+            collapsed,
+            &[vec![0, 1, 2]],
+            &[2.into(), 4.into(), 16.into()],
+        )
+        .unwrap()
+        .get_result();
+        let expanded_ty: RankedTensorType = expanded.get_type().dyn_cast();
+        assert_eq!(expanded_ty.get_rank(), 3);
+        assert!(
+            ExpandShapeOp::create(&body, loc, collapsed, &[vec![0, 1]], &[2.into(), 4.into()])
+                .is_err()
+        );
+        let huge: OpFoldResult = (1i64 << 40).into();
+        assert!(
+            ExpandShapeOp::create(&body, loc, collapsed, &[vec![0, 1]], &[huge, huge]).is_err()
+        );
+
+        let flat_ty = RankedTensorType::get(&[128], f32_ty, Attr::create_null());
+        let reshaped = ReshapeOp::create(&body, loc, source, shape, flat_ty)
+            .unwrap()
+            .get_result();
+        assert!(reshaped.get_type() == flat_ty);
+        let bad_ty = RankedTensorType::get(&[64], f32_ty, Attr::create_null());
+        assert!(ReshapeOp::create(&body, loc, source, shape, bad_ty).is_err());
+        ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
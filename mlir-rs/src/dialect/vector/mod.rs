@@ -0,0 +1,1200 @@
+use super::linalg::IteratorType;
+use super::{
+    append_block, create_region_op, get_mixed_values, get_operand_segment,
+    get_operand_segment_sizes,
+};
+use crate::affine_map::*;
+use crate::attribute::*;
+use crate::block::*;
+use crate::builder::*;
+use crate::context::*;
+use crate::diagnostic::*;
+use crate::location::*;
+use crate::operation::*;
+use crate::r#type::*;
+use crate::type_cast::*;
+use crate::value::*;
+
+use std::str::FromStr;
+
+use strum::EnumIter;
+
+/// The operation used to combine elements in `vector.contract` and `vector.reduction`.
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CombiningKind {
+    Add,
+    Mul,
+    MinUI,
+    MinSI,
+    MinNumF,
+    MaxUI,
+    MaxSI,
+    MaxNumF,
+    And,
+    Or,
+    Xor,
+    MinimumF,
+    MaximumF,
+}
+
+impl CombiningKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CombiningKind::Add => "add",
+            CombiningKind::Mul => "mul",
+            CombiningKind::MinUI => "minui",
+            CombiningKind::MinSI => "minsi",
+            CombiningKind::MinNumF => "minnumf",
+            CombiningKind::MaxUI => "maxui",
+            CombiningKind::MaxSI => "maxsi",
+            CombiningKind::MaxNumF => "maxnumf",
+            CombiningKind::And => "and",
+            CombiningKind::Or => "or",
+            CombiningKind::Xor => "xor",
+            CombiningKind::MinimumF => "minimumf",
+            CombiningKind::MaximumF => "maximumf",
+        }
+    }
+    /// Returns the `#vector.kind` attribute, fails if the vector dialect is not loaded in `ctx`.
+    pub fn get_attr<'ctx>(self, ctx: &'ctx Context) -> Result<Attr<'ctx>, Diagnostics<'ctx>> {
+        Attr::parse(ctx, &format!("#vector.kind<{}>", self.as_str()))
+    }
+    fn from_attr(attr: Attr) -> Option<Self> {
+        let ctx = ContextRef::from_handle_same_context(attr.get_context_handle(), &attr);
+        <Self as strum::IntoEnumIterator>::iter()
+            .find(|kind| kind.get_attr(&ctx).map_or(false, |a| a == attr))
+    }
+}
+
+impl FromStr for CombiningKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(CombiningKind::Add),
+            "mul" => Ok(CombiningKind::Mul),
+            "minui" => Ok(CombiningKind::MinUI),
+            "minsi" => Ok(CombiningKind::MinSI),
+            "minnumf" => Ok(CombiningKind::MinNumF),
+            "maxui" => Ok(CombiningKind::MaxUI),
+            "maxsi" => Ok(CombiningKind::MaxSI),
+            "maxnumf" => Ok(CombiningKind::MaxNumF),
+            "and" => Ok(CombiningKind::And),
+            "or" => Ok(CombiningKind::Or),
+            "xor" => Ok(CombiningKind::Xor),
+            "minimumf" => Ok(CombiningKind::MinimumF),
+            "maximumf" => Ok(CombiningKind::MaximumF),
+            _ => Err(format!("invalid combining kind '{}'", s)),
+        }
+    }
+}
+
+/// Returns the `#vector.iterator_type` attribute, fails if the vector dialect is not loaded in
+/// `ctx`.
+fn get_iterator_type_attr(ctx: &Context, iterator_type: IteratorType) -> Result<Attr, Diagnostics> {
+    Attr::parse(
+        ctx,
+        &format!("#vector.iterator_type<{}>", iterator_type.as_str()),
+    )
+}
+
+fn get_iterator_type_from_attr(attr: Attr) -> Option<IteratorType> {
+    let ctx = ContextRef::from_handle_same_context(attr.get_context_handle(), &attr);
+    <IteratorType as strum::IntoEnumIterator>::iter().find(|iterator_type| {
+        get_iterator_type_attr(&ctx, *iterator_type).map_or(false, |a| a == attr)
+    })
+}
+
+fn get_vector_type<'ctx>(value: Value<'ctx>) -> Result<VectorType<'ctx>, String> {
+    let ty: VectorType = value.get_type().dyn_cast();
+    if ty.is_null() {
+        Err(format!("expected a vector, got {}", value.get_type()))
+    } else {
+        Ok(ty)
+    }
+}
+
+/// Returns the dimension sizes and scalability of `ty`.
+fn get_shape(ty: VectorType) -> (Vec<i64>, Vec<bool>) {
+    (0..ty.get_rank() as usize)
+        .map(|dim| (ty.get_dim_size(dim), ty.is_dim_scalable(dim)))
+        .unzip()
+}
+
+/// Returns the rank of `ty` if it is a vector and 0 otherwise, along with its element type.
+fn get_rank_and_element_type(ty: Type) -> (usize, Type) {
+    let vector_ty: VectorType = ty.dyn_cast();
+    if vector_ty.is_null() {
+        (0, ty)
+    } else {
+        (vector_ty.get_rank() as usize, vector_ty.get_element_type())
+    }
+}
+
+/// Returns the rank and the element type of the memref or ranked tensor `value`, and whether it
+/// is a tensor.
+fn get_shaped_type(value: Value) -> Result<(usize, Type, bool), String> {
+    let ty = value.get_type();
+    let tensor_ty: RankedTensorType = ty.dyn_cast();
+    if !tensor_ty.is_null() {
+        return Ok((
+            tensor_ty.get_rank() as usize,
+            tensor_ty.get_element_type(),
+            true,
+        ));
+    }
+    let memref_ty: MemRefType = ty.dyn_cast();
+    if !memref_ty.is_null() {
+        return Ok((
+            memref_ty.get_rank() as usize,
+            memref_ty.get_element_type(),
+            false,
+        ));
+    }
+    Err(format!("expected a ranked tensor or memref, got {}", ty))
+}
+
+/// Checks that `mask` has the type of the masks of transfers of `vector_ty` with the
+/// permutation map `map`: the vector shape permuted back to the source dimensions used by `map`,
+/// broadcast dimensions being left out.
+fn check_mask<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    vector_ty: VectorType<'ctx>,
+    map: AffineMap<'ctx>,
+    mask: Value<'ctx>,
+) -> Result<(), String> {
+    let results = map.get_results();
+    let mut shape = vec![];
+    let mut scalable = vec![];
+    for dim in 0..map.get_num_dims() {
+        let result_pos = results
+            .iter()
+            .position(|expr| expr.is_a_dim() && expr.dim_expr_get_pos() == dim as i64);
+        if let Some(pos) = result_pos {
+            shape.push(vector_ty.get_dim_size(pos));
+            scalable.push(vector_ty.is_dim_scalable(pos));
+        }
+    }
+    let i1_ty: Type = IntegerType::get(builder.get_context(), 1).into();
+    let mask_ty: Type = VectorType::get_scalable(&shape, &scalable, i1_ty).into();
+    if mask.get_type() != mask_ty {
+        return Err(format!(
+            "expected a mask of type {} for {}, got {}",
+            mask_ty,
+            Into::<Type>::into(vector_ty),
+            mask.get_type()
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the attributes shared by transfer ops, after checking the mask of the op, if any.
+fn get_transfer_attrs<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    vector_ty: VectorType<'ctx>,
+    source_rank: usize,
+    permutation_map: Option<AffineMap<'ctx>>,
+    mask: Option<Value<'ctx>>,
+    in_bounds: &[bool],
+) -> Result<Vec<NamedAttr<'ctx>>, String> {
+    let ctx = builder.get_context();
+    let vector_rank = vector_ty.get_rank() as usize;
+    if vector_rank > source_rank {
+        return Err(format!(
+            "expected a vector of rank at most {}, got {}",
+            source_rank,
+            Into::<Type>::into(vector_ty)
+        ));
+    }
+    let map = permutation_map
+        .unwrap_or_else(|| AffineMap::minor_identity_get(ctx, source_rank, vector_rank));
+    if map.get_num_dims() != source_rank
+        || map.get_num_symbols() != 0
+        || map.get_num_results() != vector_rank
+    {
+        return Err(format!(
+            "expected a permutation map from {} dims to {} results, got {}",
+            source_rank, vector_rank, map
+        ));
+    }
+    if let Some(mask) = mask {
+        check_mask(builder, vector_ty, map, mask)?;
+    }
+    let in_bounds: Vec<Attr> = if in_bounds.is_empty() {
+        vec![BoolAttr::get(ctx, false).into(); vector_rank]
+    } else if in_bounds.len() == vector_rank {
+        in_bounds
+            .iter()
+            .map(|in_bounds| BoolAttr::get(ctx, *in_bounds).into())
+            .collect()
+    } else {
+        return Err(format!(
+            "expected {} in_bounds values, got {}",
+            vector_rank,
+            in_bounds.len()
+        ));
+    };
+    Ok(vec![
+        NamedAttr::get(ctx, "permutation_map", AffineMapAttr::get(map).into()),
+        NamedAttr::get(ctx, "in_bounds", ArrayAttr::get(ctx, &in_bounds).into()),
+    ])
+}
+
+fn get_permutation_map<'ctx>(op: OperationRef<'ctx>) -> AffineMap<'ctx> {
+    let attr: AffineMapAttr = op.get_attr_by_name("permutation_map").dyn_cast();
+    attr.get_value()
+}
+
+fn get_in_bounds(op: OperationRef) -> Vec<bool> {
+    let attr: ArrayAttr = op.get_attr_by_name("in_bounds").dyn_cast();
+    (0..attr.len())
+        .map(|pos| {
+            let in_bounds: BoolAttr = attr.get_element(pos).dyn_cast();
+            in_bounds.get_val()
+        })
+        .collect()
+}
+
+define_op_view!(BroadcastOp, "vector.broadcast");
+
+impl<'ctx> BroadcastOp<'ctx> {
+    /// Creates a `vector.broadcast` of the scalar or vector `source` to `result_ty`. Each dimension
+    /// of a vector `source` must either be 1 or match the corresponding trailing dimension of
+    /// `result_ty`, including its scalability.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        result_ty: VectorType<'ctx>,
+    ) -> Result<Self, String> {
+        let source_ty = source.get_type();
+        let (source_rank, elem_ty) = get_rank_and_element_type(source_ty);
+        let result_rank = result_ty.get_rank() as usize;
+        let mut compatible = elem_ty == result_ty.get_element_type() && source_rank <= result_rank;
+        if compatible && source_rank > 0 {
+            let (source_shape, source_scalable) = get_shape(source_ty.dyn_cast());
+            let (result_shape, result_scalable) = get_shape(result_ty);
+            let leading = result_rank - source_rank;
+            compatible = source_shape
+                .iter()
+                .zip(&source_scalable)
+                .zip(
+                    result_shape[leading..]
+                        .iter()
+                        .zip(&result_scalable[leading..]),
+                )
+                .all(|((size, scalable), (result_size, result_scalable))| {
+                    (*size == 1 && !scalable)
+                        || (size == result_size && scalable == result_scalable)
+                });
+        }
+        if !compatible {
+            return Err(format!(
+                "cannot broadcast {} to {}",
+                source_ty,
+                Into::<Type>::into(result_ty)
+            ));
+        }
+        let op =
+            builder.create_operation("vector.broadcast", loc, &[source], &[result_ty.into()], &[]);
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(TransferReadOp, "vector.transfer_read");
+
+impl<'ctx> TransferReadOp<'ctx> {
+    /// Creates a `vector.transfer_read` of a `vector_ty` from the memref or tensor `source` at
+    /// `indices`, filling out-of-bounds elements with `padding`. The permutation map defaults to
+    /// the minor identity, and an empty `in_bounds` marks every dimension as possibly out of
+    /// bounds.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        vector_ty: VectorType<'ctx>,
+        source: Value<'ctx>,
+        indices: &[Value<'ctx>],
+        padding: Value<'ctx>,
+        permutation_map: Option<AffineMap<'ctx>>,
+        mask: Option<Value<'ctx>>,
+        in_bounds: &[bool],
+    ) -> Result<Self, String> {
+        let (source_rank, elem_ty, _) = get_shaped_type(source)?;
+        if indices.len() != source_rank {
+            return Err(format!(
+                "expected {} indices, got {}",
+                source_rank,
+                indices.len()
+            ));
+        }
+        if padding.get_type() != elem_ty || vector_ty.get_element_type() != elem_ty {
+            return Err(format!(
+                "expected a padding value and vector elements of type {}",
+                elem_ty
+            ));
+        }
+        let mut attrs = get_transfer_attrs(
+            builder,
+            vector_ty,
+            source_rank,
+            permutation_map,
+            mask,
+            in_bounds,
+        )?;
+        attrs.push(get_operand_segment_sizes(
+            builder,
+            &[1, indices.len(), 1, mask.is_some() as usize],
+        ));
+        let mut operands = vec![source];
+        operands.extend_from_slice(indices);
+        operands.push(padding);
+        operands.extend(mask);
+        let op = builder.create_operation(
+            "vector.transfer_read",
+            loc,
+            &operands,
+            &[vector_ty.into()],
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_indices(&self) -> Vec<Value<'ctx>> {
+        let num_indices = get_operand_segment(self.op, 1).len();
+        self.op.operands().skip(1).take(num_indices).collect()
+    }
+    pub fn get_padding(&self) -> Value<'ctx> {
+        self.op
+            .get_operand(1 + get_operand_segment(self.op, 1).len())
+    }
+    pub fn get_mask(&self) -> Option<Value<'ctx>> {
+        if get_operand_segment(self.op, 3).len() == 0 {
+            None
+        } else {
+            Some(
+                self.op
+                    .get_operand(2 + get_operand_segment(self.op, 1).len()),
+            )
+        }
+    }
+    pub fn get_permutation_map(&self) -> AffineMap<'ctx> {
+        get_permutation_map(self.op)
+    }
+    pub fn get_in_bounds(&self) -> Vec<bool> {
+        get_in_bounds(self.op)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(TransferWriteOp, "vector.transfer_write");
+
+impl<'ctx> TransferWriteOp<'ctx> {
+    /// Creates a `vector.transfer_write` of `vector` into the memref or tensor `dest` at
+    /// `indices`, whose result is the updated tensor if `dest` is one. The permutation map
+    /// defaults to the minor identity, and an empty `in_bounds` marks every dimension as possibly
+    /// out of bounds.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        vector: Value<'ctx>,
+        dest: Value<'ctx>,
+        indices: &[Value<'ctx>],
+        permutation_map: Option<AffineMap<'ctx>>,
+        mask: Option<Value<'ctx>>,
+        in_bounds: &[bool],
+    ) -> Result<Self, String> {
+        let vector_ty = get_vector_type(vector)?;
+        let (dest_rank, elem_ty, is_tensor) = get_shaped_type(dest)?;
+        if indices.len() != dest_rank {
+            return Err(format!(
+                "expected {} indices, got {}",
+                dest_rank,
+                indices.len()
+            ));
+        }
+        if vector_ty.get_element_type() != elem_ty {
+            return Err(format!("expected vector elements of type {}", elem_ty));
+        }
+        let mut attrs = get_transfer_attrs(
+            builder,
+            vector_ty,
+            dest_rank,
+            permutation_map,
+            mask,
+            in_bounds,
+        )?;
+        attrs.push(get_operand_segment_sizes(
+            builder,
+            &[1, 1, indices.len(), mask.is_some() as usize],
+        ));
+        let mut operands = vec![vector, dest];
+        operands.extend_from_slice(indices);
+        operands.extend(mask);
+        let result_types = if is_tensor {
+            vec![dest.get_type()]
+        } else {
+            vec![]
+        };
+        let op = builder.create_operation(
+            "vector.transfer_write",
+            loc,
+            &operands,
+            &result_types,
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_vector(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_dest(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_indices(&self) -> Vec<Value<'ctx>> {
+        let num_indices = get_operand_segment(self.op, 2).len();
+        self.op.operands().skip(2).take(num_indices).collect()
+    }
+    pub fn get_mask(&self) -> Option<Value<'ctx>> {
+        if get_operand_segment(self.op, 3).len() == 0 {
+            None
+        } else {
+            Some(
+                self.op
+                    .get_operand(2 + get_operand_segment(self.op, 2).len()),
+            )
+        }
+    }
+    pub fn get_permutation_map(&self) -> AffineMap<'ctx> {
+        get_permutation_map(self.op)
+    }
+    pub fn get_in_bounds(&self) -> Vec<bool> {
+        get_in_bounds(self.op)
+    }
+    pub fn get_result(&self) -> Option<Value<'ctx>> {
+        if self.op.get_num_results() == 0 {
+            None
+        } else {
+            Some(self.op.get_result(0))
+        }
+    }
+}
+
+define_op_view!(ContractionOp, "vector.contract");
+
+impl<'ctx> ContractionOp<'ctx> {
+    /// Creates a `vector.contract` combining the products of `lhs` and `rhs` into `acc` with
+    /// `kind`. There is one indexing map for each of the three operands, and one iterator type for
+    /// each dimension of the maps.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        lhs: Value<'ctx>,
+        rhs: Value<'ctx>,
+        acc: Value<'ctx>,
+        indexing_maps: &[AffineMap<'ctx>; 3],
+        iterator_types: &[IteratorType],
+        kind: CombiningKind,
+    ) -> Result<Self, String> {
+        get_vector_type(lhs)?;
+        get_vector_type(rhs)?;
+        for (pos, (operand, map)) in [lhs, rhs, acc].iter().zip(indexing_maps).enumerate() {
+            let (rank, _) = get_rank_and_element_type(operand.get_type());
+            if map.get_num_dims() != iterator_types.len() {
+                return Err(format!(
+                    "expected indexing map #{} to have {} dims, got {}",
+                    pos,
+                    iterator_types.len(),
+                    map
+                ));
+            }
+            if map.get_num_results() != rank {
+                return Err(format!(
+                    "expected indexing map #{} to have {} results, got {}",
+                    pos, rank, map
+                ));
+            }
+        }
+
+        let ctx = builder.get_context();
+        let maps: Vec<Attr> = indexing_maps
+            .iter()
+            .map(|map| AffineMapAttr::get(*map).into())
+            .collect();
+        let iterators: Vec<Attr> = iterator_types
+            .iter()
+            .map(|iterator_type| get_iterator_type_attr(ctx, *iterator_type))
+            .collect::<Result<_, _>>()
+            .map_err(|diags| diags.to_string())?;
+        let attrs = [
+            NamedAttr::get(ctx, "indexing_maps", ArrayAttr::get(ctx, &maps).into()),
+            NamedAttr::get(
+                ctx,
+                "iterator_types",
+                ArrayAttr::get(ctx, &iterators).into(),
+            ),
+            NamedAttr::get(
+                ctx,
+                "kind",
+                kind.get_attr(ctx).map_err(|diags| diags.to_string())?,
+            ),
+        ];
+        let op = builder.create_operation(
+            "vector.contract",
+            loc,
+            &[lhs, rhs, acc],
+            &[acc.get_type()],
+            &attrs,
+        );
+        Ok(Self { op })
+    }
+    pub fn get_lhs(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_rhs(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_acc(&self) -> Value<'ctx> {
+        self.op.get_operand(2)
+    }
+    pub fn get_indexing_maps(&self) -> Vec<AffineMap<'ctx>> {
+        let maps: ArrayAttr = self.op.get_attr_by_name("indexing_maps").dyn_cast();
+        (0..maps.len())
+            .map(|pos| {
+                let map: AffineMapAttr = maps.get_element(pos).dyn_cast();
+                map.get_value()
+            })
+            .collect()
+    }
+    /// Returns `None` if any of the iterator types is not a `#vector.iterator_type`.
+    pub fn get_iterator_types(&self) -> Option<Vec<IteratorType>> {
+        let iterators: ArrayAttr = self.op.get_attr_by_name("iterator_types").dyn_cast();
+        (0..iterators.len())
+            .map(|pos| get_iterator_type_from_attr(iterators.get_element(pos)))
+            .collect()
+    }
+    /// Returns `None` if the `kind` attribute is not a `#vector.kind`.
+    pub fn get_kind(&self) -> Option<CombiningKind> {
+        CombiningKind::from_attr(self.op.get_attr_by_name("kind"))
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(ReductionOp, "vector.reduction");
+
+impl<'ctx> ReductionOp<'ctx> {
+    /// Creates a `vector.reduction` of the 1-D `vector` with `kind`, optionally combined with the
+    /// scalar `acc`.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        kind: CombiningKind,
+        vector: Value<'ctx>,
+        acc: Option<Value<'ctx>>,
+    ) -> Result<Self, String> {
+        let vector_ty = get_vector_type(vector)?;
+        if vector_ty.get_rank() != 1 {
+            return Err(format!("expected a 1-D vector, got {}", vector.get_type()));
+        }
+        let elem_ty = vector_ty.get_element_type();
+        if let Some(acc) = acc.filter(|acc| acc.get_type() != elem_ty) {
+            return Err(format!(
+                "expected an accumulator of type {}, got {}",
+                elem_ty,
+                acc.get_type()
+            ));
+        }
+        let ctx = builder.get_context();
+        let kind_attr = kind.get_attr(ctx).map_err(|diags| diags.to_string())?;
+        let mut operands = vec![vector];
+        operands.extend(acc);
+        let op = builder.create_operation(
+            "vector.reduction",
+            loc,
+            &operands,
+            &[elem_ty],
+            &[NamedAttr::get(ctx, "kind", kind_attr)],
+        );
+        Ok(Self { op })
+    }
+    /// Returns `None` if the `kind` attribute is not a `#vector.kind`.
+    pub fn get_kind(&self) -> Option<CombiningKind> {
+        CombiningKind::from_attr(self.op.get_attr_by_name("kind"))
+    }
+    pub fn get_vector(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_acc(&self) -> Option<Value<'ctx>> {
+        self.op.operands().nth(1)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+/// Returns the type of the element or sub-vector of `ty` at `position`, the dynamic indices of
+/// `position`, and its `static_position` attribute.
+fn get_position_type_and_attr<'ctx>(
+    builder: &OpBuilder<'ctx>,
+    ty: VectorType<'ctx>,
+    position: &[OpFoldResult<'ctx>],
+) -> Result<(Type<'ctx>, Vec<Value<'ctx>>, NamedAttr<'ctx>), String> {
+    let (shape, scalable) = get_shape(ty);
+    if position.len() > shape.len() {
+        return Err(format!(
+            "expected at most {} indices, got {}",
+            shape.len(),
+            position.len()
+        ));
+    }
+    let out_of_bounds = position.iter().zip(&shape).any(|(index, size)| {
+        index
+            .get_static()
+            .is_some_and(|index| index < 0 || index >= *size)
+    });
+    if out_of_bounds {
+        return Err(format!(
+            "position out of bounds of {}",
+            Into::<Type>::into(ty)
+        ));
+    }
+    let num_indices = position.len();
+    let sub_ty = if num_indices == shape.len() {
+        ty.get_element_type()
+    } else {
+        VectorType::get_scalable(
+            &shape[num_indices..],
+            &scalable[num_indices..],
+            ty.get_element_type(),
+        )
+        .into()
+    };
+    let ctx = builder.get_context();
+    let (dynamic_position, static_position) =
+        OpFoldResult::dispatch(position, VectorType::get_dynamic_size());
+    let attr = NamedAttr::get(
+        ctx,
+        "static_position",
+        DenseI64ArrayAttr::get(ctx, &static_position).into(),
+    );
+    Ok((sub_ty, dynamic_position, attr))
+}
+
+define_op_view!(ExtractOp, "vector.extract");
+
+impl<'ctx> ExtractOp<'ctx> {
+    /// Creates a `vector.extract` of the element or sub-vector of `vector` at `position`, whose
+    /// indices are either static or index values.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        vector: Value<'ctx>,
+        position: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let vector_ty = get_vector_type(vector)?;
+        let (result_ty, dynamic_position, attr) =
+            get_position_type_and_attr(builder, vector_ty, position)?;
+        let mut operands = vec![vector];
+        operands.extend(dynamic_position);
+        let op = builder.create_operation("vector.extract", loc, &operands, &[result_ty], &[attr]);
+        Ok(Self { op })
+    }
+    pub fn get_vector(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_mixed_position(&self) -> Vec<OpFoldResult<'ctx>> {
+        get_mixed_values(
+            self.op,
+            "static_position",
+            &mut self.op.operands().skip(1),
+            VectorType::get_dynamic_size(),
+        )
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(InsertOp, "vector.insert");
+
+impl<'ctx> InsertOp<'ctx> {
+    /// Creates a `vector.insert` of the element or sub-vector `source` into `dest` at `position`,
+    /// whose indices are either static or index values. The result is the updated vector.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        dest: Value<'ctx>,
+        position: &[OpFoldResult<'ctx>],
+    ) -> Result<Self, String> {
+        let dest_ty = get_vector_type(dest)?;
+        let (source_ty, dynamic_position, attr) =
+            get_position_type_and_attr(builder, dest_ty, position)?;
+        if source.get_type() != source_ty {
+            return Err(format!(
+                "expected a value of type {}, got {}",
+                source_ty,
+                source.get_type()
+            ));
+        }
+        let mut operands = vec![source, dest];
+        operands.extend(dynamic_position);
+        let op =
+            builder.create_operation("vector.insert", loc, &operands, &[dest_ty.into()], &[attr]);
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_dest(&self) -> Value<'ctx> {
+        self.op.get_operand(1)
+    }
+    pub fn get_mixed_position(&self) -> Vec<OpFoldResult<'ctx>> {
+        get_mixed_values(
+            self.op,
+            "static_position",
+            &mut self.op.operands().skip(2),
+            VectorType::get_dynamic_size(),
+        )
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(ShapeCastOp, "vector.shape_cast");
+
+impl<'ctx> ShapeCastOp<'ctx> {
+    /// Creates a `vector.shape_cast` of `source` to `result_ty`, which must have the same element
+    /// type, the same number of elements and the same number of scalable dimensions.
+    pub fn create(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        source: Value<'ctx>,
+        result_ty: VectorType<'ctx>,
+    ) -> Result<Self, String> {
+        let source_ty = get_vector_type(source)?;
+        let (source_shape, source_scalable) = get_shape(source_ty);
+        let (result_shape, result_scalable) = get_shape(result_ty);
+        let num_scalable =
+            |scalable: &[bool]| scalable.iter().filter(|scalable| **scalable).count();
+        if source_ty.get_element_type() != result_ty.get_element_type()
+            || source_shape.iter().product::<i64>() != result_shape.iter().product::<i64>()
+            || num_scalable(&source_scalable) != num_scalable(&result_scalable)
+        {
+            return Err(format!(
+                "cannot shape_cast {} to {}",
+                source.get_type(),
+                Into::<Type>::into(result_ty)
+            ));
+        }
+        let op = builder.create_operation(
+            "vector.shape_cast",
+            loc,
+            &[source],
+            &[result_ty.into()],
+            &[],
+        );
+        Ok(Self { op })
+    }
+    pub fn get_source(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_result(&self) -> Value<'ctx> {
+        self.op.get_result(0)
+    }
+}
+
+define_op_view!(YieldOp, "vector.yield");
+
+impl<'ctx> YieldOp<'ctx> {
+    pub fn create(builder: &OpBuilder<'ctx>, loc: Location<'ctx>, values: &[Value<'ctx>]) -> Self {
+        Self {
+            op: builder.create_operation("vector.yield", loc, values, &[], &[]),
+        }
+    }
+    pub fn get_operands(&self) -> Vec<Value<'ctx>> {
+        self.op.operands().collect()
+    }
+}
+
+define_op_view!(MaskOp, "vector.mask");
+
+impl<'ctx> MaskOp<'ctx> {
+    /// Creates a `vector.mask` applying `mask` to the maskable op created by `body`, which
+    /// returns the values yielded as the `result_types` results. Masked-off lanes of the results
+    /// are taken from `passthru` if it is given.
+    pub fn create<F>(
+        builder: &OpBuilder<'ctx>,
+        loc: Location<'ctx>,
+        mask: Value<'ctx>,
+        passthru: Option<Value<'ctx>>,
+        result_types: &[Type<'ctx>],
+        body: F,
+    ) -> Self
+    where
+        F: FnOnce(&OpBuilder<'ctx>) -> Vec<Value<'ctx>>,
+    {
+        let mut operands = vec![mask];
+        operands.extend(passthru);
+        let op = create_region_op(builder, "vector.mask", loc, &operands, result_types, &[], 1);
+        let block = append_block(op.get_region(0), &[], loc);
+        let body_builder = OpBuilder::at_block_end(builder.get_context(), block);
+        let yielded = body(&body_builder);
+        YieldOp::create(&body_builder, loc, &yielded);
+        Self { op }
+    }
+    pub fn get_mask(&self) -> Value<'ctx> {
+        self.op.get_operand(0)
+    }
+    pub fn get_passthru(&self) -> Option<Value<'ctx>> {
+        self.op.operands().nth(1)
+    }
+    pub fn get_body(&self) -> BlockRef<'ctx> {
+        self.op.get_region(0).get_first_block()
+    }
+    /// Returns the masked operation, which is the first operation of the body.
+    pub fn get_masked_op(&self) -> OperationRef<'ctx> {
+        self.get_body().get_first_operation()
+    }
+    pub fn get_results(&self) -> Vec<Value<'ctx>> {
+        self.op.results().collect()
+    }
+}
+
+#[cfg(test)]
+mod vector_test {
+    use super::*;
+    use crate::affine_expr::*;
+    use crate::dialect::arith;
+    use crate::dialect::dialect_test::create_context_with;
+    use crate::dialect::func::*;
+    use crate::dialect::*;
+    use crate::module::*;
+    use crate::symbol_table::*;
+
+    use strum::IntoEnumIterator;
+
+    const DIALECTS: &[UpstreamDialectName] = &[
+        UpstreamDialectName::Arith,
+        UpstreamDialectName::Func,
+        UpstreamDialectName::MemRef,
+        UpstreamDialectName::Tensor,
+        UpstreamDialectName::Vector,
+    ];
+
+    #[test]
+    fn enum_attrs() {
+        let ctx = create_context_with(DIALECTS);
+        for kind in CombiningKind::iter() {
+            let attr = kind.get_attr(&ctx).unwrap();
+            assert_eq!(CombiningKind::from_attr(attr), Some(kind));
+            assert_eq!(kind.as_str().parse::<CombiningKind>(), Ok(kind));
+        }
+        for iterator_type in IteratorType::iter() {
+            let attr = get_iterator_type_attr(&ctx, iterator_type).unwrap();
+            assert_eq!(get_iterator_type_from_attr(attr), Some(iterator_type));
+        }
+    }
+
+    #[test]
+    fn transfer_and_mask() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let i1_ty: Type = IntegerType::get(&ctx, 1).into();
+        let memref_ty: Type =
+            MemRefType::contiguous_get(f32_ty, &[8, 16], Attr::create_null()).into();
+        let tensor_ty: Type = RankedTensorType::get(&[8, 16], f32_ty, Attr::create_null()).into();
+        let func_ty = FunctionType::get(&ctx, &[memref_ty, tensor_ty], &[tensor_ty]);
+        let func = FuncOp::create(&builder, loc, "copy", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let memref = func.get_argument(0).unwrap();
+        let tensor = func.get_argument(1).unwrap();
+        let c0 = arith::ConstantOp::create_index(&body, loc, 0).get_result();
+        let padding = arith::ConstantOp::create_float(&body, loc, 0.0, f32_ty).get_result();
+
+        let vector_ty = VectorType::get_scalable(&[4], &[true], f32_ty);
+        let mask_ty = VectorType::get_scalable(&[4], &[true], i1_ty);
+        let indices = [c0, c0];
+        assert!(TransferReadOp::create(
+            &body,
+            loc,
+            vector_ty,
+            memref,
+            &[c0],
+            padding,
+            None,
+            None,
+            &[]
+        )
+        .is_err());
+        let read_op = TransferReadOp::create(
+            &body,
+            loc,
+            vector_ty,
+            memref,
+            &indices,
+            padding,
+            None,
+            None,
+            &[],
+        )
+        .unwrap();
+        let read = read_op.get_result();
+        assert!(read_op.get_permutation_map() == AffineMap::minor_identity_get(&ctx, 2, 1));
+        assert_eq!(read_op.get_in_bounds(), vec![false]);
+        assert!(read_op.get_padding() == padding);
+        assert!(read_op.get_mask().is_none());
+
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let projection = AffineMap::get(&ctx, 2, 0, &[d0]);
+        let all_true = arith::ConstantOp::create(
+            &body,
+            loc,
+            Attr::parse(&ctx, "dense<true> : vector<[4]xi1>").unwrap(),
+        )
+        .get_result();
+        assert!(all_true.get_type() == mask_ty.into());
+        let masked = MaskOp::create(&body, loc, all_true, None, &[tensor_ty], |b| {
+            let write_op = TransferWriteOp::create(
+                b,
+                loc,
+                read,
+                tensor,
+                &indices,
+                Some(projection),
+                None,
+                &[true],
+            )
+            .unwrap();
+            vec![write_op.get_result().unwrap()]
+        });
+        assert!(masked.get_passthru().is_none());
+        let write_op = TransferWriteOp::from_op(masked.get_masked_op()).unwrap();
+        assert!(write_op.get_permutation_map() == projection);
+        assert_eq!(write_op.get_in_bounds(), vec![true]);
+        assert!(write_op.get_indices() == indices.to_vec());
+        assert!(TransferWriteOp::create(
+            &body,
+            loc,
+            read,
+            memref,
+            &indices,
+            None,
+            Some(all_true),
+            &[]
+        )
+        .unwrap()
+        .get_result()
+        .is_none());
+        assert!(
+            TransferWriteOp::create(&body, loc, read, memref, &indices, None, Some(read), &[])
+                .is_err()
+        );
+        let fixed_mask = arith::ConstantOp::create(
+            &body,
+            loc,
+            Attr::parse(&ctx, "dense<true> : vector<4xi1>").unwrap(),
+        )
+        .get_result();
+        assert!(TransferWriteOp::create(
+            &body,
+            loc,
+            read,
+            memref,
+            &indices,
+            None,
+            Some(fixed_mask),
+            &[]
+        )
+        .is_err());
+        // The mask of a transfer broadcasting a source dimension only covers the others.
+        let broadcast_map = AffineMap::get(&ctx, 2, 0, &[AffineExpr::const_expr_get(&ctx, 0), d0]);
+        let matrix_ty = VectorType::get(&[2, 4], f32_ty);
+        let row_mask = arith::ConstantOp::create(
+            &body,
+            loc,
+            Attr::parse(&ctx, "dense<true> : vector<4xi1>").unwrap(),
+        )
+        .get_result();
+        let matrix_mask = arith::ConstantOp::create(
+            &body,
+            loc,
+            Attr::parse(&ctx, "dense<true> : vector<2x4xi1>").unwrap(),
+        )
+        .get_result();
+        assert!(TransferReadOp::create(
+            &body,
+            loc,
+            matrix_ty,
+            memref,
+            &indices,
+            padding,
+            Some(broadcast_map),
+            Some(matrix_mask),
+            &[true, false]
+        )
+        .is_err());
+        assert!(TransferReadOp::create(
+            &body,
+            loc,
+            matrix_ty,
+            memref,
+            &indices,
+            padding,
+            Some(broadcast_map),
+            Some(row_mask),
+            &[true, false]
+        )
+        .is_ok());
+        ReturnOp::create(&body, loc, &masked.get_results());
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+
+    #[test]
+    fn broadcast_extract_insert() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let index_ty: Type = IndexType::get(&ctx).into();
+        let func_ty = FunctionType::get(&ctx, &[f32_ty, index_ty], &[]);
+        let func = FuncOp::create(&builder, loc, "shuffle", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let scalar = func.get_argument(0).unwrap();
+        let i = func.get_argument(1).unwrap();
+
+        let row_ty = VectorType::get_scalable(&[8], &[true], f32_ty);
+        let matrix_ty = VectorType::get_scalable(&[2, 8], &[false, true], f32_ty);
+        let row = BroadcastOp::create(&body, loc, scalar, row_ty)
+            .unwrap()
+            .get_result();
+        let broadcast_op = BroadcastOp::create(&body, loc, row, matrix_ty).unwrap();
+        let matrix = broadcast_op.get_result();
+        assert!(broadcast_op.get_source() == row);
+        let fixed_row_ty = VectorType::get(&[8], f32_ty);
+        assert!(BroadcastOp::create(&body, loc, row, VectorType::get(&[2, 8], f32_ty)).is_err());
+        assert!(BroadcastOp::create(&body, loc, scalar, fixed_row_ty).is_ok());
+
+        let first_row = ExtractOp::create(&body, loc, matrix, &[0.into()])
+            .unwrap()
+            .get_result();
+        assert!(first_row.get_type() == row_ty.into());
+        let extract_op = ExtractOp::create(&body, loc, matrix, &[1.into(), i.into()]).unwrap();
+        let element = extract_op.get_result();
+        assert!(element.get_type() == f32_ty);
+        let position = extract_op.get_mixed_position();
+        assert_eq!(position[0].get_static(), Some(1));
+        assert!(position[1] == OpFoldResult::Dynamic(i));
+        assert!(ExtractOp::create(&body, loc, matrix, &[2.into()]).is_err());
+
+        let insert_op =
+            InsertOp::create(&body, loc, scalar, matrix, &[0.into(), i.into()]).unwrap();
+        let updated = insert_op.get_result();
+        assert!(updated.get_type() == matrix_ty.into());
+        assert!(InsertOp::create(&body, loc, scalar, matrix, &[0.into()]).is_err());
+        assert!(insert_op.get_dest() == matrix);
+
+        let flat_ty = VectorType::get_scalable(&[16], &[true], f32_ty);
+        let flat = ShapeCastOp::create(&body, loc, updated, flat_ty)
+            .unwrap()
+            .get_result();
+        assert!(flat.get_type() == flat_ty.into());
+        assert!(ShapeCastOp::create(&body, loc, updated, VectorType::get(&[16], f32_ty)).is_err());
+        ReturnOp::create(&body, loc, &[]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+
+    #[test]
+    fn contract_reduction() {
+        let ctx = create_context_with(DIALECTS);
+        let loc = Location::unknown_get(&ctx);
+        let module = Module::create_empty(loc);
+        let builder = OpBuilder::at_block_end(&ctx, module.get_body());
+        let f32_ty: Type = FloatType::get(&ctx, FloatKind::F32).into();
+        let lhs_ty: Type = VectorType::get(&[4, 8], f32_ty).into();
+        let rhs_ty: Type = VectorType::get(&[8, 2], f32_ty).into();
+        let acc_ty: Type = VectorType::get(&[4, 2], f32_ty).into();
+        let func_ty = FunctionType::get(&ctx, &[lhs_ty, rhs_ty, acc_ty], &[acc_ty]);
+        let func = FuncOp::create(&builder, loc, "mm", func_ty, SymbolVisibility::Public);
+        let body = OpBuilder::at_block_end(&ctx, func.get_entry_block());
+        let lhs = func.get_argument(0).unwrap();
+        let rhs = func.get_argument(1).unwrap();
+        let acc = func.get_argument(2).unwrap();
+
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let d1 = AffineExpr::dim_expr_get(&ctx, 1);
+        let d2 = AffineExpr::dim_expr_get(&ctx, 2);
+        let maps = [
+            AffineMap::get(&ctx, 3, 0, &[d0, d2]),
+            AffineMap::get(&ctx, 3, 0, &[d2, d1]),
+            AffineMap::get(&ctx, 3, 0, &[d0, d1]),
+        ];
+        let iterator_types = [
+            IteratorType::Parallel,
+            IteratorType::Parallel,
+            IteratorType::Reduction,
+        ];
+        let contract_op = ContractionOp::create(
+            &body,
+            loc,
+            lhs,
+            rhs,
+            acc,
+            &maps,
+            &iterator_types,
+            CombiningKind::Add,
+        )
+        .unwrap();
+        let result = contract_op.get_result();
+        assert!(contract_op.get_indexing_maps() == maps.to_vec());
+        assert_eq!(
+            contract_op.get_iterator_types(),
+            Some(iterator_types.to_vec())
+        );
+        assert_eq!(contract_op.get_kind(), Some(CombiningKind::Add));
+        assert!(ContractionOp::create(
+            &body,
+            loc,
+            lhs,
+            rhs,
+            acc,
+            &maps,
+            &iterator_types[..2],
+            CombiningKind::Add,
+        )
+        .is_err());
+
+        let row = ExtractOp::create(&body, loc, result, &[0.into()])
+            .unwrap()
+            .get_result();
+        let sum = ReductionOp::create(&body, loc, CombiningKind::Add, row, None)
+            .unwrap()
+            .get_result();
+        assert!(sum.get_type() == f32_ty);
+        let reduction_op =
+            ReductionOp::create(&body, loc, CombiningKind::MaximumF, row, Some(sum)).unwrap();
+        assert_eq!(reduction_op.get_kind(), Some(CombiningKind::MaximumF));
+        assert!(reduction_op.get_acc() == Some(sum));
+        assert!(ReductionOp::create(&body, loc, CombiningKind::Add, result, None).is_err());
+        ReturnOp::create(&body, loc, &[result]);
+        assert!(module.get_operation_ref().verify().is_ok());
+    }
+}
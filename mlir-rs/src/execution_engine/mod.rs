@@ -0,0 +1,350 @@
+use crate::common::*;
+use crate::context::*;
+use crate::diagnostic::*;
+use crate::module::*;
+use crate::strided_memref::*;
+use crate::support::*;
+
+use mlir_capi::ExecutionEngine as MLIR_ExecutionEngine;
+use mlir_capi::ExecutionEngine::MlirExecutionEngine;
+
+use std::ffi::c_void;
+use std::marker::PhantomData;
+
+/// A JIT compiler for modules that have been lowered to the LLVM dialect.
+///
+/// The dialects used by the module must have their LLVM IR translations registered, see
+/// `register_all_llvm_translations`.
+pub struct ExecutionEngine<'ctx> {
+    pub handle: MlirExecutionEngine,
+    phantom: PhantomData<&'ctx Context>,
+}
+
+impl<'ctx> Into<MlirExecutionEngine> for &ExecutionEngine<'ctx> {
+    fn into(self) -> MlirExecutionEngine {
+        self.handle
+    }
+}
+
+impl<'ctx> Drop for ExecutionEngine<'ctx> {
+    fn drop(&mut self) {
+        unsafe {
+            MLIR_ExecutionEngine::FFIVoid_::mlirExecutionEngineDestroy(self as &_);
+        }
+    }
+}
+
+impl<'ctx> ExecutionEngine<'ctx> {
+    /// JIT-compiles `module` at the given LLVM optimization level (0 to 3). The libraries in
+    /// `shared_libs` are loaded so that the compiled code can call into them.
+    pub fn create(
+        module: Module<'ctx>,
+        opt_level: i32,
+        shared_libs: &[&str],
+        enable_object_dump: bool,
+    ) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new_for(&module);
+        let paths: Vec<StrRef> = shared_libs.iter().map(|s| StrRef::from(*s)).collect();
+        let handle: MlirExecutionEngine = unsafe {
+            MLIR_ExecutionEngine::FFIVal_::mlirExecutionEngineCreate(
+                module,
+                opt_level,
+                paths.len() as i32,
+                paths.as_ptr() as *const _,
+                to_cbool(enable_object_dump),
+            )
+        };
+        let engine = Self {
+            handle,
+            phantom: PhantomData::default(),
+        };
+        if engine.is_null() {
+            std::mem::forget(engine);
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(engine)
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        is_null(self.handle.ptr)
+    }
+    /// Returns the address of the function `name`, compiled with its original signature.
+    pub fn lookup(&self, name: &str) -> Option<*mut c_void> {
+        let ptr: *mut c_void = unsafe {
+            MLIR_ExecutionEngine::FFIVal_::mlirExecutionEngineLookup(self, StrRef::from(name))
+        };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(ptr)
+        }
+    }
+    /// Returns the address of the packed wrapper of the function `name`, which takes a single
+    /// `*mut *mut c_void` argument, see `invoke_packed`.
+    pub fn lookup_packed(&self, name: &str) -> Option<*mut c_void> {
+        let ptr: *mut c_void = unsafe {
+            MLIR_ExecutionEngine::FFIVal_::mlirExecutionEngineLookupPacked(self, StrRef::from(name))
+        };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(ptr)
+        }
+    }
+    /// Calls the function `name` through its packed wrapper. `args` holds a pointer to each
+    /// argument, followed by a pointer to the storage of each result. Fails if there is no
+    /// such function.
+    ///
+    /// # Safety
+    /// The pointers in `args` must point to values of the types expected by the function.
+    pub unsafe fn invoke_packed(&self, name: &str, args: &mut [*mut c_void]) -> LogicalResult {
+        MLIR_ExecutionEngine::FFIVal_::mlirExecutionEngineInvokePacked(
+            self,
+            StrRef::from(name),
+            args.as_mut_ptr() as *mut _,
+        )
+    }
+    /// Makes `sym` available to the compiled code under the name `name`.
+    ///
+    /// # Safety
+    /// `sym` must stay valid for as long as the compiled code may use it, and have the type
+    /// the compiled code expects.
+    pub unsafe fn register_symbol(&self, name: &str, sym: *mut c_void) {
+        MLIR_ExecutionEngine::FFIVoid_::mlirExecutionEngineRegisterSymbol(
+            self,
+            StrRef::from(name),
+            sym,
+        );
+    }
+    /// Writes the compiled object to `file_name`, requires the engine to have been created
+    /// with `enable_object_dump`.
+    pub fn dump_to_object_file(&self, file_name: &str) {
+        unsafe {
+            MLIR_ExecutionEngine::FFIVoid_::mlirExecutionEngineDumpToObjectFile(
+                self,
+                StrRef::from(file_name),
+            );
+        }
+    }
+
+    /// Calls the function `name`, which must have the `llvm.emit_c_interface` attribute, with
+    /// scalar arguments and a single scalar result. Memref arguments are passed as a
+    /// `&mut StridedMemRefDescriptor`.
+    ///
+    /// # Safety
+    /// The function must take arguments of the types in `A`, in order, and return an `R`. The
+    /// C interface wrapper is looked up by name only, its signature is not checked.
+    pub unsafe fn invoke<A: JitArgs, R: JitScalar + Default>(
+        &self,
+        name: &str,
+        mut args: A,
+    ) -> Result<R, String> {
+        let mut result = R::default();
+        let mut ptrs = args.get_pointers();
+        ptrs.push(&mut result as *mut R as *mut c_void);
+        self.invoke_c_interface(name, &mut ptrs)?;
+        Ok(result)
+    }
+    /// Like `invoke`, for functions without results.
+    ///
+    /// # Safety
+    /// The function must take arguments of the types in `A`, in order, and return nothing.
+    pub unsafe fn invoke_void<A: JitArgs>(&self, name: &str, mut args: A) -> Result<(), String> {
+        let mut ptrs = args.get_pointers();
+        self.invoke_c_interface(name, &mut ptrs)
+    }
+    fn invoke_c_interface(&self, name: &str, ptrs: &mut [*mut c_void]) -> Result<(), String> {
+        let res = unsafe { self.invoke_packed(&format!("_mlir_ciface_{}", name), ptrs) };
+        if res.is_success() {
+            Ok(())
+        } else {
+            Err(format!("no function named '{}' with a C interface", name))
+        }
+    }
+}
+
+/// Scalar types that can be passed to and returned from JIT-compiled functions.
+///
+/// # Safety
+/// The type must have the same layout as the corresponding LLVM type: `iN`/`uN` for the
+/// integer types of the same width, `isize`/`usize` for `index`, `f32`/`f64` for the float
+/// types and `bool` for `i1`.
+pub unsafe trait JitScalar: Copy {}
+
+unsafe impl JitScalar for bool {}
+unsafe impl JitScalar for i8 {}
+unsafe impl JitScalar for i16 {}
+unsafe impl JitScalar for i32 {}
+unsafe impl JitScalar for i64 {}
+unsafe impl JitScalar for isize {}
+unsafe impl JitScalar for u8 {}
+unsafe impl JitScalar for u16 {}
+unsafe impl JitScalar for u32 {}
+unsafe impl JitScalar for u64 {}
+unsafe impl JitScalar for usize {}
+unsafe impl JitScalar for f32 {}
+unsafe impl JitScalar for f64 {}
+
+/// An argument of a JIT-compiled function.
+pub trait JitArg {
+    /// Returns a pointer to the argument, as expected by `ExecutionEngine::invoke_packed`.
+    fn get_pointer(&mut self) -> *mut c_void;
+}
+
+impl<T: JitScalar> JitArg for T {
+    fn get_pointer(&mut self) -> *mut c_void {
+        self as *mut T as *mut c_void
+    }
+}
+
+/// Memrefs are passed as a pointer to their descriptor.
+impl<'a, 'b, T, const N: usize> JitArg for &'b mut StridedMemRefDescriptor<'a, T, N> {
+    fn get_pointer(&mut self) -> *mut c_void {
+        self as *mut &'b mut StridedMemRefDescriptor<'a, T, N> as *mut c_void
+    }
+}
+
+impl<'a, 'b> JitArg for &'b mut UnrankedMemRefDescriptor<'a> {
+    fn get_pointer(&mut self) -> *mut c_void {
+        self as *mut &'b mut UnrankedMemRefDescriptor<'a> as *mut c_void
+    }
+}
+
+/// A tuple of `JitArg` arguments.
+pub trait JitArgs {
+    /// Returns a pointer to each argument, as expected by `ExecutionEngine::invoke_packed`.
+    fn get_pointers(&mut self) -> Vec<*mut c_void>;
+}
+
+macro_rules! impl_jit_args {
+    ($($ty:ident: $idx:tt),*) => {
+        impl<$($ty: JitArg),*> JitArgs for ($($ty,)*) {
+            fn get_pointers(&mut self) -> Vec<*mut c_void> {
+                vec![$(self.$idx.get_pointer()),*]
+            }
+        }
+    };
+}
+
+impl_jit_args!();
+impl_jit_args!(A: 0);
+impl_jit_args!(A: 0, B: 1);
+impl_jit_args!(A: 0, B: 1, C: 2);
+impl_jit_args!(A: 0, B: 1, C: 2, D: 3);
+impl_jit_args!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_jit_args!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_jit_args!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_jit_args!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+#[cfg(test)]
+mod execution_engine_test {
+    use super::*;
+    use crate::context::context_test::create_context_with_all_upstream_dialects;
+    use crate::dialect::*;
+    use crate::pass::*;
+    use crate::pass_manager::*;
+
+    const ADD_MODULE: &str = r#"
+module {
+  func.func @add(%a: i32, %b: i32) -> i32 attributes { llvm.emit_c_interface } {
+    %0 = arith.addi %a, %b : i32
+    return %0 : i32
+  }
+  func.func @scale(%a: f64) -> f64 attributes { llvm.emit_c_interface } {
+    %c = arith.constant 2.5 : f64
+    %0 = arith.mulf %a, %c : f64
+    return %0 : f64
+  }
+  func.func @store(%p: i64) attributes { llvm.emit_c_interface } {
+    %ptr = llvm.inttoptr %p : i64 to !llvm.ptr
+    %c = arith.constant 42 : i32
+    llvm.store %c, %ptr : i32, !llvm.ptr
+    return
+  }
+}
+"#;
+
+    fn lower_to_llvm<'ctx>(ctx: &'ctx Context, source: &str) -> Module<'ctx> {
+        register_all_passes();
+        register_all_llvm_translations(ctx);
+        let module = Module::create_parse(ctx, source).unwrap();
+        let pm = PassManager::create(ctx);
+        pm.parse_pass_pipeline(
+            "builtin.module(convert-arith-to-llvm,convert-func-to-llvm,reconcile-unrealized-casts)",
+        )
+        .unwrap();
+        assert!(pm.run_on_module(module).is_success());
+        module
+    }
+
+    #[test]
+    fn invoke() {
+        let ctx = create_context_with_all_upstream_dialects();
+        let module = lower_to_llvm(&ctx, ADD_MODULE);
+        let engine = ExecutionEngine::create(module, 2, &[], false).unwrap();
+        assert!(engine.lookup("add").is_some());
+        assert!(engine.lookup_packed("add").is_some());
+        assert!(engine.lookup("foo").is_none());
+
+        unsafe {
+            let sum: i32 = engine.invoke("add", (3i32, 4i32)).unwrap();
+            assert_eq!(sum, 7);
+            let scaled: f64 = engine.invoke("scale", (2.0f64,)).unwrap();
+            assert_eq!(scaled, 5.0);
+            let mut out = 0i32;
+            engine
+                .invoke_void("store", (&mut out as *mut i32 as i64,))
+                .unwrap();
+            assert_eq!(out, 42);
+            assert!(engine.invoke::<_, i32>("foo", ()).is_err());
+        }
+
+        let (mut a, mut b, mut res) = (10i32, -3i32, 0i32);
+        let mut args = [
+            &mut a as *mut i32 as *mut c_void,
+            &mut b as *mut i32 as *mut c_void,
+            &mut res as *mut i32 as *mut c_void,
+        ];
+        assert!(unsafe { engine.invoke_packed("add", &mut args) }.is_success());
+        assert_eq!(res, 7);
+    }
+
+    extern "C" fn host_double(x: i64) -> i64 {
+        x * 2
+    }
+
+    #[test]
+    fn register_symbol() {
+        let ctx = create_context_with_all_upstream_dialects();
+        let module = lower_to_llvm(
+            &ctx,
+            r#"
+module {
+  func.func private @host_double(i64) -> i64
+  func.func @call_host(%a: i64) -> i64 attributes { llvm.emit_c_interface } {
+    %0 = func.call @host_double(%a) : (i64) -> i64
+    return %0 : i64
+  }
+}
+"#,
+        );
+        let engine = ExecutionEngine::create(module, 0, &[], false).unwrap();
+        unsafe {
+            engine.register_symbol("host_double", host_double as *mut c_void);
+        }
+        let res: i64 = unsafe { engine.invoke("call_host", (21i64,)) }.unwrap();
+        assert_eq!(res, 42);
+    }
+
+    #[test]
+    fn create_error() {
+        let ctx = create_context_with_all_upstream_dialects();
+        register_all_llvm_translations(&ctx);
+        // Not lowered to the LLVM dialect, so translation fails.
+        let module = Module::create_parse(&ctx, ADD_MODULE).unwrap();
+        let diags = ExecutionEngine::create(module, 0, &[], false)
+            .err()
+            .unwrap();
+        assert!(!diags.is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::affine_expr::tree::*;
 use crate::affine_expr::*;
 use crate::common::*;
 use crate::context::*;
@@ -8,6 +9,7 @@ use mlir_capi::IntegerSet as MLIR_IntegerSet;
 use mlir_capi::IntegerSet::*;
 use mlir_capi::IR::*;
 
+use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
 #[repr(C)]
@@ -57,6 +59,18 @@ impl<'ctx> PartialEq for IntegerSet<'ctx> {
 }
 impl<'ctx> Eq for IntegerSet<'ctx> {}
 
+impl<'ctx> Debug for IntegerSet<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.print_to_formatter(formatter)
+    }
+}
+
+impl<'ctx> Display for IntegerSet<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.print_to_formatter(formatter)
+    }
+}
+
 impl<'ctx> IntegerSet<'ctx> {
     pub fn print(self, callback: &mut dyn PrintCallback) {
         unsafe {
@@ -67,11 +81,24 @@ impl<'ctx> IntegerSet<'ctx> {
             );
         }
     }
+    fn print_to_formatter(
+        self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> Result<(), std::fmt::Error> {
+        let mut printer = PrintToFormatter {
+            formatter: formatter,
+        };
+        self.print(&mut printer);
+        Ok(())
+    }
     pub fn dump(self) {
         unsafe {
             MLIR_IntegerSet::FFIVoid_::mlirIntegerSetDump(self);
         }
     }
+    pub fn get_context(self) -> ContextRef<'ctx> {
+        ContextRef::from_handle_same_context(self.get_context_handle(), &self)
+    }
     pub fn empty_get(ctx: &'ctx Context, num_dims: usize, num_symbols: usize) -> Self {
         let handle = unsafe {
             MLIR_IntegerSet::FFIVal_::mlirIntegerSetEmptyGet(
@@ -160,3 +187,453 @@ impl<'ctx> IntegerSet<'ctx> {
         })
     }
 }
+
+impl<'ctx> IntegerSet<'ctx> {
+    /// Returns whether the point satisfies all the constraints, or `None` if the number of values
+    /// does not match the set, or if a constraint cannot be evaluated.
+    pub fn contains(self, dims: &[i64], symbols: &[i64]) -> Option<bool> {
+        if dims.len() != self.get_num_dims() || symbols.len() != self.get_num_symbols() {
+            return None;
+        }
+        let mut contained = true;
+        for pos in 0..self.get_num_constaints() {
+            let value = AffineExprTree::from(self.get_constraint(pos)).evaluate(dims, symbols)?;
+            contained &= if self.is_constraint_equality(pos) {
+                value == 0
+            } else {
+                value >= 0
+            };
+        }
+        Some(contained)
+    }
+    fn get_linear_system(self) -> LinearSystem {
+        let num_dims = self.get_num_dims();
+        let num_symbols = self.get_num_symbols();
+        let mut system = LinearSystem::default();
+        for pos in 0..self.get_num_constaints() {
+            let tree = AffineExprTree::from(self.get_constraint(pos));
+            // Skipping a non-linear constraint only loosens the system.
+            if let Some(form) = tree.get_linear_form() {
+                let constraint = LinearConstraint::from_linear_form(&form, num_dims, num_symbols);
+                system.add(constraint, self.is_constraint_equality(pos));
+            }
+        }
+        system
+    }
+    /// Tries to prove that the set has no integer point, by Fourier-Motzkin elimination of the
+    /// linear constraints with gcd tightening. Symbols are eliminated like dims, so `true` means
+    /// the set is empty for all values of the symbols. `false` means that emptiness could not be
+    /// proven: non-linear constraints are ignored and the elimination is not exact in general.
+    pub fn is_provably_empty(self) -> bool {
+        // Give up before the elimination blows up.
+        const MAX_INEQUALITIES: usize = 1024;
+        let mut system = self.get_linear_system();
+        let mut vars: Vec<usize> = (0..self.get_num_inputs()).collect();
+        while !system.infeasible && !vars.is_empty() {
+            let (idx, cost) = vars
+                .iter()
+                .enumerate()
+                .map(|(idx, var)| (idx, system.get_elimination_cost(*var)))
+                .min_by_key(|(_, cost)| *cost)
+                .unwrap();
+            // The elimination adds up to `cost` inequalities.
+            if system.inequalities.len() + cost > MAX_INEQUALITIES {
+                return false;
+            }
+            system.eliminate(vars.swap_remove(idx));
+        }
+        system.infeasible
+    }
+    /// Eliminates the dim at `pos` by Fourier-Motzkin elimination, the dims after it are shifted
+    /// down by one. The result over-approximates the integer projection: it is exact only if the
+    /// dim has unit coefficients, and non-linear constraints involving the dim are dropped.
+    /// Returns `None` if `pos` is out of range.
+    pub fn project_out_dim(self, pos: usize) -> Option<Self> {
+        let num_dims = self.get_num_dims();
+        let num_symbols = self.get_num_symbols();
+        if pos >= num_dims {
+            return None;
+        }
+        let mut system = self.get_linear_system();
+        system.eliminate(pos);
+        let ctx = self.get_context();
+        if system.infeasible {
+            let set = IntegerSet::empty_get(&ctx, num_dims - 1, num_symbols);
+            return Some(IntegerSet::from_handle_same_context(set.handle, &self));
+        }
+        let to_expr = |constraint: &LinearConstraint| {
+            Some(constraint.to_tree(num_dims, pos)?.to_affine_expr(&ctx))
+        };
+        let mut equalities: Vec<_> = system.equalities.iter().filter_map(to_expr).collect();
+        let mut inequalities: Vec<_> = system.inequalities.iter().filter_map(to_expr).collect();
+        let shifted_dims: Vec<_> = (0..num_dims)
+            .map(|dim| AffineExprTree::Dim(if dim > pos { dim - 1 } else { dim }))
+            .collect();
+        for idx in 0..self.get_num_constaints() {
+            let tree = AffineExprTree::from(self.get_constraint(idx));
+            if tree.get_linear_form().is_some() || tree.is_func_of_dim(pos) {
+                continue;
+            }
+            let expr = tree.substitute(&shifted_dims, &[]).to_affine_expr(&ctx);
+            if self.is_constraint_equality(idx) {
+                equalities.push(expr);
+            } else {
+                inequalities.push(expr);
+            }
+        }
+        if equalities.is_empty() && inequalities.is_empty() {
+            // An integer set needs at least one constraint.
+            inequalities.push(AffineExpr::const_expr_get(&ctx, 0));
+        }
+        let set = IntegerSet::get(&ctx, num_dims - 1, num_symbols, &equalities, &inequalities);
+        Some(IntegerSet::from_handle_same_context(set.handle, &self))
+    }
+}
+
+/// Fluent builder of an `IntegerSet` from `expr >= 0` and `expr == 0` constraints, e.g.
+/// `IntegerSetBuilder::new(&ctx, 1, 1).range(d0, 0, s0).build()` for `0 <= d0 < s0`.
+pub struct IntegerSetBuilder<'ctx> {
+    ctx: &'ctx Context,
+    num_dims: usize,
+    num_symbols: usize,
+    equalities: Vec<AffineExpr<'ctx>>,
+    inequalities: Vec<AffineExpr<'ctx>>,
+}
+
+impl<'ctx> IntegerSetBuilder<'ctx> {
+    pub fn new(ctx: &'ctx Context, num_dims: usize, num_symbols: usize) -> Self {
+        Self {
+            ctx,
+            num_dims,
+            num_symbols,
+            equalities: vec![],
+            inequalities: vec![],
+        }
+    }
+    pub fn dim(&self, pos: usize) -> AffineExpr<'ctx> {
+        assert!(pos < self.num_dims, "dim position out of range");
+        AffineExpr::dim_expr_get(self.ctx, pos)
+    }
+    pub fn symbol(&self, pos: usize) -> AffineExpr<'ctx> {
+        assert!(pos < self.num_symbols, "symbol position out of range");
+        AffineExpr::symbol_expr_get(self.ctx, pos)
+    }
+    /// Adds `expr >= 0`.
+    pub fn inequality(mut self, expr: AffineExpr<'ctx>) -> Self {
+        self.inequalities.push(expr);
+        self
+    }
+    /// Adds `expr == 0`.
+    pub fn equality(mut self, expr: AffineExpr<'ctx>) -> Self {
+        self.equalities.push(expr);
+        self
+    }
+    /// Adds `lhs >= rhs`.
+    pub fn ge<T: AffineExprOperand<'ctx>>(self, lhs: AffineExpr<'ctx>, rhs: T) -> Self {
+        self.inequality(lhs - rhs)
+    }
+    /// Adds `lhs <= rhs`.
+    pub fn le<T: AffineExprOperand<'ctx>>(self, lhs: AffineExpr<'ctx>, rhs: T) -> Self {
+        self.inequality(-lhs + rhs)
+    }
+    /// Adds `lhs == rhs`.
+    pub fn equal<T: AffineExprOperand<'ctx>>(self, lhs: AffineExpr<'ctx>, rhs: T) -> Self {
+        self.equality(lhs - rhs)
+    }
+    /// Adds `lb <= expr < ub`, the usual bounds of an iteration domain.
+    pub fn range<L, U>(self, expr: AffineExpr<'ctx>, lb: L, ub: U) -> Self
+    where
+        L: AffineExprOperand<'ctx>,
+        U: AffineExprOperand<'ctx>,
+    {
+        self.ge(expr, lb).le(expr, ub.to_affine_expr(expr) - 1)
+    }
+    /// Builds the set, a builder without constraints builds the universe set `0 >= 0`.
+    pub fn build(mut self) -> IntegerSet<'ctx> {
+        if self.equalities.is_empty() && self.inequalities.is_empty() {
+            // An integer set needs at least one constraint.
+            self.inequalities
+                .push(AffineExpr::const_expr_get(self.ctx, 0));
+        }
+        IntegerSet::get(
+            self.ctx,
+            self.num_dims,
+            self.num_symbols,
+            &self.equalities,
+            &self.inequalities,
+        )
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `coeffs . (dims, symbols) + constant`, in i128 to leave headroom for the products of the
+/// elimination.
+#[derive(Clone, PartialEq, Eq)]
+struct LinearConstraint {
+    coeffs: Vec<i128>,
+    constant: i128,
+}
+
+impl LinearConstraint {
+    fn from_linear_form(form: &AffineLinearForm, num_dims: usize, num_symbols: usize) -> Self {
+        let dims = (0..num_dims).map(|pos| form.get_dim_coeff(pos));
+        let symbols = (0..num_symbols).map(|pos| form.get_symbol_coeff(pos));
+        Self {
+            coeffs: dims.chain(symbols).map(i128::from).collect(),
+            constant: form.constant.into(),
+        }
+    }
+    /// Converts back to an expression without the (eliminated) dim at `removed_dim`, `None` if a
+    /// coefficient does not fit in an i64.
+    fn to_tree(&self, num_dims: usize, removed_dim: usize) -> Option<AffineExprTree> {
+        let to_i64 = |c: &i128| i64::try_from(*c).ok();
+        let dims = self.coeffs[..num_dims]
+            .iter()
+            .enumerate()
+            .filter(|(pos, _)| *pos != removed_dim)
+            .map(|(_, c)| to_i64(c))
+            .collect::<Option<Vec<_>>>()?;
+        let symbols = self.coeffs[num_dims..]
+            .iter()
+            .map(to_i64)
+            .collect::<Option<Vec<_>>>()?;
+        let form = AffineLinearForm {
+            dims,
+            symbols,
+            constant: to_i64(&self.constant)?,
+        };
+        Some(form.to_tree())
+    }
+    /// `self * self_factor + other * other_factor`, `None` on overflow.
+    fn combine(&self, self_factor: i128, other: &Self, other_factor: i128) -> Option<Self> {
+        let combine = |a: i128, b: i128| {
+            a.checked_mul(self_factor)?
+                .checked_add(b.checked_mul(other_factor)?)
+        };
+        let coeffs = self
+            .coeffs
+            .iter()
+            .zip(other.coeffs.iter())
+            .map(|(a, b)| combine(*a, *b))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            coeffs,
+            constant: combine(self.constant, other.constant)?,
+        })
+    }
+}
+
+/// Conjunction of `== 0` and `>= 0` linear constraints. Constraints which overflow are dropped,
+/// which only loosens the system.
+#[derive(Default)]
+struct LinearSystem {
+    equalities: Vec<LinearConstraint>,
+    inequalities: Vec<LinearConstraint>,
+    infeasible: bool,
+}
+
+impl LinearSystem {
+    /// Adds a constraint divided by the gcd of its coefficients. For an inequality the constant is
+    /// rounded down, which tightens it without losing integer points.
+    fn add(&mut self, constraint: LinearConstraint, is_equality: bool) {
+        if self.infeasible {
+            return;
+        }
+        let divisor = constraint
+            .coeffs
+            .iter()
+            .fold(0, |g, c| gcd(g, c.unsigned_abs()));
+        if divisor == 0 {
+            self.infeasible = if is_equality {
+                constraint.constant != 0
+            } else {
+                constraint.constant < 0
+            };
+            return;
+        }
+        let Ok(divisor) = i128::try_from(divisor) else {
+            return;
+        };
+        if is_equality && constraint.constant % divisor != 0 {
+            self.infeasible = true;
+            return;
+        }
+        let constraint = LinearConstraint {
+            coeffs: constraint.coeffs.iter().map(|c| c / divisor).collect(),
+            constant: constraint.constant.div_euclid(divisor),
+        };
+        let constraints = if is_equality {
+            &mut self.equalities
+        } else {
+            &mut self.inequalities
+        };
+        if !constraints.contains(&constraint) {
+            constraints.push(constraint);
+        }
+    }
+    fn get_elimination_cost(&self, var: usize) -> usize {
+        if self.equalities.iter().any(|c| c.coeffs[var] != 0) {
+            return 0;
+        }
+        let lower = self
+            .inequalities
+            .iter()
+            .filter(|c| c.coeffs[var] > 0)
+            .count();
+        let upper = self
+            .inequalities
+            .iter()
+            .filter(|c| c.coeffs[var] < 0)
+            .count();
+        lower * upper
+    }
+    fn eliminate(&mut self, var: usize) {
+        let equalities = std::mem::take(&mut self.equalities);
+        let inequalities = std::mem::take(&mut self.inequalities);
+        let pivot = equalities
+            .iter()
+            .filter(|c| c.coeffs[var] != 0)
+            .min_by_key(|c| c.coeffs[var].unsigned_abs())
+            .cloned();
+        if let Some(pivot) = pivot {
+            // Substitute the var using the equality with the smallest coefficient. Inequalities
+            // are scaled by a positive factor to keep their direction.
+            let a = pivot.coeffs[var];
+            for c in equalities.into_iter().filter(|c| *c != pivot) {
+                let b = c.coeffs[var];
+                if b == 0 {
+                    self.add(c, true);
+                } else if let Some(c) = c.combine(a, &pivot, -b) {
+                    self.add(c, true);
+                }
+            }
+            for c in inequalities {
+                let b = c.coeffs[var];
+                if b == 0 {
+                    self.add(c, false);
+                } else if let Some(c) = c.combine(a.abs(), &pivot, -a.signum() * b) {
+                    self.add(c, false);
+                }
+            }
+            return;
+        }
+        for c in equalities {
+            self.add(c, true);
+        }
+        let (mut lower, mut upper) = (vec![], vec![]);
+        for c in inequalities {
+            match c.coeffs[var].cmp(&0) {
+                std::cmp::Ordering::Greater => lower.push(c),
+                std::cmp::Ordering::Less => upper.push(c),
+                std::cmp::Ordering::Equal => self.add(c, false),
+            }
+        }
+        for l in &lower {
+            for u in &upper {
+                if let Some(c) = l.combine(-u.coeffs[var], u, l.coeffs[var]) {
+                    self.add(c, false);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod integer_set_test {
+    use super::*;
+
+    #[test]
+    fn builder_and_contains() {
+        let ctx = Context::create();
+        let builder = IntegerSetBuilder::new(&ctx, 2, 1);
+        let (d0, d1, s0) = (builder.dim(0), builder.dim(1), builder.symbol(0));
+        let set = builder.range(d0, 0, s0).equal(d1, d0 * 2).build();
+        assert_eq!(set.get_num_equalities(), 1);
+        assert_eq!(set.get_num_inequalities(), 2);
+        assert_eq!(set.contains(&[1, 2], &[4]), Some(true));
+        assert_eq!(set.contains(&[0, 0], &[4]), Some(true));
+        assert_eq!(set.contains(&[1, 3], &[4]), Some(false));
+        assert_eq!(set.contains(&[4, 8], &[4]), Some(false));
+        assert_eq!(set.contains(&[-1, -2], &[4]), Some(false));
+        assert_eq!(set.contains(&[1, 2], &[]), None);
+        let universe = IntegerSetBuilder::new(&ctx, 1, 0).build();
+        assert_eq!(universe.contains(&[42], &[]), Some(true));
+        let builder = IntegerSetBuilder::new(&ctx, 1, 1);
+        let (d0, s0) = (builder.dim(0), builder.symbol(0));
+        let divisible = builder.equal(d0.rem(s0), 0).build();
+        assert_eq!(divisible.contains(&[6], &[3]), Some(true));
+        assert_eq!(divisible.contains(&[6], &[0]), None);
+    }
+
+    #[test]
+    fn emptiness() {
+        let ctx = Context::create();
+        let d0 = AffineExpr::dim_expr_get(&ctx, 0);
+        let d1 = AffineExpr::dim_expr_get(&ctx, 1);
+        let s0 = AffineExpr::symbol_expr_get(&ctx, 0);
+        assert!(IntegerSet::empty_get(&ctx, 2, 1).is_provably_empty());
+        let builder = || IntegerSetBuilder::new(&ctx, 2, 1);
+        assert!(builder().ge(d0, 0).le(d0, -1).build().is_provably_empty());
+        // Only empty over the integers, the rational solution is d0 = 1/2.
+        assert!(builder().equal(d0 * 2, 1).build().is_provably_empty());
+        assert!(builder()
+            .ge(d0 * 2, 1)
+            .le(d0 * 2, 1)
+            .build()
+            .is_provably_empty());
+        assert!(builder()
+            .ge(d0, s0)
+            .le(d0, s0 - 1)
+            .build()
+            .is_provably_empty());
+        assert!(builder()
+            .equal(d0, d1 + s0)
+            .range(d1, 0, 4)
+            .range(s0, 0, 4)
+            .ge(d0, 7)
+            .build()
+            .is_provably_empty());
+        let triangle = builder().ge(d0, 0).ge(d1, 0).le(d0 + d1, 10).build();
+        assert!(!triangle.is_provably_empty());
+        assert!(!builder()
+            .equal(d0, d1 + s0)
+            .ge(d0, 6)
+            .build()
+            .is_provably_empty());
+    }
+
+    #[test]
+    fn projection() {
+        let ctx = Context::create();
+        let builder = IntegerSetBuilder::new(&ctx, 2, 0);
+        let (d0, d1) = (builder.dim(0), builder.dim(1));
+        let set = builder.ge(d0, 0).ge(d1, d0).le(d1, 10).build();
+        let projected = set.project_out_dim(0).unwrap();
+        assert_eq!(projected.get_num_dims(), 1);
+        assert!(set.project_out_dim(2).is_none());
+        assert_eq!(projected.contains(&[0], &[]), Some(true));
+        assert_eq!(projected.contains(&[10], &[]), Some(true));
+        assert_eq!(projected.contains(&[11], &[]), Some(false));
+        assert_eq!(projected.contains(&[-1], &[]), Some(false));
+
+        let builder = IntegerSetBuilder::new(&ctx, 2, 0);
+        let set = builder.equal(d1, d0 * 2 + 1).range(d0, 0, 4).build();
+        let projected = set.project_out_dim(0).unwrap();
+        assert_eq!(projected.contains(&[1], &[]), Some(true));
+        assert_eq!(projected.contains(&[7], &[]), Some(true));
+        assert_eq!(projected.contains(&[8], &[]), Some(false));
+        assert_eq!(projected.contains(&[0], &[]), Some(false));
+
+        let set = IntegerSetBuilder::new(&ctx, 2, 0)
+            .ge(d0, 1)
+            .le(d0, 0)
+            .build();
+        assert!(set.project_out_dim(0).unwrap().is_canonical_empty());
+    }
+}
@@ -1,5 +1,10 @@
 #![feature(ptr_as_ref_unchecked)]
 
+// Lets `affine_map!` refer to this crate as `::mlir_rs` from inside it as well.
+extern crate self as mlir_rs;
+
+pub use mlir_impl_macros::affine_map;
+
 pub mod affine_expr;
 pub mod affine_map;
 pub mod asm_state;
@@ -8,16 +13,22 @@ pub mod block;
 pub mod builder;
 pub mod common;
 pub mod context;
+pub mod diagnostic;
 pub mod dialect;
+pub mod execution_engine;
 pub mod integer_set;
 pub mod location;
 pub mod module;
 pub mod op_printing_flags;
 pub mod operation;
 pub mod operation_state;
+pub mod pass;
+pub mod pass_manager;
 pub mod region;
+pub mod strided_memref;
 pub mod support;
 pub mod symbol_table;
+pub mod translation;
 pub mod r#type;
 pub mod value;
 
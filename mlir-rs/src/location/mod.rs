@@ -6,6 +6,8 @@ use crate::support::*;
 use mlir_capi::IR;
 use mlir_capi::IR::*;
 use std::convert::Into;
+use std::ffi::CString;
+use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
 #[repr(C)]
@@ -73,6 +75,29 @@ impl<'ctx> Location<'ctx> {
         let handle = unsafe { IR::FFIVal_::mlirLocationUnknownGet(ctx) };
         unsafe { Self::from_handle_and_phantom(handle, PhantomData::default()) }
     }
+    pub fn emit_error(self, message: &str) {
+        let message = CString::new(message).unwrap();
+        unsafe {
+            mlir_capi::Diagnostics::FFIVoid_::mlirEmitError(self, message.as_ptr());
+        }
+    }
+    pub fn print(self, callback: &mut dyn PrintCallback) {
+        unsafe {
+            IR::FFIVoid_::mlirLocationPrint(
+                self,
+                print_helper as *mut _,
+                &callback as *const &mut dyn PrintCallback as *mut _,
+            );
+        }
+    }
+    pub fn print_to_formatter(
+        self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> Result<(), std::fmt::Error> {
+        let mut printer = PrintToFormatter::new(formatter);
+        self.print(&mut printer);
+        Ok(())
+    }
     // FIXME
     //pub fn get_context(self) -> Context {
     //    unsafe { IR::FFIVal_::mlirLocationGetContext(&self) }
@@ -85,3 +110,15 @@ impl<'ctx> PartialEq for Location<'ctx> {
     }
 }
 impl<'ctx> Eq for Location<'ctx> {}
+
+impl<'ctx> Debug for Location<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.print_to_formatter(formatter)
+    }
+}
+
+impl<'ctx> Display for Location<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.print_to_formatter(formatter)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::block::*;
 use crate::context::*;
+use crate::diagnostic::*;
 use crate::location::*;
 use crate::operation::*;
 use crate::support::*;
@@ -46,9 +47,15 @@ impl<'ctx> Module<'ctx> {
     pub fn create_empty(loc: Location) -> Self {
         unsafe { mlir_capi::IR::FFIVal_::mlirModuleCreateEmpty(loc) }
     }
-    pub fn create_parse(ctx: &'ctx Context, s: &str) -> Self {
+    pub fn create_parse(ctx: &'ctx Context, s: &str) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new(ctx);
         let str_ref = StrRef::from_str(s);
-        unsafe { mlir_capi::IR::FFIVal_::mlirModuleCreateParse(ctx, str_ref) }
+        let module: Self = unsafe { mlir_capi::IR::FFIVal_::mlirModuleCreateParse(ctx, str_ref) };
+        if module.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(module)
+        }
     }
     pub fn get_body(self) -> BlockRef<'ctx> {
         let handle = unsafe { mlir_capi::IR::FFIVal_::mlirModuleGetBody(self) };
@@ -3,6 +3,7 @@ use crate::attribute::*;
 use crate::block::*;
 use crate::common::*;
 use crate::context::*;
+use crate::diagnostic::*;
 use crate::location::*;
 use crate::op_printing_flags::*;
 use crate::operation_state::*;
@@ -104,9 +105,9 @@ mod operation_test {
     fn create() {
         let ctx = Context::create();
         let loc = Location::unknown_get(&ctx);
-        let arith_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Arith);
+        let arith_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Arith).unwrap();
         arith_handle.load_dialect(&ctx);
-        let func_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Func);
+        let func_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Func).unwrap();
         func_handle.load_dialect(&ctx);
         let i32_ty: Type = IntegerType::get(&ctx, 32).into();
         let constant_val = NamedAttr {
@@ -145,7 +146,7 @@ mod operation_test {
             "%result:2 = \"foo_div\"() : () -> (f32, i32)",
             "unknown_source_01",
         );
-        let func_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Func);
+        let func_handle = get_handle_for_upstream_dialect(UpstreamDialectName::Func).unwrap();
         func_handle.load_dialect(&ctx);
         let func_op = Operation::create_parse(
             &ctx,
@@ -199,6 +200,10 @@ impl<'ctx> OperationRef<'ctx> {
             phantom: self.phantom,
         }
     }
+    /// Removes the operation from its parent block and destroys it.
+    pub fn erase(self) {
+        unsafe { IR::FFIVoid_::mlirOperationDestroy(self) };
+    }
 }
 
 impl<'ctx> std::ops::Deref for OperationRef<'ctx> {
@@ -248,6 +253,10 @@ impl<'ctx> Operation<'ctx> {
         let handle = unsafe { IR::FFIVal_::mlirOperationGetRegion(self, pos as i64) };
         unsafe { RegionRef::wrap(handle, self.phantom) }
     }
+    pub fn regions(&self) -> impl DoubleEndedIterator<Item = RegionRef<'ctx>> + ExactSizeIterator {
+        let op = OperationRef::from_handle_same_context(self.handle, self);
+        (0..self.get_num_regions()).map(move |pos| op.get_region(pos))
+    }
     pub fn get_next_in_block(&self) -> OperationRef<'ctx> {
         let handle = unsafe { IR::FFIVal_::mlirOperationGetNextInBlock(self) };
         OperationRef::from_handle_same_context(handle, self)
@@ -258,6 +267,10 @@ impl<'ctx> Operation<'ctx> {
     pub fn get_operand(&self, pos: usize) -> Value<'ctx> {
         unsafe { IR::FFIVal_::mlirOperationGetOperand(self, pos as i64) }
     }
+    pub fn operands(&self) -> impl DoubleEndedIterator<Item = Value<'ctx>> + ExactSizeIterator {
+        let op = OperationRef::from_handle_same_context(self.handle, self);
+        (0..self.get_num_operands()).map(move |pos| op.get_operand(pos))
+    }
     pub fn set_operand(&self, pos: usize, value: Value<'ctx>) {
         unsafe {
             IR::FFIVoid_::mlirOperationSetOperand(self, pos as i64, value);
@@ -278,6 +291,10 @@ impl<'ctx> Operation<'ctx> {
     pub fn get_result(&self, pos: usize) -> Value<'ctx> {
         unsafe { IR::FFIVal_::mlirOperationGetResult(self, pos as i64) }
     }
+    pub fn results(&self) -> impl DoubleEndedIterator<Item = Value<'ctx>> + ExactSizeIterator {
+        let op = OperationRef::from_handle_same_context(self.handle, self);
+        (0..self.get_num_results()).map(move |pos| op.get_result(pos))
+    }
     pub fn get_num_successors(&self) -> usize {
         (unsafe { IR::FFIVal_::<i64>::mlirOperationGetNumSuccessors(self) }) as _
     }
@@ -285,9 +302,19 @@ impl<'ctx> Operation<'ctx> {
         let handle = unsafe { IR::FFIVal_::mlirOperationGetSuccessor(self, pos as i64) };
         unsafe { BlockRef::wrap(handle, self.phantom) }
     }
-    pub fn set_succeccor(&self, pos: usize, block: BlockRef<'ctx>) {
+    pub fn successors(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = BlockRef<'ctx>> + ExactSizeIterator {
+        let op = OperationRef::from_handle_same_context(self.handle, self);
+        (0..self.get_num_successors()).map(move |pos| op.get_successor(pos))
+    }
+    pub fn set_successor(&self, pos: usize, block: BlockRef<'ctx>) {
         unsafe { IR::FFIVoid_::mlirOperationSetSuccessor(self, pos as i64, block) };
     }
+    #[deprecated(note = "use `set_successor` instead")]
+    pub fn set_succeccor(&self, pos: usize, block: BlockRef<'ctx>) {
+        self.set_successor(pos, block);
+    }
     pub fn has_inherent_attr_by_name(&self, name: &str) -> bool {
         let name_ref: StrRef = name.into();
         to_rbool(unsafe { IR::FFIVal_::mlirOperationHasInherentAttributeByName(self, name_ref) })
@@ -305,6 +332,12 @@ impl<'ctx> Operation<'ctx> {
         let handle = unsafe { IR::FFIVal_::mlirOperationGetAttribute(self, pos as i64) };
         NamedAttr::from_handle_same_context(handle, self)
     }
+    pub fn attributes(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = NamedAttr<'ctx>> + ExactSizeIterator {
+        let op = OperationRef::from_handle_same_context(self.handle, self);
+        (0..self.get_num_attrs()).map(move |pos| op.get_attr(pos))
+    }
     pub fn get_attr_by_name(&self, name: &str) -> Attr<'ctx> {
         let name_ref: StrRef = name.into();
         let handle = unsafe { IR::FFIVal_::mlirOperationGetAttributeByName(self, name_ref) };
@@ -375,8 +408,13 @@ impl<'ctx> Operation<'ctx> {
             IR::FFIVoid_::mlirOperationDump(self);
         }
     }
-    pub fn verify(&self) -> bool {
-        to_rbool(unsafe { IR::FFIVal_::mlirOperationVerify(self) })
+    pub fn verify(&self) -> Result<(), Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new_for(self);
+        if to_rbool(unsafe { IR::FFIVal_::mlirOperationVerify(self) }) {
+            Ok(())
+        } else {
+            Err(collector.into_diagnostics())
+        }
     }
     pub fn move_after(&self, other: &Operation<'ctx>) {
         unsafe { IR::FFIVoid_::mlirOperationMoveAfter(self, other) }
@@ -384,7 +422,70 @@ impl<'ctx> Operation<'ctx> {
     pub fn move_before(&self, other: &Operation<'ctx>) {
         unsafe { IR::FFIVoid_::mlirOperationMoveBefore(self, other) }
     }
-    // FIXME: mlirOperationWalk
+    /// Walks this operation and all nested operations, in the given order, by means of
+    /// mlirOperationWalk. Returns `WalkResult::Interrupt` if the walk was interrupted.
+    pub fn walk<F>(&self, order: WalkOrder, callback: F) -> WalkResult
+    where
+        F: FnMut(OperationRef<'ctx>) -> WalkResult,
+    {
+        let mut state = WalkState {
+            callback,
+            interrupted: false,
+        };
+        unsafe {
+            IR::FFIVoid_::mlirOperationWalk(
+                self,
+                walk_callback::<F> as *mut _,
+                &mut state as *mut WalkState<F> as *mut _,
+                order.to_ffi(),
+            );
+        }
+        if state.interrupted {
+            WalkResult::Interrupt
+        } else {
+            WalkResult::Advance
+        }
+    }
+    /// Same as `walk`, but implemented in Rust and only invoking `callback` on the operations
+    /// accepted by `filter`.
+    pub fn walk_filtered<P, F>(
+        &self,
+        order: WalkOrder,
+        mut filter: P,
+        mut callback: F,
+    ) -> WalkResult
+    where
+        P: FnMut(OperationRef<'ctx>) -> bool,
+        F: FnMut(OperationRef<'ctx>) -> WalkResult,
+    {
+        let op = OperationRef::from_handle_same_context(self.handle, self);
+        walk_impl(op, order, &mut |op| {
+            if filter(op) {
+                callback(op)
+            } else {
+                WalkResult::Advance
+            }
+        })
+    }
+    /// Walks the nested operations named `name`, e.g. "func.func".
+    pub fn walk_named<F>(&self, order: WalkOrder, name: &str, callback: F) -> WalkResult
+    where
+        F: FnMut(OperationRef<'ctx>) -> WalkResult,
+    {
+        self.walk_filtered(order, |op| op.get_name().str() == name, callback)
+    }
+    /// Walks the nested operations of the op type `T`.
+    pub fn walk_typed<T, F>(&self, order: WalkOrder, mut callback: F) -> WalkResult
+    where
+        T: OpView<'ctx>,
+        F: FnMut(T) -> WalkResult,
+    {
+        self.walk_filtered(
+            order,
+            |op| op.get_name().str() == T::get_operation_name(),
+            |op| callback(unsafe { T::from_op_unchecked(op) }),
+        )
+    }
     pub fn print_to_formatter(
         &self,
         formatter: &mut std::fmt::Formatter,
@@ -407,6 +508,132 @@ impl<'ctx> Display for Operation<'ctx> {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WalkOrder {
+    PreOrder,
+    PostOrder,
+}
+
+impl WalkOrder {
+    pub fn to_ffi(self) -> MlirWalkOrder {
+        match self {
+            WalkOrder::PreOrder => 0,
+            WalkOrder::PostOrder => 1,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WalkResult {
+    Advance,
+    Interrupt,
+    /// Skips the nested operations, only meaningful in pre-order.
+    Skip,
+}
+
+impl WalkResult {
+    pub fn to_ffi(self) -> MlirWalkResult {
+        match self {
+            WalkResult::Advance => 0,
+            WalkResult::Interrupt => 1,
+            WalkResult::Skip => 2,
+        }
+    }
+}
+
+struct WalkState<F> {
+    callback: F,
+    interrupted: bool,
+}
+
+unsafe extern "C" fn walk_callback<'ctx, F: FnMut(OperationRef<'ctx>) -> WalkResult>(
+    op: MlirOperation,
+    user_data: *mut std::ffi::c_void,
+) -> MlirWalkResult {
+    let state = (user_data as *mut WalkState<F>).as_mut_unchecked();
+    let op = OperationRef::from_handle_and_phantom(op, PhantomData::default());
+    let res = (state.callback)(op);
+    if res == WalkResult::Interrupt {
+        state.interrupted = true;
+    }
+    res.to_ffi()
+}
+
+fn walk_impl<'ctx>(
+    op: OperationRef<'ctx>,
+    order: WalkOrder,
+    callback: &mut dyn FnMut(OperationRef<'ctx>) -> WalkResult,
+) -> WalkResult {
+    if order == WalkOrder::PreOrder {
+        match callback(op) {
+            WalkResult::Advance => {}
+            WalkResult::Interrupt => return WalkResult::Interrupt,
+            WalkResult::Skip => return WalkResult::Advance,
+        }
+    }
+    for pos in 0..op.get_num_regions() {
+        let region = op.get_region(pos);
+        let mut block = region.get_first_block();
+        while !block.is_null() {
+            let mut nested = block.get_first_operation();
+            while !nested.is_null() {
+                // Fetched before the visit, so that the callback may erase the visited op in
+                // post-order.
+                let next = nested.get_next_in_block();
+                if walk_impl(nested, order, callback) == WalkResult::Interrupt {
+                    return WalkResult::Interrupt;
+                }
+                nested = next;
+            }
+            block = block.get_next_in_region();
+        }
+    }
+    if order == WalkOrder::PostOrder && callback(op) == WalkResult::Interrupt {
+        return WalkResult::Interrupt;
+    }
+    WalkResult::Advance
+}
+
+/// A typed view of an operation with a specific name.
+pub trait OpView<'ctx>: Sized {
+    fn get_operation_name() -> &'static str;
+    /// `op` must be an operation named `get_operation_name()`.
+    unsafe fn from_op_unchecked(op: OperationRef<'ctx>) -> Self;
+    fn get_op(&self) -> OperationRef<'ctx>;
+    fn from_op(op: OperationRef<'ctx>) -> Option<Self> {
+        if !op.is_null() && op.get_name().str() == Self::get_operation_name() {
+            Some(unsafe { Self::from_op_unchecked(op) })
+        } else {
+            None
+        }
+    }
+}
+
+/// Defines a `Copy` struct wrapping an `OperationRef`, implementing `OpView` for the operation
+/// named `$name`.
+macro_rules! define_op_view {
+    ($(#[$meta:meta])* $view:ident, $name:literal) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone)]
+        pub struct $view<'ctx> {
+            op: OperationRef<'ctx>,
+        }
+
+        impl<'ctx> OpView<'ctx> for $view<'ctx> {
+            fn get_operation_name() -> &'static str {
+                $name
+            }
+            unsafe fn from_op_unchecked(op: OperationRef<'ctx>) -> Self {
+                Self { op }
+            }
+            fn get_op(&self) -> OperationRef<'ctx> {
+                self.op
+            }
+        }
+    };
+}
+pub(crate) use define_op_view;
+
 #[repr(C)]
 pub struct ByteCodeWriterConfig {
     handle: MlirBytecodeWriterConfig,
@@ -433,3 +660,189 @@ impl ByteCodeWriterConfig {
         unsafe { IR::FFIVoid_::mlirBytecodeWriterConfigDesiredEmitVersion(self, version) }
     }
 }
+
+#[cfg(test)]
+mod operation_walk_test {
+    use super::*;
+    use crate::module::*;
+
+    const NESTED: &str = "module {
+  \"test.a\"() ({
+    \"test.b\"() : () -> ()
+    \"test.c\"() : () -> ()
+  }) : () -> ()
+  \"test.d\"() : () -> ()
+}";
+
+    define_op_view!(TestCOp, "test.c");
+
+    fn collect_names<'ctx>(
+        op: &Operation<'ctx>,
+        order: WalkOrder,
+        stop_at: &str,
+        result: WalkResult,
+        use_rust_walk: bool,
+    ) -> (Vec<String>, WalkResult) {
+        let mut names = vec![];
+        let callback = |op: OperationRef<'ctx>| {
+            let name = op.get_name().str();
+            names.push(name.to_string());
+            if name == stop_at {
+                result
+            } else {
+                WalkResult::Advance
+            }
+        };
+        let res = if use_rust_walk {
+            op.walk_filtered(order, |_| true, callback)
+        } else {
+            op.walk(order, callback)
+        };
+        (names, res)
+    }
+
+    #[test]
+    fn walk() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let module = Module::create_parse(&ctx, NESTED).unwrap();
+        let op = module.get_operation_ref();
+        let cases: &[(WalkOrder, &str, WalkResult, &[&str], WalkResult)] = &[
+            (
+                WalkOrder::PreOrder,
+                "",
+                WalkResult::Advance,
+                &["builtin.module", "test.a", "test.b", "test.c", "test.d"],
+                WalkResult::Advance,
+            ),
+            (
+                WalkOrder::PostOrder,
+                "",
+                WalkResult::Advance,
+                &["test.b", "test.c", "test.a", "test.d", "builtin.module"],
+                WalkResult::Advance,
+            ),
+            (
+                WalkOrder::PreOrder,
+                "test.a",
+                WalkResult::Skip,
+                &["builtin.module", "test.a", "test.d"],
+                WalkResult::Advance,
+            ),
+            (
+                WalkOrder::PreOrder,
+                "test.b",
+                WalkResult::Interrupt,
+                &["builtin.module", "test.a", "test.b"],
+                WalkResult::Interrupt,
+            ),
+            (
+                WalkOrder::PostOrder,
+                "test.a",
+                WalkResult::Interrupt,
+                &["test.b", "test.c", "test.a"],
+                WalkResult::Interrupt,
+            ),
+        ];
+        for (order, stop_at, result, expected_names, expected_res) in cases {
+            for use_rust_walk in [false, true] {
+                let (names, res) = collect_names(&op, *order, stop_at, *result, use_rust_walk);
+                assert_eq!(names, *expected_names);
+                assert_eq!(res, *expected_res);
+            }
+        }
+    }
+
+    #[test]
+    fn walk_named_and_typed() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let module = Module::create_parse(&ctx, NESTED).unwrap();
+        let op = module.get_operation_ref();
+        let mut count = 0;
+        op.walk_named(WalkOrder::PreOrder, "test.b", |op| {
+            assert_eq!(op.get_name().str(), "test.b");
+            count += 1;
+            WalkResult::Advance
+        });
+        assert_eq!(count, 1);
+        let mut typed = vec![];
+        op.walk_typed(WalkOrder::PostOrder, |c_op: TestCOp| {
+            typed.push(c_op.get_op());
+            WalkResult::Advance
+        });
+        assert_eq!(typed.len(), 1);
+        let c_op = TestCOp::from_op(typed[0]).unwrap();
+        assert!(c_op.get_op() == typed[0]);
+        assert_eq!(TestCOp::get_operation_name(), "test.c");
+        assert!(TestCOp::from_op(op).is_none());
+    }
+}
+
+#[cfg(test)]
+mod operation_iter_test {
+    use super::*;
+    use crate::module::*;
+
+    const FUNC: &str = "module {
+  \"test.func\"() ({
+  ^bb0(%arg0: i32, %arg1: i32):
+    %0 = \"test.add\"(%arg0, %arg1) {attr1 = 1 : i32, attr2 = \"s\"} : (i32, i32) -> i32
+    %1 = \"test.mul\"(%0, %0) : (i32, i32) -> i32
+    \"test.br\"(%1)[^bb1] : (i32) -> ()
+  ^bb1(%2: i32):
+    \"test.ret\"(%2) : (i32) -> ()
+  }, {
+  }) : () -> ()
+}";
+
+    fn names<'ctx>(ops: impl Iterator<Item = OperationRef<'ctx>>) -> Vec<&'ctx str> {
+        ops.map(|op| op.get_name().str()).collect()
+    }
+
+    #[test]
+    fn iterate() {
+        let ctx = Context::create();
+        ctx.set_allow_unregistered_dialects(true);
+        let module = Module::create_parse(&ctx, FUNC).unwrap();
+        let func = module.get_body().operations().next().unwrap();
+        assert_eq!(func.get_name().str(), "test.func");
+
+        let regions: Vec<_> = func.regions().collect();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(func.regions().len(), 2);
+        assert_eq!(regions[1].blocks().count(), 0);
+        let blocks: Vec<_> = regions[0].blocks().collect();
+        assert_eq!(blocks.len(), 2);
+
+        let entry = blocks[0];
+        assert_eq!(entry.arguments().len(), 2);
+        assert_eq!(
+            names(entry.operations()),
+            vec!["test.add", "test.mul", "test.br"]
+        );
+        assert_eq!(
+            names(entry.operations().rev()),
+            vec!["test.br", "test.mul", "test.add"]
+        );
+
+        let ops: Vec<_> = entry.operations().collect();
+        let (add, mul, br) = (ops[0], ops[1], ops[2]);
+        let args: Vec<_> = entry.arguments().collect();
+        assert!(add.operands().eq(args.iter().cloned()));
+        assert_eq!(add.results().len(), 1);
+        let attr_names: Vec<_> = add.attributes().map(|attr| attr.name.str()).collect();
+        assert_eq!(attr_names, vec!["attr1", "attr2"]);
+
+        let sum = add.results().next().unwrap();
+        let mut uses: Vec<_> = sum.uses().map(|u| u.get_number()).collect();
+        uses.sort();
+        assert_eq!(uses, vec![0, 1]);
+        assert!(sum.uses().all(|u| u.get_owner() == mul));
+
+        let successors: Vec<_> = br.successors().collect();
+        assert_eq!(successors.len(), 1);
+        assert!(*successors[0] == *blocks[1]);
+        assert_eq!(blocks[1].arguments().rev().count(), 1);
+    }
+}
@@ -53,7 +53,7 @@ impl<'ctx> OperationState<'ctx> {
         };
         self
     }
-    pub fn add_owned_regions(&mut self, regions: &[Region<'ctx>]) -> &mut Self {
+    pub fn add_owned_regions(&mut self, regions: Vec<Region<'ctx>>) -> &mut Self {
         unsafe {
             IR::FFIVoid_::mlirOperationStateAddOwnedRegions(
                 (&mut self.handle) as *mut _,
@@ -61,9 +61,14 @@ impl<'ctx> OperationState<'ctx> {
                 regions.as_ptr() as *const _,
             );
         };
+        // The regions are owned by the operation state from now on.
+        for region in regions {
+            std::mem::forget(region);
+        }
         self
     }
-    pub fn add_successors(&mut self, successors: &[Block<'ctx>]) -> &mut Self {
+    /// Adds blocks that already live in a region as the successors of the operation.
+    pub fn add_successors(&mut self, successors: &[BlockRef<'ctx>]) -> &mut Self {
         unsafe {
             IR::FFIVoid_::mlirOperationStateAddSuccessors(
                 (&mut self.handle) as *mut _,
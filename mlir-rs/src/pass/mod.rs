@@ -0,0 +1,444 @@
+use crate::context::*;
+use crate::dialect::*;
+use crate::operation::*;
+use crate::pass_manager::*;
+use crate::support::*;
+
+use mlir_capi::Dialect_::{Linalg, GPU};
+use mlir_capi::Pass as MLIR_Pass;
+use mlir_capi::Pass::*;
+use mlir_capi::Support::MlirLogicalResult;
+use mlir_capi::IR::{MlirContext, MlirDialectHandle, MlirOperation};
+use mlir_capi::{Conversion, RegisterEverything, Transforms};
+
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use strum::EnumIter;
+
+#[derive(EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UpstreamPassName {
+    // Transforms
+    Canonicalizer,
+    CSE,
+    ControlFlowSink,
+    GenerateRuntimeVerification,
+    Inliner,
+    LoopInvariantCodeMotion,
+    Mem2Reg,
+    PrintOpStats,
+    RemoveDeadValues,
+    SCCP,
+    SROA,
+    StripDebugInfo,
+    SymbolDCE,
+    SymbolPrivatize,
+    TopologicalSort,
+    // Conversion
+    ArithToLLVM,
+    ControlFlowToLLVM,
+    FuncToLLVM,
+    IndexToLLVM,
+    LowerAffine,
+    MathToLLVM,
+    FinalizeMemRefToLLVM,
+    ReconcileUnrealizedCasts,
+    SCFToControlFlow,
+    ToLLVM,
+    VectorToLLVM,
+    VectorToSCF,
+    // Dialect specific
+    GPUKernelOutlining,
+    LinalgToLoops,
+}
+
+pub fn create_upstream_pass<'ctx>(pass: UpstreamPassName) -> Pass<'ctx> {
+    let handle = unsafe {
+        match pass {
+            UpstreamPassName::Canonicalizer => Transforms::mlirCreateTransformsCanonicalizer(),
+            UpstreamPassName::CSE => Transforms::mlirCreateTransformsCSE(),
+            UpstreamPassName::ControlFlowSink => Transforms::mlirCreateTransformsControlFlowSink(),
+            UpstreamPassName::GenerateRuntimeVerification => {
+                Transforms::mlirCreateTransformsGenerateRuntimeVerification()
+            }
+            UpstreamPassName::Inliner => Transforms::mlirCreateTransformsInliner(),
+            UpstreamPassName::LoopInvariantCodeMotion => {
+                Transforms::mlirCreateTransformsLoopInvariantCodeMotion()
+            }
+            UpstreamPassName::Mem2Reg => Transforms::mlirCreateTransformsMem2Reg(),
+            UpstreamPassName::PrintOpStats => Transforms::mlirCreateTransformsPrintOpStats(),
+            UpstreamPassName::RemoveDeadValues => {
+                Transforms::mlirCreateTransformsRemoveDeadValues()
+            }
+            UpstreamPassName::SCCP => Transforms::mlirCreateTransformsSCCP(),
+            UpstreamPassName::SROA => Transforms::mlirCreateTransformsSROA(),
+            UpstreamPassName::StripDebugInfo => Transforms::mlirCreateTransformsStripDebugInfo(),
+            UpstreamPassName::SymbolDCE => Transforms::mlirCreateTransformsSymbolDCE(),
+            UpstreamPassName::SymbolPrivatize => Transforms::mlirCreateTransformsSymbolPrivatize(),
+            UpstreamPassName::TopologicalSort => Transforms::mlirCreateTransformsTopologicalSort(),
+            UpstreamPassName::ArithToLLVM => {
+                Conversion::mlirCreateConversionArithToLLVMConversionPass()
+            }
+            UpstreamPassName::ControlFlowToLLVM => {
+                Conversion::mlirCreateConversionConvertControlFlowToLLVMPass()
+            }
+            UpstreamPassName::FuncToLLVM => Conversion::mlirCreateConversionConvertFuncToLLVMPass(),
+            UpstreamPassName::IndexToLLVM => {
+                Conversion::mlirCreateConversionConvertIndexToLLVMPass()
+            }
+            UpstreamPassName::LowerAffine => Conversion::mlirCreateConversionLowerAffinePass(),
+            UpstreamPassName::MathToLLVM => Conversion::mlirCreateConversionConvertMathToLLVMPass(),
+            UpstreamPassName::FinalizeMemRefToLLVM => {
+                Conversion::mlirCreateConversionFinalizeMemRefToLLVMConversionPass()
+            }
+            UpstreamPassName::ReconcileUnrealizedCasts => {
+                Conversion::mlirCreateConversionReconcileUnrealizedCastsPass()
+            }
+            UpstreamPassName::SCFToControlFlow => {
+                Conversion::mlirCreateConversionSCFToControlFlowPass()
+            }
+            UpstreamPassName::ToLLVM => Conversion::mlirCreateConversionConvertToLLVMPass(),
+            UpstreamPassName::VectorToLLVM => {
+                Conversion::mlirCreateConversionConvertVectorToLLVMPass()
+            }
+            UpstreamPassName::VectorToSCF => Conversion::mlirCreateConversionConvertVectorToSCF(),
+            UpstreamPassName::GPUKernelOutlining => GPU::mlirCreateGPUGpuKernelOutliningPass(),
+            UpstreamPassName::LinalgToLoops => Linalg::mlirCreateLinalgConvertLinalgToLoopsPass(),
+        }
+    };
+    Pass::from(handle)
+}
+
+/// The command line argument of the pass, as used in textual pass pipelines.
+pub fn get_argument_for_upstream_pass(pass: UpstreamPassName) -> &'static str {
+    match pass {
+        UpstreamPassName::Canonicalizer => "canonicalize",
+        UpstreamPassName::CSE => "cse",
+        UpstreamPassName::ControlFlowSink => "control-flow-sink",
+        UpstreamPassName::GenerateRuntimeVerification => "generate-runtime-verification",
+        UpstreamPassName::Inliner => "inline",
+        UpstreamPassName::LoopInvariantCodeMotion => "loop-invariant-code-motion",
+        UpstreamPassName::Mem2Reg => "mem2reg",
+        UpstreamPassName::PrintOpStats => "print-op-stats",
+        UpstreamPassName::RemoveDeadValues => "remove-dead-values",
+        UpstreamPassName::SCCP => "sccp",
+        UpstreamPassName::SROA => "sroa",
+        UpstreamPassName::StripDebugInfo => "strip-debuginfo",
+        UpstreamPassName::SymbolDCE => "symbol-dce",
+        UpstreamPassName::SymbolPrivatize => "symbol-privatize",
+        UpstreamPassName::TopologicalSort => "topological-sort",
+        UpstreamPassName::ArithToLLVM => "convert-arith-to-llvm",
+        UpstreamPassName::ControlFlowToLLVM => "convert-cf-to-llvm",
+        UpstreamPassName::FuncToLLVM => "convert-func-to-llvm",
+        UpstreamPassName::IndexToLLVM => "convert-index-to-llvm",
+        UpstreamPassName::LowerAffine => "lower-affine",
+        UpstreamPassName::MathToLLVM => "convert-math-to-llvm",
+        UpstreamPassName::FinalizeMemRefToLLVM => "finalize-memref-to-llvm",
+        UpstreamPassName::ReconcileUnrealizedCasts => "reconcile-unrealized-casts",
+        UpstreamPassName::SCFToControlFlow => "convert-scf-to-cf",
+        UpstreamPassName::ToLLVM => "convert-to-llvm",
+        UpstreamPassName::VectorToLLVM => "convert-vector-to-llvm",
+        UpstreamPassName::VectorToSCF => "convert-vector-to-scf",
+        UpstreamPassName::GPUKernelOutlining => "gpu-kernel-outlining",
+        UpstreamPassName::LinalgToLoops => "convert-linalg-to-loops",
+    }
+}
+
+/// Registers all upstream passes, so that they can be referred to in textual pass pipelines.
+pub fn register_all_passes() {
+    unsafe {
+        RegisterEverything::mlirRegisterAllPasses();
+    }
+}
+
+pub fn register_transforms_passes() {
+    unsafe {
+        Transforms::mlirRegisterTransformsPasses();
+    }
+}
+
+pub fn register_conversion_passes() {
+    unsafe {
+        Conversion::mlirRegisterConversionPasses();
+    }
+}
+
+/// A pass implemented in Rust.
+///
+/// The pass manager clones the pass for every operation it runs on in parallel, and runs the
+/// clones on its worker threads. Each clone is dropped together with the pass manager.
+pub trait ExternalPass<'ctx>: Clone + Send + Sync + 'ctx {
+    fn get_name(&self) -> &str;
+    fn get_argument(&self) -> &str {
+        ""
+    }
+    fn get_description(&self) -> &str {
+        ""
+    }
+    /// The operation the pass is anchored on, an empty string means any operation.
+    fn get_op_name(&self) -> &str {
+        ""
+    }
+    fn get_dependent_dialects(&self) -> Vec<DialectHandle> {
+        vec![]
+    }
+    fn initialize(&mut self, _ctx: ContextRef<'ctx>) -> LogicalResult {
+        LogicalResult::success()
+    }
+    fn run_on_operation(&mut self, op: OperationRef<'ctx>);
+    /// Marks the currently running invocation of the pass as failed. Does nothing when called
+    /// outside of `run_on_operation`.
+    fn signal_failure(&self) {
+        if let Some(external_pass) = CURRENT_EXTERNAL_PASS.with(|current| current.get()) {
+            unsafe {
+                MLIR_Pass::mlirExternalPassSignalFailure(external_pass);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT_EXTERNAL_PASS: Cell<Option<MlirExternalPass>> = Cell::new(None);
+}
+
+unsafe extern "C" fn external_pass_construct<'ctx, P: ExternalPass<'ctx>>(_user_data: *mut c_void) {
+}
+
+// Panics must not unwind into the pass manager: a panic fails the pass where the C API allows it,
+// and aborts otherwise.
+
+unsafe extern "C" fn external_pass_destruct<'ctx, P: ExternalPass<'ctx>>(user_data: *mut c_void) {
+    let pass = Box::from_raw(user_data as *mut P);
+    if catch_unwind(AssertUnwindSafe(move || drop(pass))).is_err() {
+        std::process::abort();
+    }
+}
+
+unsafe extern "C" fn external_pass_initialize<'ctx, P: ExternalPass<'ctx>>(
+    ctx: MlirContext,
+    user_data: *mut c_void,
+) -> MlirLogicalResult {
+    let pass = (user_data as *mut P).as_mut_unchecked();
+    let ctx = ContextRef::from_handle_and_phantom(ctx, PhantomData::default());
+    catch_unwind(AssertUnwindSafe(|| pass.initialize(ctx)))
+        .unwrap_or_else(|_| LogicalResult::failure())
+        .handle
+}
+
+unsafe extern "C" fn external_pass_clone<'ctx, P: ExternalPass<'ctx>>(
+    user_data: *mut c_void,
+) -> *mut c_void {
+    let pass = (user_data as *const P).as_ref_unchecked();
+    match catch_unwind(AssertUnwindSafe(|| pass.clone())) {
+        Ok(clone) => Box::into_raw(Box::new(clone)) as *mut c_void,
+        // The pass manager cannot handle a failed clone.
+        Err(_) => std::process::abort(),
+    }
+}
+
+unsafe extern "C" fn external_pass_run<'ctx, P: ExternalPass<'ctx>>(
+    op: MlirOperation,
+    external_pass: MlirExternalPass,
+    user_data: *mut c_void,
+) {
+    let pass = (user_data as *mut P).as_mut_unchecked();
+    let op = OperationRef::from_handle_and_phantom(op, PhantomData::default());
+    let prev = CURRENT_EXTERNAL_PASS.with(|current| current.replace(Some(external_pass)));
+    let res = catch_unwind(AssertUnwindSafe(|| pass.run_on_operation(op)));
+    CURRENT_EXTERNAL_PASS.with(|current| current.set(prev));
+    if res.is_err() {
+        MLIR_Pass::mlirExternalPassSignalFailure(external_pass);
+    }
+}
+
+/// Wraps `pass` so that it can be added to a pass manager. The TypeID of the pass is taken
+/// from `allocator`, which is borrowed for as long as the pass exists.
+pub fn create_external_pass<'ctx, P: ExternalPass<'ctx>>(
+    pass: P,
+    allocator: &'ctx TypeIDAllocator,
+) -> Pass<'ctx> {
+    let type_id = allocator.allocate_type_id();
+    let user_data = Box::into_raw(Box::new(pass));
+    let pass = unsafe { user_data.as_ref_unchecked() };
+    let mut dependent_dialects: Vec<MlirDialectHandle> = pass
+        .get_dependent_dialects()
+        .iter()
+        .map(|handle| handle.into())
+        .collect();
+    let callbacks = MlirExternalPassCallbacks {
+        construct: Some(external_pass_construct::<P>),
+        destruct: Some(external_pass_destruct::<P>),
+        initialize: Some(external_pass_initialize::<P>),
+        clone: Some(external_pass_clone::<P>),
+        run: Some(external_pass_run::<P>),
+    };
+    let handle = unsafe {
+        MLIR_Pass::FFIVal_::mlirCreateExternalPass(
+            &type_id,
+            StrRef::from(pass.get_name()),
+            StrRef::from(pass.get_argument()),
+            StrRef::from(pass.get_description()),
+            StrRef::from(pass.get_op_name()),
+            dependent_dialects.len() as i64,
+            dependent_dialects.as_mut_ptr() as *mut _,
+            callbacks,
+            user_data as *mut c_void,
+        )
+    };
+    Pass::from(handle)
+}
+
+#[cfg(test)]
+mod pass_test {
+    use super::*;
+    use crate::context::*;
+    use crate::module::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn create_upstream_passes() {
+        let ctx = Context::create();
+        let pm = PassManager::create(&ctx);
+        for name in UpstreamPassName::iter() {
+            let pass = create_upstream_pass(name);
+            assert!(!pass.is_null());
+            pm.add_owned_pass(pass);
+        }
+        let pipeline = format!("{}", pm);
+        for name in UpstreamPassName::iter() {
+            assert!(pipeline.contains(get_argument_for_upstream_pass(name)));
+        }
+    }
+
+    #[test]
+    fn parse_registered_passes() {
+        register_all_passes();
+        let ctx = Context::create();
+        let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+        pm.parse_pass_pipeline("builtin.module(canonicalize,cse)")
+            .unwrap();
+        let module = Module::create_parse(&ctx, "module {}").unwrap();
+        assert!(pm.run_on_module(module).is_success());
+
+        let args: Vec<_> = UpstreamPassName::iter()
+            .map(get_argument_for_upstream_pass)
+            .collect();
+        let any_pm = PassManager::create(&ctx);
+        any_pm
+            .parse_pass_pipeline(&format!("any({})", args.join(",")))
+            .unwrap();
+    }
+
+    #[derive(Clone)]
+    struct CountingPass {
+        runs: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    impl<'ctx> ExternalPass<'ctx> for CountingPass {
+        fn get_name(&self) -> &str {
+            "CountingPass"
+        }
+        fn get_argument(&self) -> &str {
+            "test-counting-pass"
+        }
+        fn get_op_name(&self) -> &str {
+            "builtin.module"
+        }
+        fn get_dependent_dialects(&self) -> Vec<DialectHandle> {
+            vec![get_handle_for_upstream_dialect(UpstreamDialectName::Arith).unwrap()]
+        }
+        fn run_on_operation(&mut self, op: OperationRef<'ctx>) {
+            assert!(!op.is_null());
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            if self.fail {
+                self.signal_failure();
+            }
+        }
+    }
+
+    #[test]
+    fn external_pass() {
+        let allocator = TypeIDAllocator::create();
+        assert!(!allocator.is_null());
+        {
+            let ctx = Context::create();
+            let runs = Arc::new(AtomicUsize::new(0));
+            let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+            let pass = CountingPass {
+                runs: runs.clone(),
+                fail: false,
+            };
+            pm.add_owned_pass(create_external_pass(pass, &allocator));
+            assert!(format!("{}", pm).contains("test-counting-pass"));
+            let num_loaded_dialects = ctx.get_num_loaded_dialects();
+            let module = Module::create_parse(&ctx, "module {}").unwrap();
+            assert!(pm.run_on_module(module).is_success());
+            assert_eq!(runs.load(Ordering::Relaxed), 1);
+            assert!(ctx.get_num_loaded_dialects() > num_loaded_dialects);
+        }
+    }
+
+    #[test]
+    fn external_pass_signal_failure() {
+        let allocator = TypeIDAllocator::create();
+        {
+            let ctx = Context::create();
+            let runs = Arc::new(AtomicUsize::new(0));
+            let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+            let pass = CountingPass {
+                runs: runs.clone(),
+                fail: true,
+            };
+            pm.add_owned_pass(create_external_pass(pass, &allocator));
+            let module = Module::create_parse(&ctx, "module {}").unwrap();
+            assert!(pm.run_on_module(module).is_failure());
+            assert_eq!(runs.load(Ordering::Relaxed), 1);
+        }
+    }
+
+    #[test]
+    fn external_pass_dropped() {
+        let allocator = TypeIDAllocator::create();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let pass = CountingPass {
+            runs: runs.clone(),
+            fail: true,
+        };
+        // Outside of `run_on_operation`, there is no invocation to fail.
+        ExternalPass::signal_failure(&pass);
+        let pass = create_external_pass(pass, &allocator);
+        assert_eq!(Arc::strong_count(&runs), 2);
+        drop(pass);
+        assert_eq!(Arc::strong_count(&runs), 1);
+    }
+
+    #[derive(Clone)]
+    struct PanickingPass;
+
+    impl<'ctx> ExternalPass<'ctx> for PanickingPass {
+        fn get_name(&self) -> &str {
+            "PanickingPass"
+        }
+        fn get_op_name(&self) -> &str {
+            "builtin.module"
+        }
+        fn run_on_operation(&mut self, _op: OperationRef<'ctx>) {
+            panic!("panicking pass");
+        }
+    }
+
+    #[test]
+    fn external_pass_panic() {
+        let allocator = TypeIDAllocator::create();
+        let ctx = Context::create();
+        let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+        pm.add_owned_pass(create_external_pass(PanickingPass, &allocator));
+        let module = Module::create_parse(&ctx, "module {}").unwrap();
+        assert!(pm.run_on_module(module).is_failure());
+    }
+}
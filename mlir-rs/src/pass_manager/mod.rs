@@ -0,0 +1,292 @@
+use crate::common::*;
+use crate::context::*;
+use crate::module::*;
+use crate::operation::*;
+use crate::support::*;
+
+use mlir_capi::Pass as MLIR_Pass;
+use mlir_capi::Pass::*;
+
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+
+/// A pass which is not owned by a pass manager yet. An external pass may borrow the context and
+/// its `TypeIDAllocator` for `'ctx`, so a pass manager only accepts passes of its own `'ctx`.
+#[repr(C)]
+pub struct Pass<'ctx> {
+    pub handle: MlirPass,
+    phantom: PhantomData<fn(&'ctx Context) -> &'ctx Context>,
+}
+
+impl<'ctx> From<MlirPass> for Pass<'ctx> {
+    fn from(value: MlirPass) -> Self {
+        Self {
+            handle: value,
+            phantom: PhantomData::default(),
+        }
+    }
+}
+
+impl<'ctx> Into<MlirPass> for &Pass<'ctx> {
+    fn into(self) -> MlirPass {
+        self.handle
+    }
+}
+
+impl<'ctx> Pass<'ctx> {
+    pub fn is_null(&self) -> bool {
+        self.handle.ptr == std::ptr::null_mut()
+    }
+}
+
+/// A pass which is dropped before being added to a pass manager is destroyed, together with the
+/// Rust state of an external pass.
+impl<'ctx> Drop for Pass<'ctx> {
+    fn drop(&mut self) {
+        if !self.is_null() {
+            unsafe {
+                mlir_capi_extra::mlirPassDestroy(self.handle);
+            }
+        }
+    }
+}
+
+// Invariant in `'ctx`, otherwise a pass manager could be shortened to accept a pass which it
+// outlives.
+#[repr(C)]
+pub struct PassManager<'ctx> {
+    pub handle: MlirPassManager,
+    phantom: PhantomData<fn(&'ctx Context) -> &'ctx Context>,
+}
+
+impl<'ctx> Into<MlirPassManager> for &PassManager<'ctx> {
+    fn into(self) -> MlirPassManager {
+        self.handle
+    }
+}
+
+impl<'ctx> Drop for PassManager<'ctx> {
+    fn drop(&mut self) {
+        unsafe {
+            MLIR_Pass::FFIVoid_::mlirPassManagerDestroy(&*self);
+        }
+    }
+}
+
+impl<'ctx> PassManager<'ctx> {
+    pub fn create(ctx: &'ctx Context) -> Self {
+        let handle = unsafe { MLIR_Pass::FFIVal_::mlirPassManagerCreate(ctx) };
+        Self {
+            handle,
+            phantom: PhantomData::default(),
+        }
+    }
+    pub fn create_on_operation(ctx: &'ctx Context, anchor_op: &str) -> Self {
+        let anchor_op_ref = StrRef::from(anchor_op);
+        let handle =
+            unsafe { MLIR_Pass::FFIVal_::mlirPassManagerCreateOnOperation(ctx, anchor_op_ref) };
+        Self {
+            handle,
+            phantom: PhantomData::default(),
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        self.handle.ptr == std::ptr::null_mut()
+    }
+    pub fn get_as_op_pass_manager<'pm>(&'pm self) -> OpPassManager<'pm, 'ctx> {
+        let handle = unsafe { MLIR_Pass::FFIVal_::mlirPassManagerGetAsOpPassManager(self) };
+        OpPassManager {
+            handle,
+            phantom: PhantomData::default(),
+        }
+    }
+    pub fn run(&self, op: &Operation<'ctx>) -> LogicalResult {
+        unsafe { MLIR_Pass::FFIVal_::mlirPassManagerRunOnOp(self, op) }
+    }
+    pub fn run_on_module(&self, module: Module<'ctx>) -> LogicalResult {
+        self.run(&module.get_operation_ref())
+    }
+    pub fn enable_verifier(&self, enable: bool) {
+        unsafe {
+            MLIR_Pass::FFIVoid_::mlirPassManagerEnableVerifier(self, to_cbool(enable));
+        }
+    }
+    pub fn nest<'pm>(&'pm self, op_name: &str) -> OpPassManager<'pm, 'ctx> {
+        let op_name_ref = StrRef::from(op_name);
+        let handle =
+            unsafe { MLIR_Pass::FFIVal_::mlirPassManagerGetNestedUnder(self, op_name_ref) };
+        OpPassManager {
+            handle,
+            phantom: PhantomData::default(),
+        }
+    }
+    pub fn add_owned_pass(&self, pass: Pass<'ctx>) {
+        unsafe {
+            MLIR_Pass::FFIVoid_::mlirPassManagerAddOwnedPass(self, &pass);
+        }
+        // The pass manager owns the pass now.
+        std::mem::forget(pass);
+    }
+    pub fn add_pipeline(&self, pipeline: &str) -> Result<(), String> {
+        self.get_as_op_pass_manager().add_pipeline(pipeline)
+    }
+    pub fn parse_pass_pipeline(&self, pipeline: &str) -> Result<(), String> {
+        self.get_as_op_pass_manager().parse_pass_pipeline(pipeline)
+    }
+}
+
+impl<'ctx> Debug for PassManager<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.get_as_op_pass_manager().print_to_formatter(formatter)
+    }
+}
+
+impl<'ctx> Display for PassManager<'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.get_as_op_pass_manager().print_to_formatter(formatter)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct OpPassManager<'pm, 'ctx> {
+    pub handle: MlirOpPassManager,
+    phantom: PhantomData<&'pm PassManager<'ctx>>,
+}
+
+impl<'pm, 'ctx> Into<MlirOpPassManager> for OpPassManager<'pm, 'ctx> {
+    fn into(self) -> MlirOpPassManager {
+        self.handle
+    }
+}
+
+impl<'pm, 'ctx> OpPassManager<'pm, 'ctx> {
+    pub fn nest(self, op_name: &str) -> Self {
+        let op_name_ref = StrRef::from(op_name);
+        let handle =
+            unsafe { MLIR_Pass::FFIVal_::mlirOpPassManagerGetNestedUnder(self, op_name_ref) };
+        Self {
+            handle,
+            phantom: self.phantom,
+        }
+    }
+    pub fn add_owned_pass(self, pass: Pass<'ctx>) {
+        unsafe {
+            MLIR_Pass::FFIVoid_::mlirOpPassManagerAddOwnedPass(self, &pass);
+        }
+        // The pass manager owns the pass now.
+        std::mem::forget(pass);
+    }
+    pub fn add_pipeline(self, pipeline: &str) -> Result<(), String> {
+        let mut error = PrintToString::new();
+        let callback: &mut dyn PrintCallback = &mut error;
+        let res: LogicalResult = unsafe {
+            MLIR_Pass::FFIVal_::mlirOpPassManagerAddPipeline(
+                self,
+                StrRef::from(pipeline),
+                print_helper as *mut _,
+                &callback as *const &mut dyn PrintCallback as *mut _,
+            )
+        };
+        if res.is_success() {
+            Ok(())
+        } else {
+            Err(error.s)
+        }
+    }
+    pub fn parse_pass_pipeline(self, pipeline: &str) -> Result<(), String> {
+        let mut error = PrintToString::new();
+        let callback: &mut dyn PrintCallback = &mut error;
+        let res: LogicalResult = unsafe {
+            MLIR_Pass::FFIVal_::mlirParsePassPipeline(
+                self,
+                StrRef::from(pipeline),
+                print_helper as *mut _,
+                &callback as *const &mut dyn PrintCallback as *mut _,
+            )
+        };
+        if res.is_success() {
+            Ok(())
+        } else {
+            Err(error.s)
+        }
+    }
+    pub fn print_pass_pipeline(self, callback: &mut dyn PrintCallback) {
+        unsafe {
+            MLIR_Pass::FFIVoid_::mlirPrintPassPipeline(
+                self,
+                print_helper as *mut _,
+                &callback as *const &mut dyn PrintCallback as *mut _,
+            );
+        }
+    }
+    pub fn print_to_formatter(
+        self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> Result<(), std::fmt::Error> {
+        let mut printer = PrintToFormatter::new(formatter);
+        self.print_pass_pipeline(&mut printer);
+        Ok(())
+    }
+}
+
+impl<'pm, 'ctx> Debug for OpPassManager<'pm, 'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.print_to_formatter(formatter)
+    }
+}
+
+impl<'pm, 'ctx> Display for OpPassManager<'pm, 'ctx> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.print_to_formatter(formatter)
+    }
+}
+
+#[cfg(test)]
+mod pass_manager_test {
+    use super::*;
+
+    #[test]
+    fn create() {
+        let ctx = Context::create();
+        let pm = PassManager::create(&ctx);
+        assert!(!pm.is_null());
+        let module_pm = PassManager::create_on_operation(&ctx, "builtin.module");
+        assert!(!module_pm.is_null());
+        assert_eq!(format!("{}", module_pm), "builtin.module()");
+    }
+
+    #[test]
+    fn nest() {
+        let ctx = Context::create();
+        let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+        let func_pm = pm.nest("func.func");
+        func_pm.nest("scf.for");
+        let pipeline = format!("{}", pm);
+        assert!(pipeline.starts_with("builtin.module("));
+        assert!(pipeline.contains("func.func(scf.for())"));
+    }
+
+    #[test]
+    fn parse_pass_pipeline() {
+        let ctx = Context::create();
+        let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+        assert!(pm.parse_pass_pipeline("builtin.module()").is_ok());
+        let err = pm
+            .parse_pass_pipeline("builtin.module(this-pass-does-not-exist)")
+            .unwrap_err();
+        assert!(err.contains("this-pass-does-not-exist"));
+        assert!(pm.add_pipeline("not a pipeline(").is_err());
+    }
+
+    #[test]
+    fn run() {
+        let ctx = Context::create();
+        let module = Module::create_parse(&ctx, "module {}").unwrap();
+        assert!(!module.is_null());
+        let pm = PassManager::create_on_operation(&ctx, "builtin.module");
+        pm.enable_verifier(true);
+        assert!(pm.run_on_module(module).is_success());
+        assert!(pm.run(&module.get_operation_ref()).is_success());
+    }
+}
@@ -40,6 +40,14 @@ impl<'ctx> Into<MlirRegion> for &RegionRef<'ctx> {
     }
 }
 
+impl<'ctx> std::ops::Deref for RegionRef<'ctx> {
+    type Target = Region<'ctx>;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::mem::transmute(self) }
+    }
+}
+
 impl<'ctx> RegionRef<'ctx> {
     pub unsafe fn wrap(handle: MlirRegion, phantom: PhantomData<&'ctx Context>) -> Self {
         Self {
@@ -57,40 +65,66 @@ impl<'ctx> Region<'ctx> {
             phantom: PhantomData::default(),
         }
     }
-    pub fn is_null(self) -> bool {
+    pub fn is_null(&self) -> bool {
         self.handle.ptr == std::ptr::null_mut()
     }
-    pub fn get_first_block(self) -> BlockRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirRegionGetFirstBlock(&self) };
+    pub fn get_first_block(&self) -> BlockRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirRegionGetFirstBlock(self) };
         unsafe { BlockRef::wrap(handle, self.phantom) }
     }
-    pub fn append_owned_block(self, block: Block<'ctx>) {
+    pub fn blocks(&self) -> BlockIter<'ctx> {
+        BlockIter {
+            next: self.get_first_block(),
+        }
+    }
+    pub fn append_owned_block(&self, block: Block<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirRegionAppendOwnedBlock(&self, &block);
+            mlir_capi::IR::FFIVoid_::mlirRegionAppendOwnedBlock(self, &block);
         }
+        std::mem::forget(block);
     }
-    pub fn insert_owned_block(self, pos: usize, block: Block<'ctx>) {
+    pub fn insert_owned_block(&self, pos: usize, block: Block<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirRegionInsertOwnedBlock(&self, pos as i64, &block);
+            mlir_capi::IR::FFIVoid_::mlirRegionInsertOwnedBlock(self, pos as i64, &block);
         }
+        std::mem::forget(block);
     }
-    pub fn insert_owned_block_after(self, reference: Block<'ctx>, block: Block<'ctx>) {
+    pub fn insert_owned_block_after(&self, reference: &Block<'ctx>, block: Block<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirRegionInsertOwnedBlockAfter(&self, &reference, &block);
+            mlir_capi::IR::FFIVoid_::mlirRegionInsertOwnedBlockAfter(self, reference, &block);
         }
+        std::mem::forget(block);
     }
-    pub fn insert_owned_block_before(self, reference: Block<'ctx>, block: Block<'ctx>) {
+    pub fn insert_owned_block_before(&self, reference: &Block<'ctx>, block: Block<'ctx>) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirRegionInsertOwnedBlockBefore(&self, &reference, &block);
+            mlir_capi::IR::FFIVoid_::mlirRegionInsertOwnedBlockBefore(self, reference, &block);
         }
+        std::mem::forget(block);
     }
-    pub fn get_next_in_operation(self) -> RegionRef<'ctx> {
-        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirRegionGetNextInOperation(&self) };
+    pub fn get_next_in_operation(&self) -> RegionRef<'ctx> {
+        let handle = unsafe { mlir_capi::IR::FFIVal_::mlirRegionGetNextInOperation(self) };
         unsafe { RegionRef::wrap(handle, self.phantom) }
     }
-    pub fn take_body_of(self, other: Self) {
+    pub fn take_body_of(&self, other: Self) {
         unsafe {
-            mlir_capi::IR::FFIVoid_::mlirRegionTakeBody(&self, &other);
+            mlir_capi::IR::FFIVoid_::mlirRegionTakeBody(self, &other);
+        }
+    }
+}
+
+pub struct BlockIter<'ctx> {
+    next: BlockRef<'ctx>,
+}
+
+impl<'ctx> Iterator for BlockIter<'ctx> {
+    type Item = BlockRef<'ctx>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next.is_null() {
+            None
+        } else {
+            let block = self.next;
+            self.next = block.get_next_in_region();
+            Some(block)
         }
     }
 }
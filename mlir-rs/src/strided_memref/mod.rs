@@ -0,0 +1,293 @@
+use crate::r#type::*;
+
+use std::ffi::c_void;
+use std::marker::PhantomData;
+
+/// The descriptor of a ranked memref, as passed to and from functions with the
+/// `llvm.emit_c_interface` attribute, which take a pointer to it for every memref argument.
+///
+/// The descriptor does not own the data it points to, it mutably borrows the buffer it is
+/// created from.
+#[repr(C)]
+pub struct StridedMemRefDescriptor<'a, T, const N: usize> {
+    pub allocated: *mut T,
+    pub aligned: *mut T,
+    pub offset: i64,
+    pub sizes: [i64; N],
+    pub strides: [i64; N],
+    phantom: PhantomData<&'a mut [T]>,
+}
+
+/// Returns the row-major strides of a contiguous buffer of the given shape.
+pub fn get_contiguous_strides<const N: usize>(shape: &[i64; N]) -> [i64; N] {
+    let mut strides = [0; N];
+    let mut stride = 1;
+    for i in (0..N).rev() {
+        strides[i] = stride;
+        stride *= shape[i];
+    }
+    strides
+}
+
+impl<'a, T, const N: usize> StridedMemRefDescriptor<'a, T, N> {
+    /// Describes `data` as a contiguous row-major buffer of the given shape.
+    pub fn from_slice(data: &'a mut [T], shape: [i64; N]) -> Result<Self, String> {
+        if shape.iter().any(|size| *size < 0) {
+            return Err(format!("negative size in shape {:?}", shape));
+        }
+        let num_elements: i64 = shape.iter().product();
+        if num_elements != data.len() as i64 {
+            return Err(format!(
+                "shape {:?} has {} elements, but the buffer has {}",
+                shape,
+                num_elements,
+                data.len()
+            ));
+        }
+        let strides = get_contiguous_strides(&shape);
+        Self::from_slice_with_layout(data, shape, strides, 0)
+    }
+    pub fn from_vec(data: &'a mut Vec<T>, shape: [i64; N]) -> Result<Self, String> {
+        Self::from_slice(data.as_mut_slice(), shape)
+    }
+    /// Describes a strided view into `data`, fails if an element of the view lies outside of
+    /// `data`.
+    pub fn from_slice_with_layout(
+        data: &'a mut [T],
+        shape: [i64; N],
+        strides: [i64; N],
+        offset: i64,
+    ) -> Result<Self, String> {
+        if shape.iter().any(|size| *size < 0) {
+            return Err(format!("negative size in shape {:?}", shape));
+        }
+        if offset < 0 || strides.iter().any(|stride| *stride < 0) {
+            return Err(format!(
+                "negative strides {:?} or offset {} are not supported",
+                strides, offset
+            ));
+        }
+        if !shape.contains(&0) {
+            let last = offset
+                + shape
+                    .iter()
+                    .zip(strides.iter())
+                    .map(|(size, stride)| (size - 1) * stride)
+                    .sum::<i64>();
+            if last >= data.len() as i64 {
+                return Err(format!(
+                    "element {} of the view is out of bounds of the buffer of size {}",
+                    last,
+                    data.len()
+                ));
+            }
+        }
+        let ptr = data.as_mut_ptr();
+        Ok(Self {
+            allocated: ptr,
+            aligned: ptr,
+            offset,
+            sizes: shape,
+            strides,
+            phantom: PhantomData::default(),
+        })
+    }
+    /// Describes `data` with the static shape and layout of `ty`.
+    pub fn from_memref_type(ty: MemRefType, data: &'a mut [T]) -> Result<Self, String> {
+        if ty.get_rank() != N as i64 {
+            return Err(format!(
+                "memref type has rank {}, expected {}",
+                ty.get_rank(),
+                N
+            ));
+        }
+        if !ty.has_static_shape() {
+            return Err("memref type has a dynamic shape".to_string());
+        }
+        let (strides, offset) = ty
+            .get_strides_and_offset()
+            .ok_or_else(|| "memref type has a non-strided layout".to_string())?;
+        if MemRefType::is_dynamic_stride_or_offset(offset)
+            || strides
+                .iter()
+                .any(|stride| MemRefType::is_dynamic_stride_or_offset(*stride))
+        {
+            return Err("memref type has a dynamic stride or offset".to_string());
+        }
+        let mut shape = [0; N];
+        for (i, size) in shape.iter_mut().enumerate() {
+            *size = ty.get_dim_size(i);
+        }
+        Self::from_slice_with_layout(data, shape, strides.try_into().unwrap(), offset)
+    }
+
+    pub fn get_rank(&self) -> usize {
+        N
+    }
+    pub fn get_num_elements(&self) -> i64 {
+        self.sizes.iter().product()
+    }
+    /// Returns a pointer to the element at `index`, or `None` if it is out of bounds.
+    pub fn get_element_ptr(&self, index: [i64; N]) -> Option<*mut T> {
+        let mut linear = self.offset;
+        for i in 0..N {
+            if index[i] < 0 || index[i] >= self.sizes[i] {
+                return None;
+            }
+            linear += index[i] * self.strides[i];
+        }
+        Some(self.aligned.wrapping_offset(linear as isize))
+    }
+    /// Reads the element at `index`.
+    ///
+    /// # Safety
+    /// The fields of the descriptor must not have been changed to point outside of the buffer
+    /// it was created from.
+    pub unsafe fn get(&self, index: [i64; N]) -> Option<&T> {
+        self.get_element_ptr(index)
+            .map(|ptr| ptr.as_ref_unchecked())
+    }
+    pub fn as_unranked(&mut self) -> UnrankedMemRefDescriptor<'_> {
+        UnrankedMemRefDescriptor {
+            rank: N as i64,
+            descriptor: self as *mut Self as *mut c_void,
+            phantom: PhantomData::default(),
+        }
+    }
+}
+
+/// The descriptor of an unranked memref, which points to a ranked descriptor of the given rank.
+#[repr(C)]
+pub struct UnrankedMemRefDescriptor<'a> {
+    pub rank: i64,
+    pub descriptor: *mut c_void,
+    phantom: PhantomData<&'a mut ()>,
+}
+
+impl<'a> UnrankedMemRefDescriptor<'a> {
+    /// Returns the ranked descriptor, or `None` if its rank is not `N`.
+    ///
+    /// # Safety
+    /// The ranked descriptor must have elements of type `T`.
+    pub unsafe fn as_ranked<T, const N: usize>(
+        &mut self,
+    ) -> Option<&mut StridedMemRefDescriptor<'a, T, N>> {
+        if self.rank != N as i64 {
+            return None;
+        }
+        Some((self.descriptor as *mut StridedMemRefDescriptor<T, N>).as_mut_unchecked())
+    }
+}
+
+#[cfg(test)]
+mod strided_memref_test {
+    use super::*;
+    use crate::context::context_test::create_context_with_all_upstream_dialects;
+    use crate::context::*;
+    use crate::dialect::*;
+    use crate::execution_engine::*;
+    use crate::module::*;
+    use crate::pass::*;
+    use crate::pass_manager::*;
+    use crate::type_cast::*;
+
+    #[test]
+    fn from_slice() {
+        let mut data: Vec<f32> = (0..6).map(|x| x as f32).collect();
+        let desc = StridedMemRefDescriptor::from_vec(&mut data, [2, 3]).unwrap();
+        assert_eq!(desc.get_rank(), 2);
+        assert_eq!(desc.get_num_elements(), 6);
+        assert_eq!(desc.strides, [3, 1]);
+        assert_eq!(desc.offset, 0);
+        assert_eq!(unsafe { desc.get([1, 2]) }, Some(&5.0));
+        assert_eq!(unsafe { desc.get([0, 1]) }, Some(&1.0));
+        assert!(desc.get_element_ptr([2, 0]).is_none());
+        assert!(StridedMemRefDescriptor::from_slice(&mut data, [4, 2]).is_err());
+
+        let mut scalar = [7i64];
+        let desc = StridedMemRefDescriptor::<i64, 0>::from_slice(&mut scalar, []).unwrap();
+        assert_eq!(unsafe { desc.get([]) }, Some(&7));
+        assert_eq!(
+            std::mem::size_of::<StridedMemRefDescriptor<i64, 0>>(),
+            3 * std::mem::size_of::<i64>()
+        );
+
+        // The transposed view of the 2x3 buffer.
+        let desc =
+            StridedMemRefDescriptor::from_slice_with_layout(&mut data, [3, 2], [1, 3], 0).unwrap();
+        assert_eq!(unsafe { desc.get([2, 1]) }, Some(&5.0));
+        assert!(
+            StridedMemRefDescriptor::from_slice_with_layout(&mut data, [3, 2], [1, 3], 1).is_err()
+        );
+    }
+
+    #[test]
+    fn unranked() {
+        let mut data = vec![1i32, 2, 3, 4];
+        let mut desc = StridedMemRefDescriptor::from_slice(&mut data, [4]).unwrap();
+        let mut unranked = desc.as_unranked();
+        assert_eq!(unranked.rank, 1);
+        unsafe {
+            assert!(unranked.as_ranked::<i32, 2>().is_none());
+            let ranked = unranked.as_ranked::<i32, 1>().unwrap();
+            assert_eq!(ranked.get([3]), Some(&4));
+        }
+    }
+
+    #[test]
+    fn from_memref_type() {
+        let ctx = Context::create();
+        let mut data: Vec<f32> = (0..12).map(|x| x as f32).collect();
+        let ty = Type::parse(&ctx, "memref<2x3xf32, strided<[6, 2], offset: 1>>").unwrap();
+        let memref_ty: MemRefType = ty.dyn_cast();
+        let desc =
+            StridedMemRefDescriptor::<f32, 2>::from_memref_type(memref_ty, &mut data).unwrap();
+        assert_eq!(desc.sizes, [2, 3]);
+        assert_eq!(desc.strides, [6, 2]);
+        assert_eq!(desc.offset, 1);
+        assert_eq!(unsafe { desc.get([1, 2]) }, Some(&11.0));
+        assert!(StridedMemRefDescriptor::<f32, 3>::from_memref_type(memref_ty, &mut data).is_err());
+        assert!(
+            StridedMemRefDescriptor::<f32, 2>::from_memref_type(memref_ty, &mut data[..8]).is_err()
+        );
+
+        let dynamic: MemRefType = Type::parse(&ctx, "memref<?x3xf32>").unwrap().dyn_cast();
+        assert!(StridedMemRefDescriptor::<f32, 2>::from_memref_type(dynamic, &mut data).is_err());
+    }
+
+    #[test]
+    fn jit() {
+        let ctx = create_context_with_all_upstream_dialects();
+        register_all_passes();
+        register_all_llvm_translations(&ctx);
+        let module = Module::create_parse(
+            &ctx,
+            r#"
+module {
+  func.func @scale(%m: memref<2x3xf32>, %f: f32) attributes { llvm.emit_c_interface } {
+    %c1 = arith.constant 1 : index
+    %c2 = arith.constant 2 : index
+    %0 = memref.load %m[%c1, %c2] : memref<2x3xf32>
+    %1 = arith.mulf %0, %f : f32
+    memref.store %1, %m[%c1, %c2] : memref<2x3xf32>
+    return
+  }
+}
+"#,
+        )
+        .unwrap();
+        let pm = PassManager::create(&ctx);
+        pm.parse_pass_pipeline(
+            "builtin.module(finalize-memref-to-llvm,convert-arith-to-llvm,convert-func-to-llvm,\
+             reconcile-unrealized-casts)",
+        )
+        .unwrap();
+        assert!(pm.run_on_module(module).is_success());
+        let engine = ExecutionEngine::create(module, 0, &[], false).unwrap();
+
+        let mut data: Vec<f32> = (0..6).map(|x| x as f32).collect();
+        let mut desc = StridedMemRefDescriptor::from_vec(&mut data, [2, 3]).unwrap();
+        unsafe { engine.invoke_void("scale", (&mut desc, 3.0f32)) }.unwrap();
+        assert_eq!(data, vec![0.0, 1.0, 2.0, 3.0, 4.0, 15.0]);
+    }
+}
@@ -54,8 +54,8 @@ impl PartialEq<TypeID> for TypeID {
 }
 impl Eq for TypeID {}
 
+/// Owns the TypeIDs it allocates, which become dangling once it is dropped.
 #[repr(C)]
-#[derive(Copy, Clone)]
 pub struct TypeIDAllocator {
     pub handle: MlirTypeIDAllocator,
 }
@@ -72,6 +72,26 @@ impl Into<MlirTypeIDAllocator> for &TypeIDAllocator {
     }
 }
 
+impl TypeIDAllocator {
+    pub fn create() -> Self {
+        unsafe { mlir_capi::Support::FFIVal_::mlirTypeIDAllocatorCreate() }
+    }
+    pub fn is_null(&self) -> bool {
+        self.handle.ptr == std::ptr::null_mut()
+    }
+    pub fn allocate_type_id(&self) -> TypeID {
+        unsafe { mlir_capi::Support::FFIVal_::mlirTypeIDAllocatorAllocateTypeID(self) }
+    }
+}
+
+impl Drop for TypeIDAllocator {
+    fn drop(&mut self) {
+        unsafe {
+            mlir_capi::Support::FFIVoid_::mlirTypeIDAllocatorDestroy(&*self);
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct StrRef<'a> {
@@ -7,6 +7,7 @@ use mlir_capi::IR;
 use mlir_capi::IR::*;
 
 use std::marker::PhantomData;
+use std::str::FromStr;
 
 #[repr(C)]
 pub struct SymbolTable<'op, 'ctx> {
@@ -91,3 +92,34 @@ impl<'op, 'ctx> Drop for SymbolTable<'op, 'ctx> {
         }
     }
 }
+
+/// The visibility of a symbol, stored in the `sym_visibility` attribute.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SymbolVisibility {
+    Public,
+    Private,
+    Nested,
+}
+
+impl SymbolVisibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SymbolVisibility::Public => "public",
+            SymbolVisibility::Private => "private",
+            SymbolVisibility::Nested => "nested",
+        }
+    }
+}
+
+impl FromStr for SymbolVisibility {
+    type Err = String;
+    /// Parses the value of a `sym_visibility` attribute, symbols without one are public.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(SymbolVisibility::Public),
+            "private" => Ok(SymbolVisibility::Private),
+            "nested" => Ok(SymbolVisibility::Nested),
+            _ => Err(format!("invalid symbol visibility '{}'", s)),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::diagnostic::*;
+use crate::module::*;
+
+use llvm_capi::Types::{LLVMContextRef, LLVMModuleRef};
+use llvm_capi::{BitWriter, Core};
+use mlir_capi::Target_::LLVMIR;
+use mlir_capi::IR::MlirOperation;
+
+use std::ffi::{CStr, CString};
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+
+/// An LLVM context, which owns the LLVM modules translated into it.
+pub struct LLVMContext {
+    pub handle: LLVMContextRef,
+}
+
+impl LLVMContext {
+    pub fn create() -> Self {
+        let handle = unsafe { Core::LLVMContextCreate() };
+        Self { handle }
+    }
+}
+
+impl Drop for LLVMContext {
+    fn drop(&mut self) {
+        unsafe {
+            Core::LLVMContextDispose(self.handle);
+        }
+    }
+}
+
+pub struct LLVMModule<'llvm> {
+    pub handle: LLVMModuleRef,
+    phantom: PhantomData<&'llvm LLVMContext>,
+}
+
+impl<'llvm> Drop for LLVMModule<'llvm> {
+    fn drop(&mut self) {
+        unsafe {
+            Core::LLVMDisposeModule(self.handle);
+        }
+    }
+}
+
+impl<'llvm> LLVMModule<'llvm> {
+    pub fn print_to_string(&self) -> String {
+        unsafe {
+            let message = Core::LLVMPrintModuleToString(self.handle);
+            let s = CStr::from_ptr(message).to_string_lossy().into_owned();
+            Core::LLVMDisposeMessage(message);
+            s
+        }
+    }
+    /// Writes the textual LLVM IR to `writer`.
+    pub fn write_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        writer.write_all(self.print_to_string().as_bytes())
+    }
+    pub fn write_bitcode(&self) -> Vec<u8> {
+        unsafe {
+            let buffer = BitWriter::LLVMWriteBitcodeToMemoryBuffer(self.handle);
+            let start = Core::LLVMGetBufferStart(buffer) as *const u8;
+            let size = Core::LLVMGetBufferSize(buffer);
+            let bitcode = std::slice::from_raw_parts(start, size).to_vec();
+            Core::LLVMDisposeMemoryBuffer(buffer);
+            bitcode
+        }
+    }
+    pub fn write_bitcode_to(&self, writer: &mut dyn std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&self.write_bitcode())
+    }
+    pub fn get_target_triple(&self) -> String {
+        unsafe {
+            CStr::from_ptr(Core::LLVMGetTarget(self.handle))
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+    pub fn set_target_triple(&self, triple: &str) {
+        let triple = CString::new(triple).unwrap();
+        unsafe {
+            Core::LLVMSetTarget(self.handle, triple.as_ptr());
+        }
+    }
+    pub fn get_data_layout(&self) -> String {
+        unsafe {
+            CStr::from_ptr(Core::LLVMGetDataLayoutStr(self.handle))
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+    pub fn set_data_layout(&self, data_layout: &str) {
+        let data_layout = CString::new(data_layout).unwrap();
+        unsafe {
+            Core::LLVMSetDataLayout(self.handle, data_layout.as_ptr());
+        }
+    }
+}
+
+impl<'llvm> Debug for LLVMModule<'llvm> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.print_to_string())
+    }
+}
+
+impl<'llvm> Display for LLVMModule<'llvm> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.print_to_string())
+    }
+}
+
+/// Translates `module`, which must only contain operations of dialects whose LLVM IR
+/// translations are registered (see `register_all_llvm_translations`), into an LLVM module
+/// owned by `llvm_ctx`.
+pub fn translate_module_to_llvm_ir<'ctx, 'llvm>(
+    module: Module<'ctx>,
+    llvm_ctx: &'llvm LLVMContext,
+) -> Result<LLVMModule<'llvm>, Diagnostics<'ctx>> {
+    let collector = ScopedDiagnosticCollector::new_for(&module);
+    let op: MlirOperation = module.get_operation_ref().into();
+    let handle: LLVMModuleRef =
+        unsafe { LLVMIR::FFIVal_::mlirTranslateModuleToLLVMIR(op, llvm_ctx.handle) };
+    if handle.is_null() {
+        Err(collector.into_diagnostics())
+    } else {
+        Ok(LLVMModule {
+            handle,
+            phantom: PhantomData::default(),
+        })
+    }
+}
+
+/// Translates `module` and returns the textual LLVM IR.
+pub fn translate_module_to_llvm_ir_string<'ctx>(
+    module: Module<'ctx>,
+) -> Result<String, Diagnostics<'ctx>> {
+    let llvm_ctx = LLVMContext::create();
+    let llvm_module = translate_module_to_llvm_ir(module, &llvm_ctx)?;
+    Ok(llvm_module.print_to_string())
+}
+
+#[cfg(test)]
+mod translation_test {
+    use super::*;
+    use crate::context::context_test::create_context_with_all_upstream_dialects;
+    use crate::dialect::*;
+
+    const LLVM_MODULE: &str = r#"
+module {
+  llvm.func @add(%a: i32, %b: i32) -> i32 {
+    %0 = llvm.add %a, %b : i32
+    llvm.return %0 : i32
+  }
+}
+"#;
+
+    #[test]
+    fn translate() {
+        let ctx = create_context_with_all_upstream_dialects();
+        register_all_llvm_translations(&ctx);
+        let module = Module::create_parse(&ctx, LLVM_MODULE).unwrap();
+        let ir = translate_module_to_llvm_ir_string(module).unwrap();
+        assert!(ir.contains("define i32 @add(i32 %0, i32 %1)"));
+        assert!(ir.contains("add i32 %0, %1"));
+
+        let llvm_ctx = LLVMContext::create();
+        let llvm_module = translate_module_to_llvm_ir(module, &llvm_ctx).unwrap();
+        llvm_module.set_target_triple("x86_64-unknown-linux-gnu");
+        assert_eq!(llvm_module.get_target_triple(), "x86_64-unknown-linux-gnu");
+        llvm_module.set_data_layout("e-m:e-i64:64-n32:64");
+        assert_eq!(llvm_module.get_data_layout(), "e-m:e-i64:64-n32:64");
+
+        let mut text = Vec::new();
+        llvm_module.write_to(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("target triple = \"x86_64-unknown-linux-gnu\""));
+        assert_eq!(text, llvm_module.to_string());
+
+        let bitcode = llvm_module.write_bitcode();
+        assert_eq!(&bitcode[..4], b"BC\xC0\xDE");
+    }
+
+    #[test]
+    fn translate_error() {
+        let ctx = create_context_with_all_upstream_dialects();
+        register_all_llvm_translations(&ctx);
+        let module = Module::create_parse(&ctx, "func.func @f() {\n  return\n}").unwrap();
+        let err = translate_module_to_llvm_ir_string(module).unwrap_err();
+        assert!(err.has_errors());
+    }
+}
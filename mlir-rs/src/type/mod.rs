@@ -2,6 +2,7 @@ use crate::affine_map::*;
 use crate::attribute::*;
 use crate::common::*;
 use crate::context::*;
+use crate::diagnostic::*;
 use crate::location::*;
 use crate::support::*;
 use crate::type_cast::*;
@@ -78,9 +79,15 @@ impl<'ctx> NullableRef for Type<'ctx> {
 }
 
 impl<'ctx> Type<'ctx> {
-    pub fn parse(ctx: &'ctx Context, s: &str) -> Self {
+    pub fn parse(ctx: &'ctx Context, s: &str) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new(ctx);
         let handle = unsafe { IR::FFIVal_::mlirTypeParseGet(ctx, StrRef::from(s)) };
-        unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) }
+        let ty = unsafe { Type::from_handle_and_phantom(handle, PhantomData::default()) };
+        if ty.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(ty)
+        }
     }
 }
 
@@ -217,9 +224,9 @@ mod integer_type_test {
             assert_eq!(un_ty_str, un_ty_str_1);
             assert_eq!(sn_ty_str, sn_ty_str_1);
 
-            let in_ty_1 = Type::parse(&ctx, &in_ty_str);
-            let un_ty_1 = Type::parse(&ctx, &un_ty_str);
-            let sn_ty_1 = Type::parse(&ctx, &sn_ty_str);
+            let in_ty_1 = Type::parse(&ctx, &in_ty_str).unwrap();
+            let un_ty_1 = Type::parse(&ctx, &un_ty_str).unwrap();
+            let sn_ty_1 = Type::parse(&ctx, &sn_ty_str).unwrap();
 
             assert_eq!(in_ty, in_ty_1);
             assert_eq!(un_ty, un_ty_1);
@@ -256,7 +263,7 @@ mod index_type_test {
         let str_2 = "index";
         assert_eq!(str_1, str_2);
 
-        let index_ty_1 = Type::parse(&ctx, &str_2);
+        let index_ty_1 = Type::parse(&ctx, &str_2).unwrap();
         assert_eq!(index_ty, index_ty_1);
     }
 }
@@ -427,16 +434,25 @@ impl<'ctx> VectorType<'ctx> {
             IsA::<Self>::cast(ty)
         }
     }
-    pub fn get_checked(loc: Location<'ctx>, shape: &[i64], elem_type: Type<'ctx>) -> Self {
-        unsafe {
-            let handle = BuiltinTypes::FFIVal_::mlirVectorTypeGetChecked(
+    pub fn get_checked(
+        loc: Location<'ctx>,
+        shape: &[i64],
+        elem_type: Type<'ctx>,
+    ) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new_for(&loc);
+        let handle = unsafe {
+            BuiltinTypes::FFIVal_::mlirVectorTypeGetChecked(
                 loc,
                 shape.len() as i64,
                 shape.as_ptr(),
                 elem_type,
-            );
-            let ty = Type::from_handle_same_context(handle, &elem_type);
-            IsA::<Self>::cast(ty)
+            )
+        };
+        let ty = Type::from_handle_same_context(handle, &elem_type);
+        if ty.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(unsafe { IsA::<Self>::cast(ty) })
         }
     }
     pub fn get_scalable(shape: &[i64], scalable: &[bool], elem_type: Type<'ctx>) -> Self {
@@ -497,7 +513,7 @@ mod vector_type_test {
             for (shape, scalable) in shapes.iter().zip(scalables.iter()) {
                 let vec_ty = VectorType::get(&shape, *elem);
                 assert!(!vec_ty.is_scalable());
-                assert_eq!(vec_ty, VectorType::get_checked(loc, &shape, *elem));
+                assert_eq!(vec_ty, VectorType::get_checked(loc, &shape, *elem).unwrap());
 
                 let sca_vec_ty = VectorType::get_scalable(&shape, &scalable, *elem);
                 assert!(IsA::<VectorType>::is_a(sca_vec_ty));
@@ -707,18 +723,23 @@ impl<'ctx> MemRefType<'ctx> {
         shape: &[i64],
         layout: Attr,
         mem_space: Attr,
-    ) -> Self {
-        unsafe {
-            let handle = BuiltinTypes::FFIVal_::mlirMemRefTypeGetChecked(
+    ) -> Result<Self, Diagnostics<'ctx>> {
+        let collector = ScopedDiagnosticCollector::new_for(&loc);
+        let handle = unsafe {
+            BuiltinTypes::FFIVal_::mlirMemRefTypeGetChecked(
                 loc,
                 elem_type,
                 shape.len() as i64,
                 shape.as_ptr(),
                 layout,
                 mem_space,
-            );
-            let ty = Type::from_handle_same_context(handle, &elem_type);
-            IsA::<Self>::cast(ty)
+            )
+        };
+        let ty = Type::from_handle_same_context(handle, &elem_type);
+        if ty.is_null() {
+            Err(collector.into_diagnostics())
+        } else {
+            Ok(unsafe { IsA::<Self>::cast(ty) })
         }
     }
     pub fn contiguous_get(elem_type: Type<'ctx>, shape: &[i64], mem_space: Attr) -> Self {
@@ -863,6 +884,7 @@ mod memref_type_test {
                         assert_eq!(
                             memref_ty,
                             MemRefType::get_checked(loc, *elem, *shape, *layout, *mem_space)
+                                .unwrap()
                         );
                         assert_eq!(*elem, memref_ty.get_element_type());
                         assert!(memref_ty.has_rank());
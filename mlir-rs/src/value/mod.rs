@@ -91,6 +91,11 @@ impl<'ctx> Value<'ctx> {
     pub fn get_first_use(self) -> OpOperand<'ctx> {
         unsafe { mlir_capi::IR::FFIVal_::mlirValueGetFirstUse(self) }
     }
+    pub fn uses(self) -> UseIter<'ctx> {
+        UseIter {
+            next: self.get_first_use(),
+        }
+    }
     pub fn replace_all_use_with(self, other: Value<'ctx>) {
         unsafe {
             mlir_capi::IR::FFIVoid_::mlirValueReplaceAllUsesOfWith(self, other);
@@ -105,6 +110,51 @@ impl<'ctx> PartialEq for Value<'ctx> {
 }
 impl<'ctx> Eq for Value<'ctx> {}
 
+/// Either a constant index or an SSA value of index type, used for the mixed static/dynamic
+/// offsets, sizes and strides of ops such as `memref.subview` and `tensor.extract_slice`.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum OpFoldResult<'ctx> {
+    Static(i64),
+    Dynamic(Value<'ctx>),
+}
+
+impl<'ctx> From<i64> for OpFoldResult<'ctx> {
+    fn from(value: i64) -> Self {
+        OpFoldResult::Static(value)
+    }
+}
+
+impl<'ctx> From<Value<'ctx>> for OpFoldResult<'ctx> {
+    fn from(value: Value<'ctx>) -> Self {
+        OpFoldResult::Dynamic(value)
+    }
+}
+
+impl<'ctx> OpFoldResult<'ctx> {
+    pub fn get_static(self) -> Option<i64> {
+        match self {
+            OpFoldResult::Static(value) => Some(value),
+            OpFoldResult::Dynamic(_) => None,
+        }
+    }
+    /// Splits `values` into the dynamic values and the static values, where each dynamic
+    /// value is represented by `dynamic_marker` in the latter.
+    pub fn dispatch(values: &[Self], dynamic_marker: i64) -> (Vec<Value<'ctx>>, Vec<i64>) {
+        let mut dynamic_values = Vec::new();
+        let static_values = values
+            .iter()
+            .map(|value| match value {
+                OpFoldResult::Static(value) => *value,
+                OpFoldResult::Dynamic(value) => {
+                    dynamic_values.push(*value);
+                    dynamic_marker
+                }
+            })
+            .collect();
+        (dynamic_values, static_values)
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct OpOperand<'ctx> {
@@ -158,3 +208,20 @@ impl<'ctx> OpOperand<'ctx> {
         unsafe { mlir_capi::IR::FFIVal_::mlirOpOperandGetNextUse(self) }
     }
 }
+
+pub struct UseIter<'ctx> {
+    next: OpOperand<'ctx>,
+}
+
+impl<'ctx> Iterator for UseIter<'ctx> {
+    type Item = OpOperand<'ctx>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next.is_null() {
+            None
+        } else {
+            let operand = self.next;
+            self.next = operand.get_next_use();
+            Some(operand)
+        }
+    }
+}